@@ -1,3 +1,5 @@
+use std::collections::{HashMap, HashSet};
+
 use indexmap::IndexMap;
 use ratatui::style::Color;
 use rltk::Point;
@@ -20,6 +22,33 @@ pub enum EquipmentSlot {
     Legs,
     Feet,
     Ring,
+    Quiver,
+}
+
+impl EquipmentSlot {
+    /// Every slot, in the order the equipment screen lists them.
+    pub const ALL: [EquipmentSlot; 9] = [
+        EquipmentSlot::Weapon,
+        EquipmentSlot::Shield,
+        EquipmentSlot::Head,
+        EquipmentSlot::Chest,
+        EquipmentSlot::Hands,
+        EquipmentSlot::Legs,
+        EquipmentSlot::Feet,
+        EquipmentSlot::Ring,
+        EquipmentSlot::Quiver,
+    ];
+}
+
+/**
+ * The kind of ammunition a ranged weapon fires, which must match
+ * the ammunition sitting in the quiver.
+ */
+#[derive(PartialEq, Copy, Clone, Debug, Deserialize)]
+pub enum AmmoType {
+    Arrow,
+    Bolt,
+    Stone,
 }
 
 #[derive(PartialEq, Copy, Clone, Debug, Deserialize)]
@@ -30,6 +59,26 @@ pub enum DamageType {
     Fire,
 }
 
+/**
+ * The shape of an area of effect, sized by `Area::size`.
+ *
+ * Ball: every tile within `size` of the aim point.
+ * Line: a straight line from the caster towards the aim point, `size` tiles long.
+ * Cone: a 90 degree wedge from the caster towards the aim point, `size` tiles deep.
+ */
+#[derive(PartialEq, Copy, Clone, Debug, Deserialize)]
+pub enum AreaShape {
+    Ball,
+    Line,
+    Cone,
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Area {
+    pub shape: AreaShape,
+    pub size: i32,
+}
+
 #[derive(PartialEq)]
 pub enum AttackType {
     Melee,
@@ -37,10 +86,79 @@ pub enum AttackType {
     Magic,
 }
 
-#[derive(Debug, Deserialize, Copy, Clone)]
-pub enum PotionType {
-    Health,
-    Mana
+/**
+ * How aware a monster currently is of the player.
+ *
+ * Sleeping monsters must first be woken by noise, after which they remain
+ * unaware until they either spot the player or hear something else nearby.
+ */
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum AlertState {
+    Asleep,
+    Unaware,
+    Alert,
+}
+
+/**
+ * Feats that can be chosen at level up, see `feats.yaml`.
+ *
+ * Most feats are passive and are consulted directly by the relevant system,
+ * e.g. `PowerAttack` by melee combat or `Haggler` by trading.
+ */
+#[derive(PartialEq, Copy, Clone, Debug, Deserialize)]
+pub enum FeatType {
+    PowerAttack,
+    Toughness,
+    ExtraSpellSlot,
+    TrapSense,
+    KeenEyes,
+    Haggler,
+    TwoWeaponFighting,
+}
+
+#[derive(PartialEq, Copy, Clone, Debug, Deserialize)]
+pub enum StatusType {
+    Poisoned,
+    Regenerating,
+}
+
+/**
+ * A single primitive effect that fires when a consumable is used.
+ *
+ * Consumables carry a list of these, which are pushed onto the effect queue
+ * in order and applied to the user by `process_effects`.
+ */
+#[derive(Clone, Debug)]
+pub enum ItemEffect {
+    Heal { amount: i32 },
+    RestoreMana { amount: i32 },
+    Damage { damage: DiceExpression, damage_type: DamageType, area: Area },
+    Teleport,
+    ApplyStatus { status: StatusType, duration: i32 },
+    RevealMap,
+    LearnSpell { spell: Spell },
+    Summon { name: String, count: i32 },
+    Identify,
+    RemoveCurse,
+}
+
+impl ItemEffect {
+    /// Whether the effect does anything to a creature it is splashed on, e.g. by a shattering potion.
+    /// Effects that act for their user, like summoning or identifying, only happen when used.
+    pub fn affects_target(&self) -> bool {
+        return matches!(
+            self,
+            ItemEffect::Heal { .. }
+                | ItemEffect::RestoreMana { .. }
+                | ItemEffect::Damage { .. }
+                | ItemEffect::ApplyStatus { .. }
+        );
+    }
+
+    /// Whether the effect acts on an item the user picks from their inventory, e.g. identifying it.
+    pub fn acts_on_item(&self) -> bool {
+        return matches!(self, ItemEffect::Identify);
+    }
 }
 
 /**
@@ -96,7 +214,7 @@ pub struct Pool {
     pub max: i32,
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Stats {
     pub hp: Pool,
     pub mp: Pool,
@@ -111,28 +229,211 @@ pub struct Stats {
     pub charisma: i32,
 }
 
+/// Identifies items that are interchangeable with one another.
+/// Only items with equal signatures share a stack in an `Inventory`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ItemSignature {
+    pub name: String,
+    pub rarity: Option<RarityTier>,
+    pub known_curse: bool,
+    pub durability: Option<i32>,
+}
+
 #[derive(Component)]
 pub struct Inventory {
     pub gold: i32,
-    pub items: IndexMap<String, Vec<Entity>>,
+    pub items: IndexMap<ItemSignature, Vec<Entity>>,
     pub index: usize,
+    /// Signatures of the stacks marked for a bulk action, e.g. dropping several stacks at once.
+    pub selected: HashSet<ItemSignature>,
+    /// How the inventory screen narrows down and orders the stacks, see `get_inventory_view`.
+    pub view: InventoryView,
+}
+
+/// The tab, sort order and search text chosen on the inventory screen.
+/// `Inventory.index` points into the stacks that this view lets through.
+#[derive(Debug, Clone, Default)]
+pub struct InventoryView {
+    pub tab: InventoryTab,
+    pub sort: InventorySort,
+    pub filter: String,
+    /// Whether key presses are currently being typed into `filter`.
+    pub filtering: bool,
+}
+
+#[derive(PartialEq, Copy, Clone, Debug, Default)]
+pub enum InventoryTab {
+    #[default]
+    All,
+    Weapons,
+    Armor,
+    Consumables,
+    Spellbooks,
+    Junk,
+}
+
+impl InventoryTab {
+    pub const ALL: [InventoryTab; 6] = [
+        InventoryTab::All,
+        InventoryTab::Weapons,
+        InventoryTab::Armor,
+        InventoryTab::Consumables,
+        InventoryTab::Spellbooks,
+        InventoryTab::Junk,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InventoryTab::All => "All",
+            InventoryTab::Weapons => "Weapons",
+            InventoryTab::Armor => "Armor",
+            InventoryTab::Consumables => "Consumables",
+            InventoryTab::Spellbooks => "Spellbooks",
+            InventoryTab::Junk => "Junk",
+        }
+    }
+
+    /// Steps through the tabs in order, wrapping around at either end.
+    pub fn cycle(&self, forward: bool) -> InventoryTab {
+        let index = InventoryTab::ALL.iter().position(|tab| tab == self).unwrap_or(0);
+        let count = InventoryTab::ALL.len();
+        return match forward {
+            true => InventoryTab::ALL[(index + 1) % count],
+            false => InventoryTab::ALL[(index + count - 1) % count],
+        };
+    }
+}
+
+#[derive(PartialEq, Copy, Clone, Debug, Default)]
+pub enum InventorySort {
+    /// The order the items were picked up in.
+    #[default]
+    Acquired,
+    Name,
+    Value,
+    Weight,
+    Slot,
+}
+
+impl InventorySort {
+    pub fn label(&self) -> &'static str {
+        match self {
+            InventorySort::Acquired => "acquired",
+            InventorySort::Name => "name",
+            InventorySort::Value => "value",
+            InventorySort::Weight => "weight",
+            InventorySort::Slot => "slot",
+        }
+    }
+
+    pub fn next(&self) -> InventorySort {
+        match self {
+            InventorySort::Acquired => InventorySort::Name,
+            InventorySort::Name => InventorySort::Value,
+            InventorySort::Value => InventorySort::Weight,
+            InventorySort::Weight => InventorySort::Slot,
+            InventorySort::Slot => InventorySort::Acquired,
+        }
+    }
+}
+
+impl Inventory {
+    /// Adds an item to the stack matching its signature, starting a new stack if there is none.
+    pub fn add_item(&mut self, item: Entity, signature: ItemSignature) {
+        self.items.entry(signature).or_insert(vec![]).push(item);
+    }
+
+    /// Takes a single item out of whichever stack holds it,
+    /// dropping the stack entirely once it is empty.
+    pub fn remove_item(&mut self, item: Entity) {
+        let Some(stack_index) = self.items.values().position(|stack| stack.contains(&item)) else {
+            return;
+        };
+        let (signature, stack) = self.items.get_index_mut(stack_index).expect("Unable to access item stack");
+        stack.retain(|entity| *entity != item);
+        if stack.is_empty() {
+            let signature = signature.clone();
+            self.items.shift_remove(&signature);
+            self.selected.remove(&signature);
+            self.index = usize::min(self.index, self.items.len().saturating_sub(1));
+        }
+    }
+
+    /// Returns the stack holding the given item, if any.
+    pub fn stack_of(&self, item: Entity) -> Option<&Vec<Entity>> {
+        self.items.values().find(|stack| stack.contains(&item))
+    }
+
+    /// Regroups any items whose signature has changed since they were stacked,
+    /// e.g. when a curse is discovered.
+    pub fn restack(&mut self, signature_of: impl Fn(Entity) -> ItemSignature) {
+        let mut restacked: IndexMap<ItemSignature, Vec<Entity>> = IndexMap::new();
+        for item in self.items.values().flatten() {
+            restacked.entry(signature_of(*item)).or_insert(vec![]).push(*item);
+        }
+        if restacked.iter().eq(self.items.iter()) {
+            return;
+        }
+        self.selected.retain(|signature| restacked.contains_key(signature));
+        self.index = usize::min(self.index, restacked.len().saturating_sub(1));
+        self.items = restacked;
+    }
 }
 
 #[derive(Component, Debug)]
 pub struct Item {
     pub description: String,
     pub base_value: i32,
+    pub weight: f32,
+}
+
+/// Coins that go straight into the collector's purse rather than their backpack.
+/// The amount is rolled when the coins are picked up.
+#[derive(Component, Debug)]
+pub struct Gold {
+    pub amount: DiceExpression,
 }
 
 #[derive(Component, Debug)]
-pub struct Potion {
-    pub potion_type: PotionType,
-    pub restore_amount: i32,
+pub struct Consumable {
+    pub effects: Vec<ItemEffect>,
+}
+
+/// Items that hurt when thrown at something, e.g. a dagger.
+#[derive(Component, Debug)]
+pub struct Throwable {
+    pub damage: DiceExpression,
+    pub damage_type: DamageType,
 }
 
+/// Items that shatter when thrown, splashing their effects over everything within `radius`.
+#[derive(Component, Debug)]
+pub struct Fragile {
+    pub radius: i32,
+}
+
+/**
+ * An item that can be worn or wielded in the given slot.
+ *
+ * Two-handed weapons (`hands: 2`) take up the shield slot as well.
+ * Light one-handed weapons can go in the shield slot instead, as an off hand weapon.
+ */
 #[derive(Component, Debug)]
 pub struct Equippable {
-    pub slot: EquipmentSlot
+    pub slot: EquipmentSlot,
+    pub hands: i32,
+    pub light: bool,
+}
+
+impl Equippable {
+    pub fn is_two_handed(&self) -> bool {
+        return self.slot == EquipmentSlot::Weapon && self.hands >= 2;
+    }
+
+    /// Whether the item can be wielded in the shield slot for an off hand attack.
+    pub fn fits_off_hand(&self) -> bool {
+        return self.slot == EquipmentSlot::Weapon && self.hands == 1 && self.light;
+    }
 }
 
 #[derive(Component, Debug)]
@@ -152,9 +453,27 @@ pub struct RangedWeapon {
     pub damage: DiceExpression,
     pub damage_type: DamageType,
     pub range: i32,
+    pub ammo: Option<AmmoType>,
     pub target: Option<Entity>,
 }
 
+/**
+ * A single piece of ammunition, consumed from the quiver on every ranged attack.
+ *
+ * Special ammunition can add damage, change the damage type, or inflict a
+ * status on whatever it hits. Spent ammunition has a `recovery_chance` percent
+ * chance of landing intact near the target so it can be picked back up.
+ */
+#[derive(Component, Debug, Clone)]
+pub struct Ammunition {
+    pub ammo_type: AmmoType,
+    pub damage_bonus: i32,
+    pub damage_type: Option<DamageType>,
+    pub status: Option<StatusType>,
+    pub status_duration: i32,
+    pub recovery_chance: i32,
+}
+
 #[derive(Component, Debug)]
 pub struct MagicWeapon {
     pub range: i32,
@@ -164,6 +483,122 @@ pub struct MagicWeapon {
 #[derive(Component, Debug)]
 pub struct Armor {
     pub defense: i32,
+    pub noise: i32,
+}
+
+/**
+ * Bonuses granted by an equippable item for as long as it is `Equipped`.
+ *
+ * Attribute, max hp and viewshed bonuses are added to the wearer's `DerivedStats`,
+ * the rest are read from `DerivedStats::equipment` by whichever system needs them.
+ */
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub struct Modifiers {
+    pub strength: i32,
+    pub dexterity: i32,
+    pub constitution: i32,
+    pub intelligence: i32,
+    pub wisdom: i32,
+    pub charisma: i32,
+    pub max_hp: i32,
+    pub viewshed_range: i32,
+    pub mp_regeneration: i32,
+    pub speed: i32,
+    pub to_hit: i32,
+    pub resistances: Vec<DamageType>,
+}
+
+impl Modifiers {
+    /// Adds another set of modifiers onto this one, e.g. when totalling up equipment.
+    pub fn combine(&mut self, other: &Modifiers) {
+        self.strength += other.strength;
+        self.dexterity += other.dexterity;
+        self.constitution += other.constitution;
+        self.intelligence += other.intelligence;
+        self.wisdom += other.wisdom;
+        self.charisma += other.charisma;
+        self.max_hp += other.max_hp;
+        self.viewshed_range += other.viewshed_range;
+        self.mp_regeneration += other.mp_regeneration;
+        self.speed += other.speed;
+        self.to_hit += other.to_hit;
+        for resistance in other.resistances.iter() {
+            if !self.resistances.contains(resistance) {
+                self.resistances.push(*resistance);
+            }
+        }
+    }
+
+    pub fn resists(&self, damage_type: DamageType) -> bool {
+        self.resistances.contains(&damage_type)
+    }
+}
+
+/**
+ * How rare a generated item is, which decides how many affixes it was rolled with.
+ *
+ * Common: no affixes, i.e. a plain item straight from `items.yaml`.
+ * Magic: a single prefix or suffix.
+ * Rare: both a prefix and a suffix.
+ * Unique: one of the hand-made items from `affixes.yaml`.
+ */
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub enum RarityTier {
+    Common,
+    Magic,
+    Rare,
+    Unique,
+}
+
+impl RarityTier {
+    /// The color the item's name is shown in throughout the inventory screens.
+    pub fn color(&self) -> Color {
+        match self {
+            RarityTier::Common => Color::Cyan,
+            RarityTier::Magic => Color::LightBlue,
+            RarityTier::Rare => Color::Yellow,
+            RarityTier::Unique => Color::Rgb(255, 140, 0),
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Rarity {
+    pub tier: RarityTier,
+}
+
+/// Equipment that refuses to leave its slot once equipped, until the curse is lifted.
+/// The curse stays hidden (`known: false`) until the item is first equipped or identified.
+#[derive(Component, Debug)]
+pub struct Cursed {
+    pub known: bool,
+}
+
+/// Wear and tear on a weapon or piece of armor, worn down by the blows it deals or takes.
+/// A broken item (`current` of zero) stays equipped but gives none of its bonuses until repaired.
+#[derive(Component, Debug, Clone)]
+pub struct Durability {
+    pub current: i32,
+    pub max: i32,
+}
+
+impl Durability {
+    pub fn is_broken(&self) -> bool {
+        return self.current <= 0;
+    }
+
+    /// Wears the item down by the given amount, returning whether this is what broke it.
+    pub fn wear(&mut self, amount: i32) -> bool {
+        let was_broken = self.is_broken();
+        self.current = i32::max(0, self.current - amount);
+        return !was_broken && self.is_broken();
+    }
+}
+
+/// A shrine that lifts the curses from whoever prays at it, good for a single use.
+#[derive(Component, Debug)]
+pub struct Shrine {
+    pub used: bool,
 }
 
 #[derive(Component, Debug, Clone)]
@@ -172,36 +607,166 @@ pub struct Spell {
     pub mp_cost: i32,
     pub damage: DiceExpression,
     pub damage_type: DamageType,
+    pub area: Option<Area>,
 }
 
-#[derive(Component, Debug)]
+/// Number keys that can be bound to spells in the spellbook.
+/// `1` and `2` are reserved for ranged and magic targeting.
+pub const SPELL_HOTKEYS: [char; 7] = ['3', '4', '5', '6', '7', '8', '9'];
+
+#[derive(Component, Debug, Default)]
 pub struct SpellKnowledge {
     pub spells: Vec<Spell>,
+    pub active: usize,
+    pub hotkeys: HashMap<char, usize>,
+}
+
+impl SpellKnowledge {
+    /// The spell that will be cast when the player casts a spell.
+    pub fn active_spell(&self) -> Option<&Spell> {
+        self.spells.get(self.active)
+    }
+
+    /// Returns the hotkey bound to the spell at the given index, if any.
+    pub fn hotkey_for(&self, index: usize) -> Option<char> {
+        SPELL_HOTKEYS
+            .iter()
+            .find(|key| self.hotkeys.get(key) == Some(&index))
+            .copied()
+    }
+
+    /// Binds the given hotkey to the spell at the given index,
+    /// replacing any other spell bound to that key. Binding a spell to the
+    /// key it already has toggles the binding off.
+    pub fn bind_hotkey(&mut self, key: char, index: usize) {
+        if self.hotkeys.get(&key) == Some(&index) {
+            self.hotkeys.remove(&key);
+            return;
+        }
+        self.hotkeys.retain(|_, bound| *bound != index);
+        self.hotkeys.insert(key, index);
+    }
 }
 
-#[derive(Component, Debug)]
-pub struct MagicMapper {}
 
 #[derive(Component, Debug)]
 pub struct InBackpack {
     pub owner: Entity,
 }
 
+/// Picks up the given items, `auto` is set when the items were collected
+/// by an auto-pickup rule rather than by the player asking for them.
 #[derive(Component, Debug)]
 pub struct WantsToPickupItem {
     pub collected_by: Entity,
     pub items: Vec<Entity>,
+    pub auto: bool,
+}
+
+#[derive(Component, Debug)]
+pub struct WantsToDropItem {
+    pub items: Vec<Entity>,
+}
+
+#[derive(Component, Debug)]
+pub struct WantsToDestroyItem {
+    pub items: Vec<Entity>,
 }
 
 #[derive(Component, Debug)]
 pub struct WantsToConsumeItem {
     pub item: Entity,
+    /// For equipment, the slot to put the item in if not its own, e.g. an off hand weapon.
+    pub slot: Option<EquipmentSlot>,
+    /// For consumables that act on another item, e.g. a scroll of identify, the item picked for it.
+    pub target: Option<Entity>,
+}
+
+/// Marks an item as readied for throwing while the player picks a target tile.
+#[derive(Component, Debug)]
+pub struct Throwing {
+    pub item: Entity,
+}
+
+/// Marks a consumable, e.g. a scroll of identify, as waiting for the player
+/// to pick the item in their inventory that it should act on.
+#[derive(Component, Debug)]
+pub struct PickingItem {
+    pub item: Entity,
+}
+
+#[derive(Component, Debug)]
+pub struct WantsToThrowItem {
+    pub item: Entity,
+    pub target: Point,
+}
+
+/**
+ * Secondary stats derived from an entity's attributes, equipment, statuses and feats.
+ *
+ * These are recalculated continuously by the `DerivedStatsSystem`, so anything
+ * that cares about e.g. armor class should read it from here rather than
+ * recombining the raw attributes itself.
+ *
+ * `Stats` only ever hold the base attributes, the effective ones (including
+ * whatever the entity has equipped) live here alongside everything derived from them.
+ */
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub struct DerivedStats {
+    pub strength: i32,
+    pub dexterity: i32,
+    pub constitution: i32,
+    pub intelligence: i32,
+    pub wisdom: i32,
+    pub charisma: i32,
+    pub max_hp: i32,
+    /// Viewshed range including equipment, used by the `VisibilitySystem`.
+    pub sight_range: i32,
+    pub armor_class: i32,
+    pub melee_to_hit: i32,
+    pub ranged_to_hit: i32,
+    pub spell_to_hit: i32,
+    pub melee_damage: i32,
+    pub ranged_damage: i32,
+    pub spell_power: i32,
+    pub perception: i32,
+    pub price_modifier: i32,
+    pub carry_capacity: i32,
+    /// Total weight of everything in the entity's backpack.
+    pub load: f32,
+    pub encumbrance: Encumbrance,
+    /// Speed from equipment, less any encumbrance penalty, see `App::gains_extra_turn`.
+    pub speed: i32,
+
+    /// The combined `Modifiers` of everything the entity has equipped.
+    pub equipment: Modifiers,
+}
+
+/// How badly an entity is weighed down by what it carries, relative to its carry capacity.
+#[derive(PartialEq, Copy, Clone, Debug, Default)]
+pub enum Encumbrance {
+    #[default]
+    Unburdened,
+    Burdened,
+    Overloaded,
+}
+
+impl Encumbrance {
+    /// The color the player's load is shown in on the side panel and inventory screen.
+    pub fn color(&self) -> Color {
+        match self {
+            Encumbrance::Unburdened => Color::Gray,
+            Encumbrance::Burdened => Color::Yellow,
+            Encumbrance::Overloaded => Color::Red,
+        }
+    }
 }
 
 #[derive(Component)]
 pub struct Attack {
     pub attack_type: AttackType,
-    pub target: Entity,
+    pub target: Option<Entity>,
+    pub aim: Option<Point>,
     pub spell: Option<Spell>,
 }
 
@@ -244,6 +809,30 @@ impl Experience {
     }
 }
 
+#[derive(Component, Debug)]
+pub struct Alertness {
+    pub state: AlertState,
+}
+
+/**
+ * Noise emitted by an entity this turn, e.g. footsteps or the clash of combat.
+ * Each volume is the radius (in tiles) at which the noise can still be heard.
+ */
+#[derive(Component, Debug)]
+pub struct Noise {
+    pub volume: Vec<i32>,
+}
+
+impl Noise {
+    pub fn new(store: &mut WriteStorage<Noise>, source: Entity, volume: i32) {
+        if let Some(noise) = store.get_mut(source) {
+            noise.volume.push(volume);
+        } else {
+            store.insert(source, Noise { volume: vec![volume] }).expect("Unable to make noise");
+        }
+    }
+}
+
 #[derive(Component, Debug)]
 pub struct Lifetime {
     pub created_at: u128,
@@ -265,7 +854,126 @@ pub struct Npc {
     pub dialogue: Option<Vec<String>>,
 }
 
+/// A merchant's wares. `items` are for sale, while `buyback` holds whatever the player
+/// has sold them since they last restocked, oldest first.
 #[derive(Component, Debug)]
 pub struct Vendor {
     pub items: Vec<Entity>,
+    pub buyback: Vec<Entity>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Status {
+    pub status_type: StatusType,
+    pub duration: i32,
+}
+
+/// The feats an entity has chosen so far.
+#[derive(Component, Debug, Default)]
+pub struct Feats {
+    pub feats: Vec<FeatType>,
+}
+
+impl Feats {
+    pub fn has(&self, feat: FeatType) -> bool {
+        self.feats.contains(&feat)
+    }
+}
+
+/// Any lingering statuses (e.g. poison) currently affecting an entity.
+#[derive(Component, Debug)]
+pub struct Statuses {
+    pub statuses: Vec<Status>,
+}
+
+impl Statuses {
+    /// Applies a status to the target, refreshing the duration if it is already active.
+    pub fn apply(store: &mut WriteStorage<Statuses>, target: Entity, status_type: StatusType, duration: i32) {
+        if let Some(statuses) = store.get_mut(target) {
+            match statuses.statuses.iter_mut().find(|s| s.status_type == status_type) {
+                Some(status) => status.duration = i32::max(status.duration, duration),
+                None => statuses.statuses.push(Status { status_type, duration }),
+            }
+        } else {
+            let statuses = Statuses {
+                statuses: vec![Status { status_type, duration }],
+            };
+            store.insert(target, statuses).expect("Unable to insert statuses");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signature(name: &str) -> ItemSignature {
+        ItemSignature { name: name.to_string(), rarity: None, known_curse: false, durability: None }
+    }
+
+    fn empty_inventory() -> Inventory {
+        Inventory {
+            gold: 0,
+            items: IndexMap::new(),
+            index: 0,
+            selected: HashSet::new(),
+            view: InventoryView::default(),
+        }
+    }
+
+    fn items(count: usize) -> Vec<Entity> {
+        let mut world = World::new();
+        (0..count).map(|_| world.create_entity().build()).collect()
+    }
+
+    #[test]
+    fn same_signature_merges() {
+        let items = items(2);
+        let mut inventory = empty_inventory();
+        inventory.add_item(items[0], signature("Dagger"));
+        inventory.add_item(items[1], signature("Dagger"));
+        assert_eq!(inventory.items.len(), 1);
+        assert_eq!(inventory.stack_of(items[1]), Some(&vec![items[0], items[1]]));
+    }
+
+    #[test]
+    fn different_durability_or_known_curse_splits() {
+        let items = items(3);
+        let mut inventory = empty_inventory();
+        inventory.add_item(items[0], ItemSignature { durability: Some(30), ..signature("Dagger") });
+        inventory.add_item(items[1], ItemSignature { durability: Some(12), ..signature("Dagger") });
+        inventory.add_item(items[2], ItemSignature { durability: Some(30), known_curse: true, ..signature("Dagger") });
+        assert_eq!(inventory.items.len(), 3);
+        assert_eq!(inventory.stack_of(items[1]), Some(&vec![items[1]]));
+    }
+
+    #[test]
+    fn removing_last_item_drops_stack() {
+        let items = items(2);
+        let mut inventory = empty_inventory();
+        inventory.add_item(items[0], signature("Dagger"));
+        inventory.add_item(items[1], signature("Arrow"));
+        inventory.selected.insert(signature("Dagger"));
+        inventory.index = 1;
+
+        inventory.remove_item(items[1]);
+        assert!(!inventory.items.contains_key(&signature("Arrow")));
+        assert_eq!(inventory.index, 0);
+
+        inventory.remove_item(items[0]);
+        assert!(inventory.items.is_empty());
+        assert!(inventory.selected.is_empty());
+        assert_eq!(inventory.stack_of(items[0]), None);
+    }
+
+    #[test]
+    fn restack_regroups_changed_signatures() {
+        let items = items(2);
+        let mut inventory = empty_inventory();
+        inventory.add_item(items[0], signature("Dagger"));
+        inventory.add_item(items[1], signature("Dagger"));
+        inventory.restack(|item| ItemSignature { known_curse: item == items[1], ..signature("Dagger") });
+        assert_eq!(inventory.items.len(), 2);
+        assert_eq!(inventory.stack_of(items[0]), Some(&vec![items[0]]));
+    }
 }
@@ -3,7 +3,11 @@ use std::{collections::VecDeque, sync::Mutex};
 use lazy_static::lazy_static;
 use specs::prelude::*;
 
-use crate::{App, RunState, Screen};
+use crate::{
+    App, RunState, Screen,
+    component::ItemEffect,
+    effect::item_effect::{apply_item_effect, identify_by_use},
+};
 
 lazy_static! {
     pub static ref EFFECT_QUEUE: Mutex<VecDeque<Effect>> = Mutex::new(VecDeque::new());
@@ -11,6 +15,8 @@ lazy_static! {
 
 pub enum EffectType {
     LevelUp { _level: i32 },
+    ItemUse { effect: ItemEffect, user: Entity, target: Option<Entity> },
+    IdentifyByUse { item_name: String },
 }
 
 pub struct Effect {
@@ -40,6 +46,25 @@ pub fn process_effects(app: &mut App) {
                         app.screen = Screen::Inventory;
                         app.runstate = RunState::LevelUp { index: 0 };
                     }
+
+                    /*
+                     * __Item Use__
+                     * Consumables queue one of these per effect listed in their config.
+                     * Effects can teleport, summon and otherwise reach well outside of
+                     * any single system's data, so they are applied here with full ecs access.
+                     */
+                    EffectType::ItemUse { effect, user, target } => {
+                        apply_item_effect(&mut app.ecs, &effect, user, target);
+                    }
+
+                    /*
+                     * __Identify By Use__
+                     * Queued after a potion or scroll's effects, so that the player
+                     * sees what happened before learning what the item actually was.
+                     */
+                    EffectType::IdentifyByUse { item_name } => {
+                        identify_by_use(&app.ecs, &item_name);
+                    }
                 }
             },
             None => break,
@@ -0,0 +1,374 @@
+use ratatui::style::Color;
+use rltk::{Point, RandomNumberGenerator};
+use specs::prelude::*;
+
+use crate::{
+    component::{
+        AlertState, Alertness, Area, AreaShape, Cursed, Damage, DamageType, DerivedStats,
+        InBackpack, ItemEffect, Lifetime, Monster, Name, Position, Renderable, SpellKnowledge,
+        Stats, StatusType, Statuses, Viewshed,
+    },
+    generate::{
+        config::DiceExpression,
+        identification::identify_item_type,
+        map::{Map, TileType},
+        spawn::spawn_named_monster,
+    },
+    logbook::logbook::Logger,
+    system::{
+        derived_stats_system::{effective_max_hp, resist_damage},
+        inventory_system::lift_curses,
+        particle_system::{ParticleStorages, spawn_explosion},
+        ranged_combat_system::get_area_tiles,
+    },
+};
+
+/// Applies a single consumable effect on behalf of the entity that used the item.
+/// Effects that act on an item, e.g. identifying it, are given the item the user picked.
+///
+/// Each primitive is self contained, so new consumables can be built purely
+/// by combining these in `items.yaml`.
+pub fn apply_item_effect(ecs: &mut World, effect: &ItemEffect, user: Entity, target: Option<Entity>) {
+    let is_player = user == *ecs.fetch::<Entity>();
+    match effect {
+        ItemEffect::Heal { amount } => {
+            let mut stats = ecs.write_storage::<Stats>();
+            let derived_stats = ecs.read_storage::<DerivedStats>();
+            if let Some(stat) = stats.get_mut(user) {
+                stat.hp.current = i32::min(effective_max_hp(stat, derived_stats.get(user)), stat.hp.current + amount);
+                if is_player {
+                    Logger::new()
+                        .append("You feel better, restoring ")
+                        .append_with_color(Color::Green, format!("{} hp.", amount))
+                        .log();
+                }
+            }
+        }
+
+        ItemEffect::RestoreMana { amount } => {
+            let mut stats = ecs.write_storage::<Stats>();
+            if let Some(stat) = stats.get_mut(user) {
+                stat.mp.current = i32::min(stat.mp.max, stat.mp.current + amount);
+                if is_player {
+                    Logger::new()
+                        .append("Your mind clears, restoring ")
+                        .append_with_color(Color::Green, format!("{} mp.", amount))
+                        .log();
+                }
+            }
+        }
+
+        ItemEffect::Damage {
+            damage,
+            damage_type,
+            area,
+        } => apply_area_damage(ecs, user, damage, *damage_type, area),
+
+        ItemEffect::Teleport => teleport(ecs, user, is_player),
+
+        ItemEffect::ApplyStatus { status, duration } => {
+            let mut statuses = ecs.write_storage::<Statuses>();
+            Statuses::apply(&mut statuses, user, *status, *duration);
+            if is_player {
+                Logger::new()
+                    .append(match status {
+                        StatusType::Poisoned => "You feel sick to your stomach.",
+                        StatusType::Regenerating => "Your wounds begin to knit themselves closed.",
+                    })
+                    .log();
+            }
+        }
+
+        ItemEffect::RevealMap => {
+            let mut map = ecs.write_resource::<Map>();
+            for tile in map.revealed_tiles.iter_mut() {
+                *tile = true;
+            }
+            if is_player {
+                Logger::new()
+                    .append("The darkness lifts, and you become more aware of everything around you.")
+                    .log();
+            }
+        }
+
+        ItemEffect::LearnSpell { spell } => {
+            let mut spell_knowledge = ecs.write_storage::<SpellKnowledge>();
+            if let Some(known) = spell_knowledge.get_mut(user) {
+                if known.spells.iter().any(|s| s.name == spell.name) {
+                    if is_player {
+                        Logger::new()
+                            .append("You already know the spell ")
+                            .append_with_color(Color::Green, format!("{}.", spell.name))
+                            .log();
+                    }
+                } else {
+                    known.spells.push(spell.clone());
+                    if is_player {
+                        Logger::new()
+                            .append("You learn the spell ")
+                            .append_with_color(Color::Green, format!("{}!", spell.name))
+                            .log();
+                    }
+                }
+            }
+        }
+
+        ItemEffect::Summon { name, count } => summon(ecs, user, name, *count),
+
+        ItemEffect::RemoveCurse => {
+            let uncursed = lift_curses(ecs, user);
+            if !is_player {
+                return;
+            }
+            if uncursed.is_empty() {
+                Logger::new()
+                    .append("A soothing warmth washes over you, but nothing seems to change.")
+                    .log();
+            }
+            for item_name in uncursed {
+                Logger::new()
+                    .append("The curse on the ")
+                    .append_with_color(Color::Green, item_name)
+                    .append(" is lifted.")
+                    .log();
+            }
+        }
+
+        ItemEffect::Identify => {
+            if !is_player {
+                return;
+            }
+            /*
+             * Only the item picked by the player is identified, curse and all.
+             * It has to still be in their pack by the time the scroll is read.
+             */
+            let item_name = target
+                .filter(|item| ecs.read_storage::<InBackpack>().get(*item).is_some_and(|backpack| backpack.owner == user))
+                .and_then(|item| ecs.read_storage::<Name>().get(item).map(|name| (item, name.name.clone())));
+            let Some((item, item_name)) = item_name else {
+                Logger::new()
+                    .append("The runes fade, with nothing to reveal.")
+                    .log();
+                return;
+            };
+            let mut identified_any = identify_by_use(ecs, &item_name);
+            identified_any |= reveal_curse(ecs, item);
+            if !identified_any {
+                Logger::new()
+                    .append("The runes fade, there was nothing left to learn about the ")
+                    .append_with_color(Color::Green, format!("{}.", item_name))
+                    .log();
+            }
+        }
+    }
+}
+
+/// Reveals a hidden curse on the given item.
+/// Returns true if there was one to reveal.
+fn reveal_curse(ecs: &World, item: Entity) -> bool {
+    let names = ecs.read_storage::<Name>();
+    let mut curses = ecs.write_storage::<Cursed>();
+    let Some(curse) = curses.get_mut(item).filter(|curse| !curse.known) else {
+        return false;
+    };
+    curse.known = true;
+    if let Some(name) = names.get(item) {
+        Logger::new()
+            .append("You sense a curse lurking in the ")
+            .append_with_color(Color::Red, format!("{}.", name.name))
+            .log();
+    }
+    return true;
+}
+
+/// Identifies the given item type after the player has used it.
+/// Returns true if the item was not already identified.
+pub fn identify_by_use(ecs: &World, item_name: &str) -> bool {
+    match identify_item_type(ecs, item_name) {
+        Some(appearance) => {
+            Logger::new()
+                .append("You identify the ")
+                .append_with_color(Color::Blue, appearance)
+                .append(" as a ")
+                .append_with_color(Color::Green, format!("{}.", item_name))
+                .log();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Damages every other entity caught in the area of effect.
+///
+/// Consumables can't be aimed, so balls are centered on the user while lines
+/// and cones are pointed at the closest monster the user can see.
+fn apply_area_damage(
+    ecs: &mut World,
+    user: Entity,
+    damage: &DiceExpression,
+    damage_type: DamageType,
+    area: &Area,
+) {
+    let entities = ecs.entities();
+    let map = ecs.fetch::<Map>();
+    let monsters = ecs.read_storage::<Monster>();
+    let viewsheds = ecs.read_storage::<Viewshed>();
+    let names = ecs.read_storage::<Name>();
+    let stats = ecs.read_storage::<Stats>();
+    let derived_stats = ecs.read_storage::<DerivedStats>();
+    let mut positions = ecs.write_storage::<Position>();
+    let mut renderables = ecs.write_storage::<Renderable>();
+    let mut lifetimes = ecs.write_storage::<Lifetime>();
+    let mut damages = ecs.write_storage::<Damage>();
+    let mut alertness = ecs.write_storage::<Alertness>();
+    let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+    let damage_name = format!("{:?}", damage_type).to_lowercase();
+
+    let Some(origin) = positions.get(user).map(|p| Point::new(p.x, p.y)) else {
+        return;
+    };
+
+    let aim = match area.shape {
+        AreaShape::Ball => Some(origin),
+        AreaShape::Line | AreaShape::Cone => {
+            let visible_tiles = viewsheds.get(user).map(|v| v.visible_tiles.clone()).unwrap_or_default();
+            (&positions, &monsters)
+                .join()
+                .map(|(position, _)| Point::new(position.x, position.y))
+                .filter(|point| visible_tiles.contains(point))
+                .min_by(|a, b| {
+                    let distance_a = rltk::DistanceAlg::Pythagoras.distance2d(origin, *a);
+                    let distance_b = rltk::DistanceAlg::Pythagoras.distance2d(origin, *b);
+                    distance_a.total_cmp(&distance_b)
+                })
+        }
+    };
+    let Some(aim) = aim else {
+        Logger::new()
+            .append(format!("A burst of {} sputters out, there was nothing to aim it at.", damage_name))
+            .log();
+        return;
+    };
+
+    let area_tiles = get_area_tiles(&map, origin, aim, area);
+    let mut victims = 0;
+    for index in area_tiles.iter() {
+        for entity in map.tile_content[*index].iter() {
+            if *entity == user || !stats.contains(*entity) {
+                continue;
+            }
+            let amount = resist_damage(
+                rng.roll_dice(damage.dice_count, damage.dice_sides) + damage.modifier,
+                damage_type,
+                derived_stats.get(*entity),
+            );
+            Damage::new_damage(&mut damages, Some(user), *entity, amount);
+            if let Some(alert) = alertness.get_mut(*entity) {
+                alert.state = AlertState::Alert;
+            }
+            victims += 1;
+            if let Some(name) = names.get(*entity) {
+                Logger::new()
+                    .append_with_color(Color::Red, format!("{} ", name.name))
+                    .append("is caught in the blast, taking ")
+                    .append_with_color(Color::Red, format!("{} {} damage.", amount, damage_name))
+                    .log();
+            }
+        }
+    }
+    spawn_explosion(
+        &entities,
+        ParticleStorages {
+            positions: &mut positions,
+            renderables: &mut renderables,
+            lifetimes: &mut lifetimes,
+        },
+        &map,
+        if area.shape == AreaShape::Ball { aim } else { origin },
+        &area_tiles,
+        damage_type,
+    );
+
+    if victims == 0 {
+        Logger::new()
+            .append(format!("A burst of {} erupts, but nothing is caught in it.", damage_name))
+            .log();
+    }
+}
+
+/// Moves the user to a random open floor tile on the current map.
+fn teleport(ecs: &mut World, user: Entity, is_player: bool) {
+    let destination = {
+        let map = ecs.fetch::<Map>();
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        let candidates: Vec<usize> = map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(index, tile)| **tile == TileType::Floor && !map.blocked_tiles[*index])
+            .map(|(index, _)| index)
+            .collect();
+        if candidates.is_empty() {
+            return;
+        }
+        let index = candidates[rng.roll_dice(1, candidates.len() as i32) as usize - 1];
+        map.idx_xy(index)
+    };
+
+    let mut positions = ecs.write_storage::<Position>();
+    if let Some(position) = positions.get_mut(user) {
+        position.x = destination.0;
+        position.y = destination.1;
+    }
+    if is_player {
+        let mut player_position = ecs.write_resource::<Point>();
+        *player_position = Point::new(destination.0, destination.1);
+        Logger::new()
+            .append("The world blurs around you, and you find yourself somewhere else entirely.")
+            .log();
+    }
+}
+
+/// Spawns up to `count` hostile monsters in the open tiles surrounding the user.
+fn summon(ecs: &mut World, user: Entity, monster_name: &str, count: i32) {
+    let spawn_points: Vec<Position> = {
+        let map = ecs.fetch::<Map>();
+        let positions = ecs.read_storage::<Position>();
+        let Some(origin) = positions.get(user) else {
+            return;
+        };
+        let mut spawn_points = Vec::new();
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (x, y) = (origin.x + dx, origin.y + dy);
+                if x < 0 || y < 0 || x >= map.width || y >= map.height {
+                    continue;
+                }
+                let index = map.xy_idx(x, y);
+                if map.tiles[index] == TileType::Floor && !map.blocked_tiles[index] {
+                    spawn_points.push(Position { x, y });
+                }
+            }
+        }
+        spawn_points.into_iter().take(count as usize).collect()
+    };
+
+    if spawn_points.is_empty() {
+        Logger::new()
+            .append("The air shimmers for a moment, but nothing appears.")
+            .log();
+        return;
+    }
+
+    for position in spawn_points {
+        if spawn_named_monster(ecs, position, monster_name, AlertState::Alert).is_some() {
+            let mut map = ecs.write_resource::<Map>();
+            let index = map.xy_idx(position.x, position.y);
+            map.blocked_tiles[index] = true;
+            Logger::new()
+                .append_with_color(Color::Red, format!("{} ", monster_name))
+                .append("appears out of thin air!")
+                .log();
+        }
+    }
+}
@@ -1 +1,2 @@
-pub mod effect;
\ No newline at end of file
+pub mod effect;
+pub mod item_effect;
\ No newline at end of file
@@ -0,0 +1,196 @@
+use std::collections::HashSet;
+
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+
+use crate::{
+    component::{
+        Armor, Cursed, EquipmentSlot, Equippable, Item, MeleeWeapon, Modifiers, Name, RangedWeapon,
+        Rarity, RarityTier,
+    },
+    generate::{
+        config::AffixConfig,
+        random_table::RandomTable,
+        spawn::{AFFIXES, modifiers_from_config},
+    },
+};
+
+/// Rarity is rolled as a d100, plus this much for every floor below the first.
+pub const RARITY_BONUS_PER_FLOOR: i32 = 3;
+pub const MAGIC_THRESHOLD: i32 = 60;
+pub const RARE_THRESHOLD: i32 = 85;
+pub const UNIQUE_THRESHOLD: i32 = 105;
+
+/// Plain (common) equipment has a 1 in N chance of carrying a hidden curse.
+pub const CURSE_CHANCE: i32 = 12;
+
+/// Names of the uniques spawned so far this run.
+///
+/// Kept for the whole run rather than looked up among living entities, so that a unique
+/// which has since been destroyed or sold off never turns up a second time.
+#[derive(Default)]
+pub struct SpawnedUniques {
+    pub names: HashSet<String>,
+}
+
+pub fn roll_rarity(rng: &mut RandomNumberGenerator, floor_index: u32) -> RarityTier {
+    let roll = rng.roll_dice(1, 100) + floor_index as i32 * RARITY_BONUS_PER_FLOOR;
+    match roll {
+        UNIQUE_THRESHOLD.. => RarityTier::Unique,
+        RARE_THRESHOLD.. => RarityTier::Rare,
+        MAGIC_THRESHOLD.. => RarityTier::Magic,
+        _ => RarityTier::Common,
+    }
+}
+
+/**
+ * Rolls a rarity for a freshly spawned equippable item and applies the resulting affixes.
+ *
+ * Deeper floors roll higher, so magic and rare items become commonplace the further down
+ * the player gets. Affixes are chosen by weight from those that fit the item's slot and have
+ * unlocked on the current floor. A unique is only rolled if one exists for the base item
+ * and it hasn't already been spawned this run, otherwise the item falls back to being rare.
+ *
+ * Affixed items get their own name, e.g. "Flaming Dagger of Accuracy", so they are kept
+ * in their own stack in `Inventory.items` rather than being lumped in with plain daggers.
+ *
+ * Negative affixes carry a curse, and plain gear is occasionally cursed as well.
+ * Either way the curse stays hidden until the item is equipped, see `Cursed`.
+ */
+pub fn roll_affixes(ecs: &mut World, item: Entity, floor_index: u32) {
+    let Some(slot) = ecs.read_storage::<Equippable>().get(item).map(|e| e.slot) else {
+        return;
+    };
+    // Ammunition never rolls affixes, otherwise a bundle would never stack
+    if slot == EquipmentSlot::Quiver {
+        return;
+    }
+    let Some(base_name) = ecs.read_storage::<Name>().get(item).map(|n| n.name.clone()) else {
+        return;
+    };
+    let affixes = AFFIXES.lock().unwrap();
+    let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+    let floor = floor_index as i32;
+    let mut tier = roll_rarity(&mut rng, floor_index);
+
+    if tier == RarityTier::Unique {
+        let mut spawned_uniques = ecs.write_resource::<SpawnedUniques>();
+        let unique = affixes.uniques.iter().find(|unique| {
+            unique.base == base_name
+                && unique.affix.min_floor <= floor
+                && !spawned_uniques.names.contains(&unique.affix.name)
+        });
+        if let Some(unique) = unique {
+            spawned_uniques.names.insert(unique.affix.name.clone());
+            drop(spawned_uniques);
+            drop(rng);
+            apply_affix(ecs, item, &unique.affix);
+            if let Some(name) = ecs.write_storage::<Name>().get_mut(item) {
+                name.name = unique.affix.name.clone();
+            }
+            if let Some(item_component) = ecs.write_storage::<Item>().get_mut(item) {
+                item_component.description = unique.description.clone();
+            }
+            insert_rarity(ecs, item, tier);
+            return;
+        }
+        tier = RarityTier::Rare;
+    }
+
+    let eligible = |affix: &&AffixConfig| affix.slots.contains(&slot) && affix.min_floor <= floor;
+    let pick = |rng: &mut RandomNumberGenerator, choices: &[AffixConfig]| -> Option<usize> {
+        let mut table = RandomTable::new();
+        for affix in choices.iter().filter(eligible) {
+            table.push(affix.name.clone(), affix.weight);
+        }
+        let name = table.roll(rng);
+        choices.iter().position(|affix| affix.name == name)
+    };
+    let (prefix, suffix) = match tier {
+        RarityTier::Common | RarityTier::Unique => (None, None),
+        RarityTier::Magic => {
+            if rng.roll_dice(1, 2) == 1 {
+                (pick(&mut rng, &affixes.prefixes), None)
+            } else {
+                (None, pick(&mut rng, &affixes.suffixes))
+            }
+        }
+        RarityTier::Rare => (pick(&mut rng, &affixes.prefixes), pick(&mut rng, &affixes.suffixes)),
+    };
+    let plain_curse = rng.roll_dice(1, CURSE_CHANCE) == 1;
+    drop(rng);
+
+    let prefix = prefix.map(|index| &affixes.prefixes[index]);
+    let suffix = suffix.map(|index| &affixes.suffixes[index]);
+    if prefix.is_none() && suffix.is_none() {
+        if plain_curse {
+            insert_curse(ecs, item);
+        }
+        return;
+    }
+    if prefix.iter().chain(suffix.iter()).any(|affix| affix.cursed) {
+        insert_curse(ecs, item);
+    }
+    for affix in prefix.iter().chain(suffix.iter()) {
+        apply_affix(ecs, item, affix);
+    }
+    if let Some(name) = ecs.write_storage::<Name>().get_mut(item) {
+        name.name = format!(
+            "{}{}{}",
+            prefix.map(|p| format!("{} ", p.name)).unwrap_or_default(),
+            base_name,
+            suffix.map(|s| format!(" {}", s.name)).unwrap_or_default(),
+        );
+    }
+    let tier = if prefix.is_some() && suffix.is_some() { RarityTier::Rare } else { RarityTier::Magic };
+    insert_rarity(ecs, item, tier);
+}
+
+/// Applies the bonuses of a single affix onto an item, leaving its name alone.
+fn apply_affix(ecs: &mut World, item: Entity, affix: &AffixConfig) {
+    let mut items = ecs.write_storage::<Item>();
+    let mut melee_weapons = ecs.write_storage::<MeleeWeapon>();
+    let mut ranged_weapons = ecs.write_storage::<RangedWeapon>();
+    let mut armors = ecs.write_storage::<Armor>();
+    let mut modifiers = ecs.write_storage::<Modifiers>();
+
+    if let Some(item) = items.get_mut(item) {
+        item.base_value = item.base_value * (100 + affix.value_percent) / 100;
+    }
+    if let Some(melee) = melee_weapons.get_mut(item) {
+        melee.damage.modifier += affix.damage_bonus;
+        melee.damage_type = affix.damage_type.unwrap_or(melee.damage_type);
+    }
+    if let Some(ranged) = ranged_weapons.get_mut(item) {
+        ranged.damage.modifier += affix.damage_bonus;
+        ranged.damage_type = affix.damage_type.unwrap_or(ranged.damage_type);
+    }
+    if let Some(armor) = armors.get_mut(item) {
+        armor.defense += affix.defense_bonus;
+    }
+
+    let affix_modifiers = modifiers_from_config(&affix.modifiers);
+    if affix_modifiers == Modifiers::default() {
+        return;
+    }
+    match modifiers.get_mut(item) {
+        Some(existing) => existing.combine(&affix_modifiers),
+        None => {
+            modifiers
+                .insert(item, affix_modifiers)
+                .expect("Unable to insert affix modifiers");
+        }
+    }
+}
+
+fn insert_curse(ecs: &mut World, item: Entity) {
+    ecs.write_storage::<Cursed>()
+        .insert(item, Cursed { known: false })
+        .expect("Unable to curse item");
+}
+
+fn insert_rarity(ecs: &mut World, item: Entity, tier: RarityTier) {
+    ecs.write_storage::<Rarity>()
+        .insert(item, Rarity { tier })
+        .expect("Unable to insert item rarity");
+}
@@ -0,0 +1,393 @@
+use rand::Rng;
+use ratatui::style::Color;
+use specs::prelude::*;
+
+use crate::{
+    component::{
+        Equippable, Equipped, FeatType, Feats, InBackpack, Inventory, Name, Pool, SpellKnowledge,
+        Stats, Viewshed,
+    },
+    generate::{
+        config::{ClassConfig, FeatConfig},
+        identification::IdentificationTable,
+        spawn::{CLASSES, FEATS, bundle_size, spawn_named_item, spell_from_config},
+    },
+    logbook::logbook::Logger,
+    system::{derived_stats_system::stat_to_modifier, inventory_system::{get_equip_conflicts, get_item_signature}},
+};
+
+pub const DEFAULT_PLAYER_NAME: &str = "Player the unnamed";
+pub const MAX_NAME_LENGTH: usize = 24;
+
+pub const ATTRIBUTE_NAMES: [&str; 6] = [
+    "Strength",
+    "Dexterity",
+    "Constitution",
+    "Intelligence",
+    "Wisdom",
+    "Charisma",
+];
+
+/// Point-buy follows the usual dnd rules, every attribute starts at 8
+/// and can be raised to 15 using a shared pool of points.
+pub const POINT_BUY_TOTAL: i32 = 27;
+pub const POINT_BUY_MIN: i32 = 8;
+pub const POINT_BUY_MAX: i32 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CreationStep {
+    Name,
+    Class,
+    Attributes,
+}
+
+/// The in-progress character, built up over the steps of character creation
+/// and applied to the freshly spawned player once the run begins.
+#[derive(Debug, Clone)]
+pub struct CharacterCreation {
+    pub step: CreationStep,
+    pub name: String,
+    pub class_index: usize,
+    pub attributes: [i32; 6],
+    pub attribute_index: usize,
+    pub rolled: bool,
+}
+
+impl CharacterCreation {
+    pub fn new() -> CharacterCreation {
+        CharacterCreation {
+            step: CreationStep::Name,
+            name: String::new(),
+            class_index: 0,
+            attributes: [POINT_BUY_MIN; 6],
+            attribute_index: 0,
+            rolled: false,
+        }
+    }
+
+    pub fn display_name(&self) -> String {
+        if self.name.trim().is_empty() {
+            DEFAULT_PLAYER_NAME.to_string()
+        } else {
+            self.name.trim().to_string()
+        }
+    }
+
+    /// Points left to spend, meaningless once the attributes have been rolled.
+    pub fn points_remaining(&self) -> i32 {
+        POINT_BUY_TOTAL - self.attributes.iter().map(|a| point_buy_cost(*a)).sum::<i32>()
+    }
+
+    /// Raises the selected attribute by one if there are enough points left to pay for it.
+    pub fn increase_attribute(&mut self) {
+        let current = self.attributes[self.attribute_index];
+        if self.rolled || current >= POINT_BUY_MAX {
+            return;
+        }
+        if point_buy_cost(current + 1) - point_buy_cost(current) <= self.points_remaining() {
+            self.attributes[self.attribute_index] += 1;
+        }
+    }
+
+    /// Lowers the selected attribute by one, refunding its points.
+    pub fn decrease_attribute(&mut self) {
+        if self.rolled || self.attributes[self.attribute_index] <= POINT_BUY_MIN {
+            return;
+        }
+        self.attributes[self.attribute_index] -= 1;
+    }
+
+    /// Rolls every attribute as 4d6, dropping the lowest die.
+    pub fn roll_attributes(&mut self) {
+        let mut rng = rand::rng();
+        for attribute in self.attributes.iter_mut() {
+            let mut dice: Vec<i32> = (0..4).map(|_| rng.random_range(1..=6)).collect();
+            dice.sort();
+            *attribute = dice.iter().skip(1).sum();
+        }
+        self.rolled = true;
+    }
+
+    /// Throws away any rolled attributes and starts the point-buy over.
+    pub fn reset_attributes(&mut self) {
+        self.attributes = [POINT_BUY_MIN; 6];
+        self.rolled = false;
+    }
+}
+
+/// Returns the total point-buy cost of raising an attribute from 8 to the given score.
+pub fn point_buy_cost(score: i32) -> i32 {
+    match score {
+        ..=8 => 0,
+        9..=13 => score - 8,
+        14 => 7,
+        _ => 9,
+    }
+}
+
+/// Returns the attribute bonuses granted by a class, in `ATTRIBUTE_NAMES` order.
+pub fn class_bonuses(class: &ClassConfig) -> [i32; 6] {
+    [
+        class.attributes.strength,
+        class.attributes.dexterity,
+        class.attributes.constitution,
+        class.attributes.intelligence,
+        class.attributes.wisdom,
+        class.attributes.charisma,
+    ]
+}
+
+/// Applies the finished character to the player entity.
+///
+/// Sets the name and stats, then hands out the class kit. Every item in the kit
+/// is already identified, and anything equippable is worn if the slot is free.
+pub fn apply_character(ecs: &mut World, character: &CharacterCreation) {
+    let player_entity = *ecs.fetch::<Entity>();
+    let classes = CLASSES.lock().unwrap();
+    let class = classes
+        .get(character.class_index)
+        .expect("Unable to find the selected class");
+
+    let bonuses = class_bonuses(class);
+    let attributes: Vec<i32> = character
+        .attributes
+        .iter()
+        .zip(bonuses.iter())
+        .map(|(attribute, bonus)| attribute + bonus)
+        .collect();
+    let hp = i32::max(1, class.hp + 2 * stat_to_modifier(attributes[2]));
+    let mp = i32::max(0, class.mp + 2 * stat_to_modifier(attributes[3]));
+
+    {
+        let mut names = ecs.write_storage::<Name>();
+        let mut stats = ecs.write_storage::<Stats>();
+        let mut inventories = ecs.write_storage::<Inventory>();
+        let mut spell_knowledge = ecs.write_storage::<SpellKnowledge>();
+
+        names
+            .insert(player_entity, Name { name: character.display_name() })
+            .expect("Unable to name the player");
+        if let Some(stat) = stats.get_mut(player_entity) {
+            stat.hp = Pool { current: hp, max: hp };
+            stat.mp = Pool { current: mp, max: mp };
+            stat.strength = attributes[0];
+            stat.dexterity = attributes[1];
+            stat.constitution = attributes[2];
+            stat.intelligence = attributes[3];
+            stat.wisdom = attributes[4];
+            stat.charisma = attributes[5];
+        }
+        if let Some(inventory) = inventories.get_mut(player_entity) {
+            inventory.gold += class.gold;
+        }
+        if let Some(known) = spell_knowledge.get_mut(player_entity) {
+            known.spells.extend(class.spells.iter().map(spell_from_config));
+        }
+    }
+
+    let kit: Vec<String> = class
+        .items
+        .iter()
+        .flat_map(|item_name| std::iter::repeat_n(item_name.clone(), bundle_size(item_name) as usize))
+        .collect();
+    for item_name in kit.iter() {
+        ecs.write_resource::<IdentificationTable>().identify(item_name);
+        let item = spawn_named_item(ecs, None, item_name.clone());
+        let signature = get_item_signature(ecs, item);
+
+        let mut backpack = ecs.write_storage::<InBackpack>();
+        let mut inventories = ecs.write_storage::<Inventory>();
+        let mut equipment = ecs.write_storage::<Equipped>();
+        let equippables = ecs.read_storage::<Equippable>();
+
+        backpack
+            .insert(item, InBackpack { owner: player_entity })
+            .expect("Unable to add starting item to backpack");
+        if let Some(inventory) = inventories.get_mut(player_entity) {
+            inventory.add_item(item, signature);
+        }
+        if let Some(equippable) = equippables.get(item) {
+            let conflicts =
+                get_equip_conflicts(player_entity, equippable, equippable.slot, &ecs.entities(), &equipment, &equippables);
+            if conflicts.is_empty() {
+                equipment
+                    .insert(item, Equipped { slot: equippable.slot, owner: player_entity })
+                    .expect("Unable to equip starting item");
+            }
+        }
+    }
+}
+
+/// A feat is chosen on every level that is a multiple of this.
+pub const FEAT_LEVEL_INTERVAL: i32 = 2;
+pub const TOUGHNESS_HP: i32 = 10;
+pub const EXTRA_SPELL_SLOT_MP: i32 = 4;
+pub const KEEN_EYES_RANGE: i32 = 2;
+
+/// Returns true if the given stats and feats satisfy every prerequisite of the feat,
+/// and the feat hasn't already been taken.
+pub fn meets_prerequisites(feat: &FeatConfig, stat: &Stats, feats: &Feats) -> bool {
+    let prerequisites = &feat.prerequisites;
+    let attributes = [
+        stat.strength,
+        stat.dexterity,
+        stat.constitution,
+        stat.intelligence,
+        stat.wisdom,
+        stat.charisma,
+    ];
+    let required = [
+        prerequisites.attributes.strength,
+        prerequisites.attributes.dexterity,
+        prerequisites.attributes.constitution,
+        prerequisites.attributes.intelligence,
+        prerequisites.attributes.wisdom,
+        prerequisites.attributes.charisma,
+    ];
+    return !feats.has(feat.feat)
+        && stat.level >= prerequisites.level
+        && attributes.iter().zip(required.iter()).all(|(attribute, required)| attribute >= required)
+        && prerequisites.feats.iter().all(|required| feats.has(*required));
+}
+
+/// Returns true if the entity should pick a feat at its current level,
+/// and there is at least one feat available to it.
+pub fn can_choose_feat(ecs: &World, entity: Entity) -> bool {
+    let stats = ecs.read_storage::<Stats>();
+    let feats = ecs.read_storage::<Feats>();
+    let (Some(stat), Some(known)) = (stats.get(entity), feats.get(entity)) else {
+        return false;
+    };
+    return stat.level % FEAT_LEVEL_INTERVAL == 0
+        && FEATS.lock().unwrap().iter().any(|feat| meets_prerequisites(feat, stat, known));
+}
+
+/// Grants the feat at the given index of `FEATS` to the entity, if it qualifies.
+///
+/// Feats that simply raise a stat are applied once here, everything else is
+/// looked up by the relevant system whenever it matters.
+pub fn choose_feat(ecs: &mut World, entity: Entity, feat_index: usize) -> bool {
+    let all_feats = FEATS.lock().unwrap();
+    let Some(feat) = all_feats.get(feat_index) else {
+        return false;
+    };
+    let mut stats = ecs.write_storage::<Stats>();
+    let mut feats = ecs.write_storage::<Feats>();
+    let mut viewsheds = ecs.write_storage::<Viewshed>();
+    let (Some(stat), Some(known)) = (stats.get_mut(entity), feats.get_mut(entity)) else {
+        return false;
+    };
+    if !meets_prerequisites(feat, stat, known) {
+        return false;
+    }
+
+    known.feats.push(feat.feat);
+    match feat.feat {
+        FeatType::Toughness => {
+            stat.hp.max += TOUGHNESS_HP;
+            stat.hp.current += TOUGHNESS_HP;
+        }
+        FeatType::ExtraSpellSlot => {
+            stat.mp.max += EXTRA_SPELL_SLOT_MP;
+            stat.mp.current += EXTRA_SPELL_SLOT_MP;
+        }
+        FeatType::KeenEyes => {
+            if let Some(viewshed) = viewsheds.get_mut(entity) {
+                viewshed.range += KEEN_EYES_RANGE;
+            }
+        }
+        FeatType::PowerAttack | FeatType::TrapSense | FeatType::Haggler | FeatType::TwoWeaponFighting => {}
+    }
+    Logger::new()
+        .append("You have learned the feat ")
+        .append_with_color(Color::Yellow, format!("{}!", feat.name))
+        .log();
+    return true;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point_buy() -> CharacterCreation {
+        let mut creation = CharacterCreation::new();
+        creation.step = CreationStep::Attributes;
+        return creation;
+    }
+
+    #[test]
+    fn point_buy_costs_follow_the_dnd_table() {
+        let costs: Vec<i32> = (8..=15).map(point_buy_cost).collect();
+        assert_eq!(costs, vec![0, 1, 2, 3, 4, 5, 7, 9]);
+    }
+
+    #[test]
+    fn attributes_stop_at_the_maximum() {
+        let mut creation = point_buy();
+        for _ in 0..10 {
+            creation.increase_attribute();
+        }
+        assert_eq!(creation.attributes[0], POINT_BUY_MAX);
+        assert_eq!(creation.points_remaining(), POINT_BUY_TOTAL - 9);
+    }
+
+    #[test]
+    fn attributes_stop_at_the_minimum() {
+        let mut creation = point_buy();
+        creation.decrease_attribute();
+        assert_eq!(creation.attributes[0], POINT_BUY_MIN);
+        assert_eq!(creation.points_remaining(), POINT_BUY_TOTAL);
+    }
+
+    #[test]
+    fn spending_never_exceeds_the_budget() {
+        let mut creation = point_buy();
+        for index in 0..6 {
+            creation.attribute_index = index;
+            for _ in 0..10 {
+                creation.increase_attribute();
+            }
+        }
+        /* 15, 15, 15 costs exactly 27, nothing is left for the rest */
+        assert_eq!(creation.attributes, [15, 15, 15, 8, 8, 8]);
+        assert_eq!(creation.points_remaining(), 0);
+    }
+
+    #[test]
+    fn an_increase_that_costs_more_than_is_left_is_refused() {
+        let mut creation = point_buy();
+        creation.attributes = [15, 15, 14, 9, 8, 8];
+        assert_eq!(creation.points_remaining(), 1);
+        /* 14 to 15 costs two points */
+        creation.attribute_index = 2;
+        creation.increase_attribute();
+        assert_eq!(creation.attributes[2], 14);
+        /* 8 to 9 costs one */
+        creation.attribute_index = 4;
+        creation.increase_attribute();
+        assert_eq!(creation.attributes[4], 9);
+        assert_eq!(creation.points_remaining(), 0);
+    }
+
+    #[test]
+    fn decreasing_refunds_the_points() {
+        let mut creation = point_buy();
+        creation.attributes = [15, 15, 15, 8, 8, 8];
+        creation.decrease_attribute();
+        assert_eq!(creation.attributes[0], 14);
+        assert_eq!(creation.points_remaining(), 2);
+    }
+
+    #[test]
+    fn rolled_attributes_are_not_adjusted_by_point_buy() {
+        let mut creation = point_buy();
+        creation.roll_attributes();
+        let rolled = creation.attributes;
+        creation.increase_attribute();
+        creation.decrease_attribute();
+        assert_eq!(creation.attributes, rolled);
+        creation.reset_attributes();
+        assert_eq!(creation.attributes, [POINT_BUY_MIN; 6]);
+        assert_eq!(creation.points_remaining(), POINT_BUY_TOTAL);
+    }
+}
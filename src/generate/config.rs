@@ -1,26 +1,36 @@
 use lazy_static::lazy_static;
-use regex::Regex;
-use serde::Deserialize;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Deserializer};
 
-use crate::component::{DamageType, EquipmentSlot, Pool, PotionType};
+use crate::component::{
+    AmmoType, AreaShape, DamageType, EquipmentSlot, FeatType, Pool, StatusType,
+};
 
 #[derive(Deserialize)]
 pub struct ItemConfig {
     pub name: String,
     pub description: String,
     pub base_value: i32,
+    #[serde(default)]
+    pub weight: f32,
     pub renderable: Option<RenderableConfig>,
     pub spawn: Option<SpawnConfig>,
-    pub potion: Option<PotionConfig>,
-    pub scroll: Option<ScrollConfig>,
     pub equippable: Option<EquippableConfig>,
     pub melee_weapon: Option<MeleeWeaponConfig>,
     pub ranged_weapon: Option<RangedWeaponConfig>,
+    pub ammunition: Option<AmmunitionConfig>,
+    pub throwable: Option<ThrowableConfig>,
+    pub fragile: Option<FragileConfig>,
+    pub bundle: Option<i32>,
+    pub durability: Option<i32>,
+    pub gold: Option<String>,
     pub magic_weapon: Option<MagicWeaponConfig>,
-    pub spells: Option<Vec<SpellConfig>>,
     pub armor: Option<ArmorConfig>,
+    pub modifiers: Option<ModifiersConfig>,
     pub hidden: Option<bool>,
     pub triggerable: Option<TriggerableConfig>,
+    pub identification: Option<IdentificationType>,
+    pub effects: Option<Vec<EffectConfig>>,
 }
 
 #[derive(Deserialize)]
@@ -34,6 +44,135 @@ pub struct MonsterConfig {
     pub drop_type: Option<DropType>,
 }
 
+#[derive(Deserialize)]
+pub struct ClassConfig {
+    pub name: String,
+    pub description: String,
+    pub hp: i32,
+    pub mp: i32,
+    #[serde(default)]
+    pub gold: i32,
+    #[serde(default)]
+    pub attributes: AttributesConfig,
+    #[serde(default)]
+    pub items: Vec<String>,
+    #[serde(default)]
+    pub spells: Vec<SpellConfig>,
+}
+
+#[derive(Deserialize)]
+pub struct FeatConfig {
+    pub name: String,
+    pub description: String,
+    pub feat: FeatType,
+    #[serde(default)]
+    pub prerequisites: PrerequisitesConfig,
+}
+
+/// Requirements that must be met before a feat can be chosen.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct PrerequisitesConfig {
+    pub level: i32,
+    pub attributes: AttributesConfig,
+    pub feats: Vec<FeatType>,
+}
+
+/// Prefixes and suffixes that loot generation can roll onto equippable items,
+/// along with the handful of unique items that replace a base item outright.
+#[derive(Deserialize, Default)]
+pub struct AffixesConfig {
+    pub prefixes: Vec<AffixConfig>,
+    pub suffixes: Vec<AffixConfig>,
+    #[serde(default)]
+    pub uniques: Vec<UniqueConfig>,
+}
+
+/// The stock table of a vendor, matched to them by name, see `restock_vendor`.
+#[derive(Deserialize)]
+pub struct VendorConfig {
+    pub name: String,
+    pub gold: i32,
+    pub stock: Vec<VendorStockConfig>,
+}
+
+/// A single line of a vendor's stock, stocked `count` times with a `chance` percent chance.
+#[derive(Deserialize)]
+pub struct VendorStockConfig {
+    pub item: String,
+    pub count: Option<i32>,
+    pub chance: Option<i32>,
+}
+
+/// Rules deciding which items are picked up as soon as the player steps onto them.
+/// An item only has to match one of the rules to be collected.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct AutoPickupConfig {
+    pub enabled: bool,
+    pub categories: Vec<ItemCategory>,
+    /// Compiled once as the config is loaded, so a broken pattern fails loudly right away.
+    #[serde(deserialize_with = "deserialize_patterns")]
+    pub patterns: Vec<Regex>,
+    pub min_value: Option<i32>,
+}
+
+/// Compiles auto-pickup patterns, which match item names case-insensitively.
+fn deserialize_patterns<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Regex>, D::Error> {
+    let patterns: Vec<String> = Vec::deserialize(deserializer)?;
+    return patterns
+        .iter()
+        .map(|pattern| {
+            RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .map_err(|error| serde::de::Error::custom(format!("invalid auto-pickup pattern {:?}: {}", pattern, error)))
+        })
+        .collect();
+}
+
+/// Broad kinds of items that auto-pickup rules can refer to.
+#[derive(Deserialize, PartialEq, Debug, Clone, Copy)]
+pub enum ItemCategory {
+    Potion,
+    Scroll,
+    Ammunition,
+    Gold,
+}
+
+/// A single affix, e.g. "Flaming" or "of Accuracy", and the bonuses it grants.
+#[derive(Deserialize)]
+pub struct AffixConfig {
+    pub name: String,
+    #[serde(default)]
+    pub slots: Vec<EquipmentSlot>,
+    #[serde(default)]
+    pub min_floor: i32,
+    #[serde(default)]
+    pub weight: i32,
+    #[serde(default)]
+    pub value_percent: i32,
+    #[serde(default)]
+    pub damage_bonus: i32,
+    pub damage_type: Option<DamageType>,
+    #[serde(default)]
+    pub defense_bonus: i32,
+    #[serde(default)]
+    pub modifiers: ModifiersConfig,
+    #[serde(default)]
+    pub cursed: bool,
+}
+
+/// A unique item, rolled in place of the named base item.
+/// Its bonuses are given exactly like an affix, but its name replaces the base name entirely.
+#[derive(Deserialize)]
+pub struct UniqueConfig {
+    pub base: String,
+    pub description: String,
+    #[serde(flatten)]
+    pub affix: AffixConfig,
+}
+
 #[derive(Deserialize)]
 pub struct DropConfig {
     pub drop_type: DropType,
@@ -67,25 +206,38 @@ pub struct SpawnConfig {
     pub base_weight: i32,
 }
 
-#[derive(Deserialize)]
-pub struct PotionConfig {
-    pub potion_type: PotionType,
-    pub restore_amount: i32,
-}
 
-#[derive(Deserialize)]
-pub enum ScrollType {
-    MagicMapper,
+/// Items with an identification type are shown under a randomized
+/// appearance until the player identifies them.
+#[derive(Deserialize, PartialEq, Debug, Clone)]
+pub enum IdentificationType {
+    Potion,
+    Scroll,
 }
 
+/// The on-use effects of a consumable, applied in the order they are listed.
 #[derive(Deserialize)]
-pub struct ScrollConfig {
-    pub scroll_type: ScrollType,
+#[serde(tag = "type")]
+pub enum EffectConfig {
+    Heal { amount: i32 },
+    RestoreMana { amount: i32 },
+    Damage { damage: String, damage_type: DamageType, area: AreaConfig },
+    Teleport,
+    ApplyStatus { status: StatusType, duration: i32 },
+    RevealMap,
+    LearnSpell(SpellConfig),
+    Summon { name: String, count: i32 },
+    Identify,
+    RemoveCurse,
 }
 
 #[derive(Deserialize)]
 pub struct EquippableConfig {
-    pub slot: EquipmentSlot
+    pub slot: EquipmentSlot,
+    /// One unless given, see `Equippable`.
+    pub hands: Option<i32>,
+    #[serde(default)]
+    pub light: bool,
 }
 
 #[derive(Deserialize)]
@@ -98,7 +250,31 @@ pub struct MeleeWeaponConfig {
 pub struct RangedWeaponConfig {
     pub damage: String,
     pub damage_type: DamageType,
-    pub range: i32
+    pub range: i32,
+    pub ammo: Option<AmmoType>,
+}
+
+#[derive(Deserialize)]
+pub struct ThrowableConfig {
+    pub damage: String,
+    pub damage_type: DamageType,
+}
+
+#[derive(Deserialize)]
+pub struct FragileConfig {
+    pub radius: i32,
+}
+
+#[derive(Deserialize)]
+pub struct AmmunitionConfig {
+    pub ammo_type: AmmoType,
+    #[serde(default)]
+    pub damage_bonus: i32,
+    pub damage_type: Option<DamageType>,
+    pub status: Option<StatusType>,
+    #[serde(default)]
+    pub status_duration: i32,
+    pub recovery_chance: i32,
 }
 
 #[derive(Deserialize)]
@@ -112,11 +288,38 @@ pub struct SpellConfig {
     pub mp_cost: i32,
     pub damage: String,
     pub damage_type: DamageType,
+    pub area: Option<AreaConfig>,
+}
+
+#[derive(Deserialize)]
+pub struct AreaConfig {
+    pub shape: AreaShape,
+    pub size: i32,
+}
+
+/// Bonuses that apply while the item is equipped, every field is optional.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct ModifiersConfig {
+    pub strength: i32,
+    pub dexterity: i32,
+    pub constitution: i32,
+    pub intelligence: i32,
+    pub wisdom: i32,
+    pub charisma: i32,
+    pub max_hp: i32,
+    pub viewshed_range: i32,
+    pub mp_regeneration: i32,
+    pub speed: i32,
+    pub to_hit: i32,
+    pub resistances: Vec<DamageType>,
 }
 
 #[derive(Deserialize)]
 pub struct ArmorConfig {
-    pub defense: i32
+    pub defense: i32,
+    #[serde(default)]
+    pub noise: i32,
 }
 
 #[derive(Deserialize)]
@@ -129,6 +332,18 @@ pub struct ViewshedConfig {
     pub range: i32
 }
 
+/// Flat attribute bonuses, any attribute left out defaults to zero.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct AttributesConfig {
+    pub strength: i32,
+    pub dexterity: i32,
+    pub constitution: i32,
+    pub intelligence: i32,
+    pub wisdom: i32,
+    pub charisma: i32,
+}
+
 #[derive(Deserialize)]
 pub struct StatsConfig {
     pub hp: Pool,
@@ -156,15 +371,14 @@ impl DiceExpression {
             "{}d{}{}",
             self.dice_count,
             self.dice_sides,
-            if self.modifier == 0 { "".to_string() } else {
-                format!(
-                    "{}{}",
-                    if self.modifier > 0 { "+" } else { "-" },
-                    self.modifier
-                )
-            }
+            if self.modifier == 0 { "".to_string() } else { format!("{:+}", self.modifier) }
         );
     }
+
+    /// The mean of every possible roll, e.g. 3.5 for 1d6.
+    pub fn average(&self) -> f32 {
+        return self.dice_count as f32 * (self.dice_sides + 1) as f32 / 2.0 + self.modifier as f32;
+    }
 }
 
 pub fn parse_dice_expression(dice : &str) -> DiceExpression {
@@ -188,3 +402,22 @@ pub fn parse_dice_expression(dice : &str) -> DiceExpression {
     }
     DiceExpression { dice_count, dice_sides, modifier }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_pickup_patterns_are_compiled_case_insensitively() {
+        let config: AutoPickupConfig = serde_yaml::from_str("patterns: [\"^animal hide$\"]").unwrap();
+        assert_eq!(config.patterns.len(), 1);
+        assert!(config.patterns[0].is_match("Animal Hide"));
+    }
+
+    #[test]
+    fn invalid_auto_pickup_pattern_fails_to_load() {
+        let config: Result<AutoPickupConfig, _> = serde_yaml::from_str("patterns: [\"(unclosed\"]");
+        let error = config.err().expect("An invalid pattern should fail to load").to_string();
+        assert!(error.contains("invalid auto-pickup pattern"));
+    }
+}
@@ -3,18 +3,23 @@ use std::ops::Deref;
 
 use crate::{
     App, Player, Position, RunState,
-    component::OtherLevelPosition,
+    component::{OtherLevelPosition, Vendor},
     generate::{
+        affix::SpawnedUniques,
+        identification::IdentificationTable,
         map::{Map, MapOptions},
         spawn::{
-            spawn_npc_captain, spawn_npc_merchant, spawn_player, spawn_weighted_item,
-            spawn_weighted_monster,
+            restock_vendor, spawn_npc_captain, spawn_npc_merchant, spawn_player, spawn_shrine,
+            spawn_weighted_item, spawn_weighted_monster,
         },
     },
 };
 use rltk::{Point, RandomNumberGenerator};
 use specs::prelude::*;
 
+/// Each dungeon floor has a 1 in N chance of containing a shrine.
+pub const SHRINE_CHANCE: i32 = 3;
+
 /// Performs all of the associated mutations for switching a floor.
 ///
 /// Functionality is encapsulated here to reduce complexoity in the main run fn.
@@ -26,6 +31,9 @@ pub fn switch_floor(app: &mut App, next_index: u32) -> RunState {
     let mut map = match existing_map {
         Some(map) => {
             thaw_floor(next_index, &mut app.ecs);
+            if next_index == 0 {
+                restock_vendors(&mut app.ecs);
+            }
             map
         }
         None => {
@@ -53,6 +61,17 @@ pub fn switch_floor(app: &mut App, next_index: u32) -> RunState {
     return RunState::AwaitingInput;
 }
 
+/// Vendors restock while the player is off in the dungeon, see `restock_vendor`.
+fn restock_vendors(world: &mut World) {
+    let vendors: Vec<Entity> = (&world.entities(), &world.read_storage::<Vendor>())
+        .join()
+        .map(|(entity, _)| entity)
+        .collect();
+    for vendor in vendors.iter() {
+        restock_vendor(world, *vendor);
+    }
+}
+
 pub fn freeze_floor(index: u32, world: &mut World) {
     let entities = world.entities();
     let mut positions = world.write_storage::<Position>();
@@ -120,6 +139,8 @@ pub fn generate_floor(seed: u64, floor_index: u32, world: &mut World) -> Map {
         rng = existing_rng.deref().clone();
     } else {
         world.insert(rng.clone());
+        world.insert(IdentificationTable::new(&mut rng));
+        world.insert(SpawnedUniques::default());
     }
 
     let map = match floor_index {
@@ -151,7 +172,17 @@ pub fn generate_floor(seed: u64, floor_index: u32, world: &mut World) -> Map {
                     has_debris: true,
                 },
             );
-            for (_index, room) in map.rooms.iter().skip(1).enumerate() {
+            // Some floors set aside a quiet room for a shrine, see `Shrine`
+            let shrine_room = if rng.roll_dice(1, SHRINE_CHANCE) == 1 && map.rooms.len() > 1 {
+                Some(rng.range(1, map.rooms.len()))
+            } else {
+                None
+            };
+            for (index, room) in map.rooms.iter().enumerate().skip(1) {
+                if Some(index) == shrine_room {
+                    spawn_shrine(world, room);
+                    continue;
+                }
                 spawn_weighted_item(world, floor_index, room);
                 spawn_weighted_monster(world, floor_index, room);
             }
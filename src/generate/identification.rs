@@ -0,0 +1,118 @@
+use std::collections::{HashMap, HashSet};
+
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+
+use crate::{
+    component::Name,
+    generate::{config::IdentificationType, spawn::ITEMS},
+};
+
+const POTION_ADJECTIVES: [&str; 10] = [
+    "murky", "bubbling", "cloudy", "fizzy", "viscous", "glittering", "smoky", "oily", "clear",
+    "swirling",
+];
+
+const POTION_COLORS: [&str; 10] = [
+    "green", "red", "blue", "amber", "violet", "black", "silver", "pink", "orange", "white",
+];
+
+const SCROLL_LETTERS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+pub const UNIDENTIFIED_DESCRIPTION: &str = "You aren't sure what this does. Using it is one way to find out.";
+
+/// Tracks which potions and scrolls have been identified during the current run.
+///
+/// Every identifiable item is assigned a randomized appearance at the start of
+/// the run, e.g. "murky green potion" or "scroll labelled XOBQ". Until the item
+/// is identified, that appearance is shown in place of its true `Name`.
+#[derive(Default, Clone)]
+pub struct IdentificationTable {
+    appearances: HashMap<String, String>,
+    identified: HashSet<String>,
+}
+
+impl IdentificationTable {
+    pub fn new(rng: &mut RandomNumberGenerator) -> IdentificationTable {
+        let mut table = IdentificationTable::default();
+        let mut used: HashSet<String> = HashSet::new();
+
+        for item in ITEMS.lock().unwrap().iter() {
+            let Some(identification) = &item.identification else {
+                continue;
+            };
+            let mut appearance = generate_appearance(rng, identification);
+            while used.contains(&appearance) {
+                appearance = generate_appearance(rng, identification);
+            }
+            used.insert(appearance.clone());
+            table.appearances.insert(item.name.clone(), appearance);
+        }
+        return table;
+    }
+
+    /// Returns the name that should be shown to the player for the given item name.
+    pub fn display_name(&self, name: &str) -> String {
+        match self.appearances.get(name) {
+            Some(appearance) if !self.identified.contains(name) => appearance.clone(),
+            _ => name.to_string(),
+        }
+    }
+
+    /// Returns the description that should be shown to the player for the given item name.
+    pub fn display_description(&self, name: &str, description: &str) -> String {
+        if self.is_identified(name) {
+            description.to_string()
+        } else {
+            UNIDENTIFIED_DESCRIPTION.to_string()
+        }
+    }
+
+    pub fn is_identified(&self, name: &str) -> bool {
+        return !self.appearances.contains_key(name) || self.identified.contains(name);
+    }
+
+    /// Convenience wrapper around `is_identified` for an item entity.
+    pub fn is_item_identified(&self, item: Entity, names: &ReadStorage<Name>) -> bool {
+        return names
+            .get(item)
+            .map(|name| self.is_identified(&name.name))
+            .unwrap_or(true);
+    }
+
+    /// Marks the given item name as identified.
+    /// Returns the previous (unidentified) appearance if this is newly identified.
+    pub fn identify(&mut self, name: &str) -> Option<String> {
+        if self.is_identified(name) {
+            return None;
+        }
+        self.identified.insert(name.to_string());
+        return self.appearances.get(name).cloned();
+    }
+}
+
+/// Identifies the item type with the given name.
+/// Returns its unidentified appearance if the item type was newly identified.
+pub fn identify_item_type(ecs: &World, name: &str) -> Option<String> {
+    ecs.write_resource::<IdentificationTable>().identify(name)
+}
+
+fn generate_appearance(rng: &mut RandomNumberGenerator, identification: &IdentificationType) -> String {
+    match identification {
+        IdentificationType::Potion => {
+            let adjective = POTION_ADJECTIVES[rng.roll_dice(1, POTION_ADJECTIVES.len() as i32) as usize - 1];
+            let color = POTION_COLORS[rng.roll_dice(1, POTION_COLORS.len() as i32) as usize - 1];
+            format!("{} {} potion", adjective, color)
+        }
+        IdentificationType::Scroll => {
+            let length = rng.range(4, 7);
+            let label: String = (0..length)
+                .map(|_| {
+                    let index = rng.roll_dice(1, SCROLL_LETTERS.len() as i32) as usize - 1;
+                    SCROLL_LETTERS.as_bytes()[index] as char
+                })
+                .collect();
+            format!("scroll labelled {}", label)
+        }
+    }
+}
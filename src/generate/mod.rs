@@ -1,6 +1,9 @@
+pub mod affix;
+pub mod character;
 pub mod config;
 pub mod dungeon;
 pub mod generate;
+pub mod identification;
 pub mod map;
 pub mod random_table;
 pub mod rect;
@@ -1,5 +1,5 @@
 use core::panic;
-use std::{fs, sync::Mutex};
+use std::{collections::HashSet, fs, sync::Mutex};
 
 use indexmap::IndexMap;
 use lazy_static::lazy_static;
@@ -9,14 +9,20 @@ use specs::prelude::*;
 
 use crate::{
     component::{
-        Armor, BlocksTile, Equippable, Hidden, Inventory, Item, MagicMapper, MagicWeapon,
-        MeleeWeapon, Monster, Name, Npc, Player, Pool, Position, Potion, RangedWeapon, Renderable,
-        Spell, SpellKnowledge, Stats, Triggerable, Vendor, Viewshed,
+        AlertState, Alertness, Ammunition, Area, Armor, BlocksTile, Consumable, Durability,
+        Equippable, Feats, Fragile, Gold, Hidden, Inventory, InventoryView, Item, ItemEffect,
+        MagicWeapon, MeleeWeapon, Modifiers, Monster, Name, Npc, Player, Pool, Position,
+        RangedWeapon, Renderable, Shrine, Spell, SpellKnowledge, Stats, Throwable, Triggerable,
+        Vendor, Viewshed,
     },
     generate::{
+        affix::roll_affixes,
         config::{
-            DropConfig, DropType, ItemConfig, MonsterConfig, ScrollType, parse_dice_expression,
+            AffixesConfig, AutoPickupConfig, ClassConfig, DropConfig, DropType, EffectConfig,
+            FeatConfig, ItemConfig, ModifiersConfig, MonsterConfig, SpellConfig, VendorConfig,
+            parse_dice_expression,
         },
+        map::Map,
         random_table::RandomTable,
         rect::Rect,
     },
@@ -26,6 +32,11 @@ lazy_static! {
     pub static ref ITEMS: Mutex<Vec<ItemConfig>> = Mutex::new(Vec::new());
     pub static ref MONSTERS: Mutex<Vec<MonsterConfig>> = Mutex::new(Vec::new());
     pub static ref DROPS: Mutex<Vec<DropConfig>> = Mutex::new(Vec::new());
+    pub static ref CLASSES: Mutex<Vec<ClassConfig>> = Mutex::new(Vec::new());
+    pub static ref FEATS: Mutex<Vec<FeatConfig>> = Mutex::new(Vec::new());
+    pub static ref AFFIXES: Mutex<AffixesConfig> = Mutex::new(AffixesConfig::default());
+    pub static ref AUTO_PICKUP: Mutex<AutoPickupConfig> = Mutex::new(AutoPickupConfig::default());
+    pub static ref VENDORS: Mutex<Vec<VendorConfig>> = Mutex::new(Vec::new());
 }
 
 pub fn initialize_config() {
@@ -43,6 +54,31 @@ pub fn initialize_config() {
         .unwrap_or_else(|_| include_str!("../../config/drops.yaml").to_string());
     let drops: Vec<DropConfig> = serde_yaml::from_str(&drops_raw).unwrap();
     DROPS.lock().unwrap().extend(drops);
+
+    let classes_raw = fs::read_to_string("./config/classes.yaml")
+        .unwrap_or_else(|_| include_str!("../../config/classes.yaml").to_string());
+    let classes: Vec<ClassConfig> = serde_yaml::from_str(&classes_raw).unwrap();
+    CLASSES.lock().unwrap().extend(classes);
+
+    let feats_raw = fs::read_to_string("./config/feats.yaml")
+        .unwrap_or_else(|_| include_str!("../../config/feats.yaml").to_string());
+    let feats: Vec<FeatConfig> = serde_yaml::from_str(&feats_raw).unwrap();
+    FEATS.lock().unwrap().extend(feats);
+
+    let affixes_raw = fs::read_to_string("./config/affixes.yaml")
+        .unwrap_or_else(|_| include_str!("../../config/affixes.yaml").to_string());
+    let affixes: AffixesConfig = serde_yaml::from_str(&affixes_raw).unwrap();
+    *AFFIXES.lock().unwrap() = affixes;
+
+    let auto_pickup_raw = fs::read_to_string("./config/autopickup.yaml")
+        .unwrap_or_else(|_| include_str!("../../config/autopickup.yaml").to_string());
+    let auto_pickup: AutoPickupConfig = serde_yaml::from_str(&auto_pickup_raw).unwrap();
+    *AUTO_PICKUP.lock().unwrap() = auto_pickup;
+
+    let vendors_raw = fs::read_to_string("./config/vendors.yaml")
+        .unwrap_or_else(|_| include_str!("../../config/vendors.yaml").to_string());
+    let vendors: Vec<VendorConfig> = serde_yaml::from_str(&vendors_raw).unwrap();
+    VENDORS.lock().unwrap().extend(vendors);
 }
 
 /// Spawns a single named item from the master list given a name and position.
@@ -81,20 +117,37 @@ pub fn spawn_weighted_item(ecs: &mut World, floor_index: u32, room: &Rect) {
         (pos, item_spawn_table.roll(&mut rng))
     };
 
+    let mut spawned: Vec<Entity> = Vec::new();
     for item in ITEMS.lock().unwrap().iter() {
         if item.name != spawn {
             continue;
         }
-        let mut entity = ecs.create_entity();
-        entity = spawn_item(entity, Some(pos), item);
-        entity.build();
+        for _ in 0..item.bundle.unwrap_or(1) {
+            let mut entity = ecs.create_entity();
+            entity = spawn_item(entity, Some(pos), item);
+            spawned.push(entity.build());
+        }
         break;
     }
+    for item in spawned {
+        roll_affixes(ecs, item, floor_index);
+    }
+}
+
+/// Returns how many of the named item are found together, e.g. a bundle of arrows.
+pub fn bundle_size(item_name: &str) -> i32 {
+    ITEMS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|item| item.name == item_name)
+        .and_then(|item| item.bundle)
+        .unwrap_or(1)
 }
 
 /// Spawns a weighted monster based on the current floor and internal spawn table.
 pub fn spawn_weighted_monster(ecs: &mut World, floor_index: u32, room: &Rect) {
-    let (pos, spawn): (Position, String) = {
+    let (pos, spawn, alert_state): (Position, String, AlertState) = {
         let mut rng = ecs.fetch_mut::<RandomNumberGenerator>();
         let width = room.x2 - room.x1;
         let height = room.y2 - room.y1;
@@ -113,112 +166,155 @@ pub fn spawn_weighted_monster(ecs: &mut World, floor_index: u32, room: &Rect) {
                 None => {}
             };
         }
-        (pos, monster_spawn_table.roll(&mut rng))
+        // Monsters are never expecting company, roughly half of them are caught napping
+        let alert_state = if rng.roll_dice(1, 2) == 1 {
+            AlertState::Asleep
+        } else {
+            AlertState::Unaware
+        };
+        (pos, monster_spawn_table.roll(&mut rng), alert_state)
     };
 
     for monster in MONSTERS.lock().unwrap().iter() {
         if monster.name != spawn {
             continue;
         }
-        let mut entity = ecs
-            .create_entity()
-            .with(pos)
-            .with(Name {
-                name: monster.name.clone(),
-            })
-            .with(Monster {
-                description: monster.description.clone(),
-                drop_type: monster.drop_type.clone(),
-            });
+        let entity = ecs.create_entity();
+        spawn_monster(entity, pos, monster, alert_state).build();
+        break;
+    }
+}
 
-        match &monster.renderable {
-            Some(renderable) => {
-                entity = entity.with(Renderable {
-                    glyph: renderable.glyph.chars().next().unwrap_or('!'),
-                    fg: renderable
-                        .fg
-                        .clone()
-                        .map(|fg| color_from_hex(fg.as_str()).unwrap())
-                        .unwrap_or(Color::default()),
-                    bg: renderable
-                        .bg
-                        .clone()
-                        .map(|bg| color_from_hex(bg.as_str()).unwrap())
-                        .unwrap_or(Color::default()),
-                    index: renderable.index,
-                })
-            }
-            None => {}
+/// Spawns a single named monster from the master list given a name and position.
+/// Returns `None` if no monster with that name exists.
+pub fn spawn_named_monster(
+    ecs: &mut World,
+    pos: Position,
+    monster_name: &str,
+    alert_state: AlertState,
+) -> Option<Entity> {
+    for monster in MONSTERS.lock().unwrap().iter() {
+        if monster.name == monster_name {
+            let entity = ecs.create_entity();
+            return Some(spawn_monster(entity, pos, monster, alert_state).build());
         }
+    }
+    None
+}
 
-        match &monster.viewshed {
-            Some(viewshed) => {
-                entity = entity.with(Viewshed {
-                    range: viewshed.range,
-                    visible_tiles: Vec::new(),
-                });
-            }
-            None => {}
+pub fn spawn_monster<'a>(
+    entity: EntityBuilder<'a>,
+    pos: Position,
+    monster: &MonsterConfig,
+    alert_state: AlertState,
+) -> EntityBuilder<'a> {
+    let mut entity = entity
+        .with(pos)
+        .with(Name {
+            name: monster.name.clone(),
+        })
+        .with(Monster {
+            description: monster.description.clone(),
+            drop_type: monster.drop_type.clone(),
+        })
+        .with(Alertness { state: alert_state });
+
+    match &monster.renderable {
+        Some(renderable) => {
+            entity = entity.with(Renderable {
+                glyph: renderable.glyph.chars().next().unwrap_or('!'),
+                fg: renderable
+                    .fg
+                    .clone()
+                    .map(|fg| color_from_hex(fg.as_str()).unwrap())
+                    .unwrap_or(Color::default()),
+                bg: renderable
+                    .bg
+                    .clone()
+                    .map(|bg| color_from_hex(bg.as_str()).unwrap())
+                    .unwrap_or(Color::default()),
+                index: renderable.index,
+            })
         }
+        None => {}
+    }
 
-        match &monster.stats {
-            Some(stats) => {
-                entity = entity.with(Stats {
-                    hp: Pool {
-                        current: stats.hp.current,
-                        max: stats.hp.max,
-                    },
-                    mp: Pool {
-                        current: stats.mp.current,
-                        max: stats.mp.max,
-                    },
-                    exp: Pool {
-                        current: stats.exp.current,
-                        max: stats.exp.max,
-                    },
-                    level: stats.level,
-                    strength: stats.strength,
-                    dexterity: stats.dexterity,
-                    constitution: stats.constitution,
-                    intelligence: stats.intelligence,
-                    wisdom: stats.wisdom,
-                    charisma: stats.charisma,
-                });
-            }
-            None => {}
+    match &monster.viewshed {
+        Some(viewshed) => {
+            entity = entity.with(Viewshed {
+                range: viewshed.range,
+                visible_tiles: Vec::new(),
+            });
         }
+        None => {}
+    }
 
-        entity.build();
-        break;
+    match &monster.stats {
+        Some(stats) => {
+            entity = entity.with(Stats {
+                hp: Pool {
+                    current: stats.hp.current,
+                    max: stats.hp.max,
+                },
+                mp: Pool {
+                    current: stats.mp.current,
+                    max: stats.mp.max,
+                },
+                exp: Pool {
+                    current: stats.exp.current,
+                    max: stats.exp.max,
+                },
+                level: stats.level,
+                strength: stats.strength,
+                dexterity: stats.dexterity,
+                constitution: stats.constitution,
+                intelligence: stats.intelligence,
+                wisdom: stats.wisdom,
+                charisma: stats.charisma,
+            });
+        }
+        None => {}
     }
+
+    return entity;
 }
 
 /// Spawns one or more items using a supporting drop table.
 /// Intended to be used to spawn items when monsters are defeated.
+/// Drops roll affixes just like floor loot, scaled by the current floor.
 pub fn spawn_weighted_drop(ecs: &mut World, drop_type: DropType, pos: Position) {
-    let mut rng = ecs.fetch_mut::<RandomNumberGenerator>();
-    let mut drop_spawn_table = RandomTable::new();
-    if let Some(drop) = DROPS
-        .lock()
-        .unwrap()
-        .iter()
-        .find(|drop| drop.drop_type == drop_type)
+    let mut spawned: Vec<Entity> = Vec::new();
     {
-        for drop_choice in drop.drops.iter() {
-            drop_spawn_table.push(drop_choice.name.clone(), drop_choice.weight);
-        }
-        let selected_item = drop_spawn_table.roll(&mut rng);
-        if let Some(item) = ITEMS
+        let mut rng = ecs.fetch_mut::<RandomNumberGenerator>();
+        let mut drop_spawn_table = RandomTable::new();
+        if let Some(drop) = DROPS
             .lock()
             .unwrap()
             .iter()
-            .find(|item| item.name == selected_item)
+            .find(|drop| drop.drop_type == drop_type)
         {
-            let mut entity = ecs.create_entity_unchecked();
-            entity = spawn_item(entity, Some(pos), item);
-            entity.build();
+            for drop_choice in drop.drops.iter() {
+                drop_spawn_table.push(drop_choice.name.clone(), drop_choice.weight);
+            }
+            let selected_item = drop_spawn_table.roll(&mut rng);
+            if let Some(item) = ITEMS
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|item| item.name == selected_item)
+            {
+                for _ in 0..item.bundle.unwrap_or(1) {
+                    let mut entity = ecs.create_entity_unchecked();
+                    entity = spawn_item(entity, Some(pos), item);
+                    spawned.push(entity.build());
+                }
+            }
         }
     }
+    let floor_index = ecs.fetch::<Map>().index;
+    for item in spawned {
+        roll_affixes(ecs, item, floor_index);
+    }
 }
 
 pub fn spawn_item<'a>(
@@ -233,6 +329,7 @@ pub fn spawn_item<'a>(
         .with(Item {
             description: item.description.clone(),
             base_value: item.base_value,
+            weight: item.weight,
         });
 
     if pos.is_some() {
@@ -259,20 +356,12 @@ pub fn spawn_item<'a>(
         None => {}
     }
 
-    match &item.potion {
-        Some(potion) => {
-            entity = entity.with(Potion {
-                potion_type: potion.potion_type,
-                restore_amount: potion.restore_amount,
-            });
-        }
-        None => {}
-    }
-
     match &item.equippable {
         Some(equippable) => {
             entity = entity.with(Equippable {
                 slot: equippable.slot,
+                hands: equippable.hands.unwrap_or(1),
+                light: equippable.light,
             });
         }
         None => {}
@@ -294,12 +383,51 @@ pub fn spawn_item<'a>(
                 damage: parse_dice_expression(&ranged_weapon.damage),
                 range: ranged_weapon.range,
                 damage_type: ranged_weapon.damage_type,
+                ammo: ranged_weapon.ammo,
                 target: None,
             });
         }
         None => {}
     }
 
+    match &item.throwable {
+        Some(throwable) => {
+            entity = entity.with(Throwable {
+                damage: parse_dice_expression(&throwable.damage),
+                damage_type: throwable.damage_type,
+            });
+        }
+        None => {}
+    }
+
+    match &item.fragile {
+        Some(fragile) => {
+            entity = entity.with(Fragile { radius: fragile.radius });
+        }
+        None => {}
+    }
+
+    match &item.ammunition {
+        Some(ammunition) => {
+            entity = entity.with(Ammunition {
+                ammo_type: ammunition.ammo_type,
+                damage_bonus: ammunition.damage_bonus,
+                damage_type: ammunition.damage_type,
+                status: ammunition.status,
+                status_duration: ammunition.status_duration,
+                recovery_chance: ammunition.recovery_chance,
+            });
+        }
+        None => {}
+    }
+
+    match item.durability {
+        Some(durability) => {
+            entity = entity.with(Durability { current: durability, max: durability });
+        }
+        None => {}
+    }
+
     match &item.magic_weapon {
         Some(magic_weapon) => {
             entity = entity.with(MagicWeapon {
@@ -310,16 +438,43 @@ pub fn spawn_item<'a>(
         None => {}
     }
 
-    match &item.spells {
-        Some(spells) => {
-            entity = entity.with(SpellKnowledge {
-                spells: spells
+    match &item.effects {
+        Some(effects) => {
+            entity = entity.with(Consumable {
+                effects: effects
                     .iter()
-                    .map(|s| Spell {
-                        name: s.name.clone(),
-                        mp_cost: s.mp_cost,
-                        damage: parse_dice_expression(&s.damage),
-                        damage_type: s.damage_type,
+                    .map(|effect| match effect {
+                        EffectConfig::Heal { amount } => ItemEffect::Heal { amount: *amount },
+                        EffectConfig::RestoreMana { amount } => {
+                            ItemEffect::RestoreMana { amount: *amount }
+                        }
+                        EffectConfig::Damage {
+                            damage,
+                            damage_type,
+                            area,
+                        } => ItemEffect::Damage {
+                            damage: parse_dice_expression(damage),
+                            damage_type: *damage_type,
+                            area: Area {
+                                shape: area.shape,
+                                size: area.size,
+                            },
+                        },
+                        EffectConfig::Teleport => ItemEffect::Teleport,
+                        EffectConfig::ApplyStatus { status, duration } => ItemEffect::ApplyStatus {
+                            status: *status,
+                            duration: *duration,
+                        },
+                        EffectConfig::RevealMap => ItemEffect::RevealMap,
+                        EffectConfig::LearnSpell(spell) => ItemEffect::LearnSpell {
+                            spell: spell_from_config(spell),
+                        },
+                        EffectConfig::Summon { name, count } => ItemEffect::Summon {
+                            name: name.clone(),
+                            count: *count,
+                        },
+                        EffectConfig::Identify => ItemEffect::Identify,
+                        EffectConfig::RemoveCurse => ItemEffect::RemoveCurse,
                     })
                     .collect(),
             });
@@ -331,6 +486,23 @@ pub fn spawn_item<'a>(
         Some(armor) => {
             entity = entity.with(Armor {
                 defense: armor.defense,
+                noise: armor.noise,
+            });
+        }
+        None => {}
+    }
+
+    match &item.modifiers {
+        Some(modifiers) => {
+            entity = entity.with(modifiers_from_config(modifiers));
+        }
+        None => {}
+    }
+
+    match &item.gold {
+        Some(gold) => {
+            entity = entity.with(Gold {
+                amount: parse_dice_expression(gold),
             });
         }
         None => {}
@@ -353,31 +525,12 @@ pub fn spawn_item<'a>(
         }
         None => {}
     }
-
-    match &item.scroll {
-        Some(scroll) => match scroll.scroll_type {
-            ScrollType::MagicMapper => {
-                entity = entity.with(MagicMapper {});
-            }
-        },
-        None => {}
-    }
     return entity;
 }
 
+/// Spawns the Oakwood merchant, stocked from their table in `vendors.yaml`.
 pub fn spawn_npc_merchant(ecs: &mut World, x: i32, y: i32) -> Entity {
-    let health_potion = spawn_named_item(
-        ecs,
-        None,
-        "Potion of pathetically minor healing".to_string(),
-    );
-    let mana_potion = spawn_named_item(ecs, None, "Potion of pathetically minor mana".to_string());
-    let steel_shield = spawn_named_item(ecs, None, "Steel Shield".to_string());
-    let steel_chestplate = spawn_named_item(ecs, None, "Steel Chestplate".to_string());
-    let steel_gauntlets = spawn_named_item(ecs, None, "Steel Gauntlets".to_string());
-    let steel_chausses = spawn_named_item(ecs, None, "Steel Chausses".to_string());
-    let steel_boots = spawn_named_item(ecs, None, "Steel Boots".to_string());
-    return ecs
+    let merchant = ecs
         .create_entity()
         .with(Position { x: x, y: y })
         .with(Renderable {
@@ -412,23 +565,64 @@ pub fn spawn_npc_merchant(ecs: &mut World, x: i32, y: i32) -> Entity {
             charisma: 10,
         })
         .with(Inventory {
-            gold: 10,
+            gold: 0,
             items: IndexMap::new(),
             index: 0,
+            selected: HashSet::new(),
+            view: InventoryView::default(),
         })
         .with(Npc { dialogue: None })
         .with(Vendor {
-            items: vec![
-                health_potion,
-                mana_potion,
-                steel_shield,
-                steel_chestplate,
-                steel_gauntlets,
-                steel_chausses,
-                steel_boots,
-            ],
+            items: vec![],
+            buyback: vec![],
         })
         .build();
+    restock_vendor(ecs, merchant);
+    return merchant;
+}
+
+/// Clears out whatever the vendor has left, along with anything the player sold them,
+/// and rolls fresh stock from their table in `vendors.yaml`. Their purse is topped
+/// back up to the table's gold, but never taken away from.
+pub fn restock_vendor(ecs: &mut World, vendor_entity: Entity) {
+    let vendor_name = match ecs.read_storage::<Name>().get(vendor_entity) {
+        Some(name) => name.name.clone(),
+        None => return,
+    };
+    let Some((gold, stock)) = VENDORS.lock().unwrap().iter().find(|vendor| vendor.name == vendor_name).map(|vendor| {
+        let stock: Vec<(String, i32, i32)> = vendor
+            .stock
+            .iter()
+            .map(|line| (line.item.clone(), line.count.unwrap_or(1), line.chance.unwrap_or(100)))
+            .collect();
+        (vendor.gold, stock)
+    }) else {
+        return;
+    };
+
+    let leftovers: Vec<Entity> = match ecs.read_storage::<Vendor>().get(vendor_entity) {
+        Some(vendor) => vendor.items.iter().chain(vendor.buyback.iter()).copied().collect(),
+        None => return,
+    };
+    ecs.delete_entities(&leftovers).expect("Unable to clear out old vendor stock");
+
+    let mut items: Vec<Entity> = Vec::new();
+    for (item_name, count, chance) in stock.iter() {
+        if ecs.write_resource::<RandomNumberGenerator>().roll_dice(1, 100) > *chance {
+            continue;
+        }
+        for _ in 0..*count {
+            items.push(spawn_named_item(ecs, None, item_name.clone()));
+        }
+    }
+
+    if let Some(vendor) = ecs.write_storage::<Vendor>().get_mut(vendor_entity) {
+        vendor.items = items;
+        vendor.buyback.clear();
+    }
+    if let Some(inventory) = ecs.write_storage::<Inventory>().get_mut(vendor_entity) {
+        inventory.gold = i32::max(inventory.gold, gold);
+    }
 }
 
 pub fn spawn_npc_captain(ecs: &mut World, x: i32, y: i32) -> Entity {
@@ -459,6 +653,8 @@ pub fn spawn_npc_captain(ecs: &mut World, x: i32, y: i32) -> Entity {
             gold: 10,
             items: IndexMap::new(),
             index: 0,
+            selected: HashSet::new(),
+            view: InventoryView::default(),
         })
         .with(Npc {
             dialogue: Some(vec![
@@ -479,6 +675,24 @@ pub fn spawn_npc_captain(ecs: &mut World, x: i32, y: i32) -> Entity {
         .build();
 }
 
+/// Spawns a shrine in the middle of the given room, see `Shrine`.
+pub fn spawn_shrine(ecs: &mut World, room: &Rect) -> Entity {
+    let (x, y) = room.center();
+    return ecs
+        .create_entity()
+        .with(Position { x: x, y: y })
+        .with(Renderable {
+            glyph: '_',
+            bg: Color::Black,
+            fg: color_from_hex("#FFD700").expect("Unable to parse shrine hex color"),
+            index: 1,
+        })
+        .with(Name { name: "Shrine".to_string() })
+        .with(BlocksTile {})
+        .with(Shrine { used: false })
+        .build();
+}
+
 pub fn spawn_player(ecs: &mut World, x: i32, y: i32) -> Entity {
     return ecs
         .create_entity()
@@ -523,11 +737,45 @@ pub fn spawn_player(ecs: &mut World, x: i32, y: i32) -> Entity {
             gold: 0,
             items: IndexMap::new(),
             index: 0,
+            selected: HashSet::new(),
+            view: InventoryView::default(),
         })
-        .with(SpellKnowledge { spells: vec![] })
+        .with(SpellKnowledge::default())
+        .with(Feats::default())
         .build();
 }
 
+/// Converts a spell definition from config into the `Spell` known by an entity.
+pub fn modifiers_from_config(modifiers: &ModifiersConfig) -> Modifiers {
+    Modifiers {
+        strength: modifiers.strength,
+        dexterity: modifiers.dexterity,
+        constitution: modifiers.constitution,
+        intelligence: modifiers.intelligence,
+        wisdom: modifiers.wisdom,
+        charisma: modifiers.charisma,
+        max_hp: modifiers.max_hp,
+        viewshed_range: modifiers.viewshed_range,
+        mp_regeneration: modifiers.mp_regeneration,
+        speed: modifiers.speed,
+        to_hit: modifiers.to_hit,
+        resistances: modifiers.resistances.clone(),
+    }
+}
+
+pub fn spell_from_config(spell: &SpellConfig) -> Spell {
+    Spell {
+        name: spell.name.clone(),
+        mp_cost: spell.mp_cost,
+        damage: parse_dice_expression(&spell.damage),
+        damage_type: spell.damage_type,
+        area: spell.area.as_ref().map(|area| Area {
+            shape: area.shape,
+            size: area.size,
+        }),
+    }
+}
+
 fn color_from_hex(hex: &str) -> Result<Color, &'static str> {
     let hex = hex.strip_prefix('#').ok_or("missing #")?;
     if hex.len() != 6 {
@@ -0,0 +1,72 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::{
+    App, RootScreen, RunState,
+    generate::{
+        character::{ATTRIBUTE_NAMES, CreationStep, MAX_NAME_LENGTH, apply_character},
+        spawn::CLASSES,
+    },
+    input::menu::start_new_game,
+};
+
+pub fn handle_character_creation_key_event(app: &mut App, key_event: KeyEvent) -> Option<RunState> {
+    match app.character.step {
+        CreationStep::Name => match key_event.code {
+            KeyCode::Char(c) if c.is_alphanumeric() || c == ' ' || c == '-' || c == '\'' => {
+                if app.character.name.chars().count() < MAX_NAME_LENGTH {
+                    app.character.name.push(c);
+                }
+            }
+            KeyCode::Backspace => {
+                app.character.name.pop();
+            }
+            KeyCode::Enter => app.character.step = CreationStep::Class,
+            KeyCode::Esc => app.root_screen = RootScreen::Menu,
+            _ => {}
+        },
+
+        CreationStep::Class => {
+            let class_count = CLASSES.lock().unwrap().len();
+            match key_event.code {
+                KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('k') => {
+                    if app.character.class_index > 0 {
+                        app.character.class_index -= 1;
+                    }
+                }
+                KeyCode::Down | KeyCode::Char('s') | KeyCode::Char('j') => {
+                    if app.character.class_index + 1 < class_count {
+                        app.character.class_index += 1;
+                    }
+                }
+                KeyCode::Enter => app.character.step = CreationStep::Attributes,
+                KeyCode::Esc => app.character.step = CreationStep::Name,
+                _ => {}
+            }
+        }
+
+        CreationStep::Attributes => match key_event.code {
+            KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('k') => {
+                if app.character.attribute_index > 0 {
+                    app.character.attribute_index -= 1;
+                }
+            }
+            KeyCode::Down | KeyCode::Char('s') | KeyCode::Char('j') => {
+                if app.character.attribute_index + 1 < ATTRIBUTE_NAMES.len() {
+                    app.character.attribute_index += 1;
+                }
+            }
+            KeyCode::Right | KeyCode::Char('d') | KeyCode::Char('l') => app.character.increase_attribute(),
+            KeyCode::Left | KeyCode::Char('a') | KeyCode::Char('h') => app.character.decrease_attribute(),
+            KeyCode::Char('r') => app.character.roll_attributes(),
+            KeyCode::Char('p') => app.character.reset_attributes(),
+            KeyCode::Enter => {
+                start_new_game(app);
+                let character = app.character.clone();
+                apply_character(&mut app.ecs, &character);
+            }
+            KeyCode::Esc => app.character.step = CreationStep::Class,
+            _ => {}
+        },
+    }
+    return None;
+}
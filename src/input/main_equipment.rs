@@ -0,0 +1,99 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use specs::prelude::*;
+
+use crate::{
+    App, RunState, Screen,
+    component::{EquipmentSlot, WantsToConsumeItem},
+    logbook::logbook::Logger,
+    system::inventory_system::{get_equipped_in_slot, get_slot_candidates},
+};
+
+pub fn handle_main_equipment_key_event(
+    app: &mut App,
+    index: usize,
+    candidate: Option<usize>,
+    key_event: KeyEvent,
+) -> Option<RunState> {
+    let player_entity = *app.ecs.fetch::<Entity>();
+    let slot = EquipmentSlot::ALL[index];
+    let candidates = get_slot_candidates(&app.ecs, player_entity, slot);
+
+    match key_event.code {
+        KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('k') => {
+            app.screen = match candidate {
+                Some(candidate) => Screen::Equipment { index, candidate: Some(candidate.saturating_sub(1)) },
+                None => Screen::Equipment { index: index.saturating_sub(1), candidate: None },
+            };
+            return None;
+        }
+
+        KeyCode::Down | KeyCode::Char('s') | KeyCode::Char('j') => {
+            app.screen = match candidate {
+                Some(candidate) => Screen::Equipment {
+                    index,
+                    candidate: Some(usize::min(candidate + 1, candidates.len().saturating_sub(1))),
+                },
+                None => Screen::Equipment {
+                    index: usize::min(index + 1, EquipmentSlot::ALL.len() - 1),
+                    candidate: None,
+                },
+            };
+            return None;
+        }
+
+        // Go back to the slots, or close the screen if already there
+        KeyCode::Esc | KeyCode::Left | KeyCode::Char('h') => {
+            app.screen = match candidate {
+                Some(_) => Screen::Equipment { index, candidate: None },
+                None => Screen::Explore,
+            };
+            return None;
+        }
+
+        KeyCode::Char('e') => {
+            app.screen = Screen::Explore;
+            return None;
+        }
+
+        // Pick from the backpack items that fit the slot, or equip the chosen one
+        KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+            match candidate {
+                Some(candidate) => {
+                    if let Some(item) = candidates.get(candidate) {
+                        try_toggle_equipped(&mut app.ecs, *item, Some(slot));
+                    }
+                    app.screen = Screen::Equipment { index, candidate: None };
+                }
+                None if candidates.is_empty() => {
+                    Logger::new()
+                        .append(format!("Nothing in your backpack fits your {:?} slot.", slot))
+                        .log();
+                }
+                None => {
+                    app.screen = Screen::Equipment { index, candidate: Some(0) };
+                }
+            }
+            return None;
+        }
+
+        // Take off whatever is in the slot
+        KeyCode::Char('u') | KeyCode::Backspace => {
+            if let Some(item) = get_equipped_in_slot(&app.ecs, player_entity, slot) {
+                try_toggle_equipped(&mut app.ecs, item, None);
+            }
+            return None;
+        }
+        _ => None,
+    }
+}
+
+/// Equips the given item, or takes it off if it is already equipped.
+/// This goes through the same path as using the item from the inventory,
+/// so curses, slot swaps and two-handed weapons are handled by the `InventorySystem`.
+/// Giving a slot lets a light weapon go into the off hand.
+fn try_toggle_equipped(ecs: &mut World, item: Entity, slot: Option<EquipmentSlot>) {
+    let player_entity = ecs.fetch::<Entity>();
+    ecs.write_storage::<WantsToConsumeItem>()
+        .insert(*player_entity, WantsToConsumeItem { item, slot, target: None })
+        .expect("Unable to insert item consumption into ecs");
+}
@@ -1,4 +1,5 @@
 use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::style::Color;
 use rltk::Point;
 use specs::prelude::*;
 use std::cmp::{max, min};
@@ -6,10 +7,26 @@ use std::cmp::{max, min};
 use crate::{
     App, RunState, Screen,
     component::{
-        Attack, AttackType, EquipmentSlot, Equipped, Item, MagicWeapon, Monster, Npc, Player, Pool, Position, RangedWeapon, SpellKnowledge, Stats, Vendor, WantsToPickupItem
+        Ammunition, Armor, Attack, AttackType, DerivedStats, EquipmentSlot, Equipped, Gold, Item,
+        MagicWeapon, Monster, Name, Noise, Npc, Player, Pool, Position, RangedWeapon, Renderable,
+        SPELL_HOTKEYS, Shrine, SpellKnowledge, Stats, Throwing, Vendor, Viewshed, WantsToPickupItem,
+        WantsToThrowItem,
+    },
+    generate::{
+        config::{IdentificationType, ItemCategory},
+        identification::IdentificationTable,
+        map::{Map, TileType},
+        spawn::{AUTO_PICKUP, ITEMS},
+    },
+    input::{main_pickup::try_open_pickup, main_spellbook::try_ready_spell},
+    logbook::logbook::Logger,
+    system::{
+        alertness_system::FOOTSTEP_NOISE,
+        derived_stats_system::effective_max_hp,
+        inventory_system::{get_quivered_ammo, get_tile_items, lift_curses},
+        ranged_combat_system::{get_eligible_ranged_tiles, has_line_of_sight},
+        throw_system::throw_range,
     },
-    generate::map::{Map, TileType},
-    logbook::logbook::Logger, system::ranged_combat_system::{get_eligible_ranged_tiles, has_line_of_sight},
 };
 
 pub fn handle_main_explore_key_event(
@@ -20,7 +37,10 @@ pub fn handle_main_explore_key_event(
     match key_event.code {
         KeyCode::Esc => match runstate {
             RunState::Examining { index: _ } => Some(RunState::AwaitingInput),
-            RunState::FreeAiming { index: _ } => Some(RunState::AwaitingInput),
+            RunState::FreeAiming { index: _ } => {
+                app.ecs.write_storage::<Throwing>().clear();
+                Some(RunState::AwaitingInput)
+            }
             RunState::Dialogue { npc: _ } => Some(RunState::AwaitingInput),
             RunState::AwaitingInput => {
                 app.screen = Screen::Quit { quit: false };
@@ -75,11 +95,49 @@ pub fn handle_main_explore_key_event(
 
         KeyCode::Tab => try_cycle_targeting(&mut app.ecs),
         KeyCode::Char(' ') => try_free_aim(app),
+        KeyCode::Char('1') | KeyCode::Char('t') if is_throwing(&app.ecs) => try_throw_target(app),
         KeyCode::Char('1') => try_ranged_target(app),
         KeyCode::Char('2') => try_magic_target(app),
-        KeyCode::Char('g') => try_get_item(&mut app.ecs),
+        KeyCode::Char('g') => match runstate {
+            RunState::AwaitingInput => try_get_item(app),
+            RunState::Examining { index } => {
+                let (x, y) = app.ecs.fetch::<Map>().idx_xy(index);
+                try_open_pickup(app, x, y)
+            }
+            _ => None,
+        },
+        KeyCode::Char('z') => match runstate {
+            RunState::AwaitingInput => Some(RunState::PlayerTurn),
+            _ => None,
+        },
+        KeyCode::Char('p') => {
+            toggle_auto_pickup();
+            return None;
+        }
+        KeyCode::Char('r') => match runstate {
+            RunState::AwaitingInput => try_rest(app),
+            _ => None,
+        },
         KeyCode::Char('i') => {
             app.screen = Screen::Inventory;
+            return try_put_away_throw(&mut app.ecs);
+        }
+        KeyCode::Char('e') => {
+            app.screen = Screen::Equipment { index: 0, candidate: None };
+            return try_put_away_throw(&mut app.ecs);
+        }
+        KeyCode::Char('m') => {
+            app.screen = Screen::Spellbook {
+                index: {
+                    let player = app.ecs.fetch::<Entity>();
+                    let spell_knowledge = app.ecs.read_storage::<SpellKnowledge>();
+                    spell_knowledge.get(*player).map(|s| s.active).unwrap_or(0)
+                },
+            };
+            return try_put_away_throw(&mut app.ecs);
+        }
+        KeyCode::Char(key) if SPELL_HOTKEYS.contains(&key) => {
+            try_ready_spell_hotkey(&mut app.ecs, key);
             return None;
         }
         KeyCode::Char('.') => try_next_level(&mut app.ecs, true),
@@ -92,11 +150,12 @@ pub fn handle_main_explore_key_event(
             let ecs = &mut app.ecs;
             let player = ecs.fetch::<Entity>();
             let mut stats = ecs.write_storage::<Stats>();
+            let derived_stats = ecs.read_storage::<DerivedStats>();
             let player_stats = stats
                 .get_mut(*player)
                 .expect("Unable to access player stats");
             player_stats.hp = Pool {
-                current: player_stats.hp.max,
+                current: effective_max_hp(player_stats, derived_stats.get(*player)),
                 max: player_stats.hp.max,
             };
             return None;
@@ -127,10 +186,68 @@ fn try_move_examine(app: &mut App, delta_x: i32, delta_y: i32) -> Option<RunStat
     }
 }
 
+///
+/// R E S T I N G
+/// 
+/// Starts passing turns until the player is fully rested.
+/// Resting is refused outright if there is nothing to recover or a monster is in view,
+/// the game loop is responsible for interrupting it later on.
+fn try_rest(app: &mut App) -> Option<RunState> {
+    if let Some(monster_name) = visible_monster(&app.ecs) {
+        Logger::new()
+            .append("You can't rest with ")
+            .append_with_color(Color::Red, monster_name)
+            .append(" nearby.")
+            .log();
+        return None;
+    }
+    if is_fully_rested(&app.ecs) {
+        Logger::new().append("You are already fully rested.").log();
+        return None;
+    }
+    Logger::new().append("You settle down to rest.").log();
+    app.resting = true;
+    return Some(RunState::PlayerTurn);
+}
+
+/// Returns the name of a monster the player can currently see, if any.
+pub fn visible_monster(ecs: &World) -> Option<String> {
+    let player_entity = ecs.fetch::<Entity>();
+    let viewsheds = ecs.read_storage::<Viewshed>();
+    let positions = ecs.read_storage::<Position>();
+    let monsters = ecs.read_storage::<Monster>();
+    let names = ecs.read_storage::<Name>();
+    let viewshed = viewsheds.get(*player_entity)?;
+    (&positions, &monsters, &names)
+        .join()
+        .find(|(position, _, _)| viewshed.visible_tiles.contains(&Point::new(position.x, position.y)))
+        .map(|(_, _, name)| name.name.clone())
+}
+
+/// Returns true if the player has nothing left to recover by resting.
+pub fn is_fully_rested(ecs: &World) -> bool {
+    let player_entity = ecs.fetch::<Entity>();
+    let stats = ecs.read_storage::<Stats>();
+    let derived_stats = ecs.read_storage::<DerivedStats>();
+    stats
+        .get(*player_entity)
+        .map(|stat| {
+            stat.hp.current >= effective_max_hp(stat, derived_stats.get(*player_entity))
+                && stat.mp.current >= stat.mp.max
+        })
+        .unwrap_or(true)
+}
+
 /// 
 /// M O V E M E N T
 /// 
 fn try_move_player(delta_x: i32, delta_y: i32, app: &mut App) -> Option<RunState> {
+    // Shrines are prayed at rather than walked into
+    if let Some(shrine) = shrine_at(&app.ecs, delta_x, delta_y) {
+        pray_at_shrine(&app.ecs, shrine);
+        return Some(RunState::PlayerTurn);
+    }
+
     let entities = app.ecs.entities();
     let mut positions = app.ecs.write_storage::<Position>();
     let mut players = app.ecs.write_storage::<Player>();
@@ -138,8 +255,12 @@ fn try_move_player(delta_x: i32, delta_y: i32, app: &mut App) -> Option<RunState
     let monsters = app.ecs.read_storage::<Monster>();
     let npcs = app.ecs.read_storage::<Npc>();
     let vendors = app.ecs.read_storage::<Vendor>();
+    let equipped = app.ecs.read_storage::<Equipped>();
+    let armor = app.ecs.read_storage::<Armor>();
+    let mut noises = app.ecs.write_storage::<Noise>();
     let mut player_position = app.ecs.write_resource::<Point>();
     let map = app.ecs.fetch::<Map>();
+    let mut moved = false;
 
     for (entity, pos, _player) in (&entities, &mut positions, &mut players).join() {
         let next_pos_x = min(map.width - 1, max(0, pos.x + delta_x));
@@ -157,7 +278,8 @@ fn try_move_player(delta_x: i32, delta_y: i32, app: &mut App) -> Option<RunState
                             entity,
                             Attack {
                                 attack_type: AttackType::Melee,
-                                target: *target,
+                                target: Some(*target),
+                                aim: None,
                                 spell: None,
                             },
                         )
@@ -173,6 +295,7 @@ fn try_move_player(delta_x: i32, delta_y: i32, app: &mut App) -> Option<RunState
                         vendor_index: 0,
                         player_index: 0,
                         is_buying: true,
+                        is_buyback: false,
                     };
                     return None;
                 }
@@ -186,11 +309,150 @@ fn try_move_player(delta_x: i32, delta_y: i32, app: &mut App) -> Option<RunState
             pos.y = next_pos_y;
             player_position.x = next_pos_x;
             player_position.y = next_pos_y;
+
+            // Heavier armor clanks along with every step
+            let armor_noise: i32 = (&equipped, &armor)
+                .join()
+                .filter(|(equipped, _)| equipped.owner == entity)
+                .map(|(_, armor)| armor.noise)
+                .sum();
+            Noise::new(&mut noises, entity, FOOTSTEP_NOISE + armor_noise);
+            moved = true;
         }
     }
+
+    drop(positions);
+    drop(player_position);
+    if moved {
+        try_auto_pickup(&app.ecs);
+    }
     return Some(RunState::PlayerTurn);
 }
 
+///
+/// A U T O  P I C K U P
+/// 
+/// Collects everything on the player's tile that matches one of the
+/// auto-pickup rules, in the same turn the player stepped onto it.
+fn try_auto_pickup(ecs: &World) {
+    let auto_pickup = AUTO_PICKUP.lock().unwrap();
+    if !auto_pickup.enabled {
+        return;
+    }
+    let player_entity = ecs.fetch::<Entity>();
+    let player_pos = *ecs.fetch::<Point>();
+    let items = ecs.read_storage::<Item>();
+    let ammunition = ecs.read_storage::<Ammunition>();
+    let golds = ecs.read_storage::<Gold>();
+    let identification = ecs.fetch::<IdentificationTable>();
+
+    let collected: Vec<Entity> = get_tile_items(ecs, player_pos.x, player_pos.y)
+        .into_iter()
+        .filter(|(signature, stack)| {
+            let Some(item) = stack.first() else {
+                return false;
+            };
+            let identification_type = ITEMS
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|config| config.name == signature.name)
+                .and_then(|config| config.identification.clone());
+            let in_category = auto_pickup.categories.iter().any(|category| match category {
+                ItemCategory::Potion => identification_type == Some(IdentificationType::Potion),
+                ItemCategory::Scroll => identification_type == Some(IdentificationType::Scroll),
+                ItemCategory::Ammunition => ammunition.contains(*item),
+                ItemCategory::Gold => golds.contains(*item),
+            });
+            let is_valuable = match (auto_pickup.min_value, items.get(*item)) {
+                (Some(min_value), Some(item)) => item.base_value >= min_value,
+                _ => false,
+            };
+            let display_name = identification.display_name(&signature.name);
+            in_category || is_valuable || auto_pickup.patterns.iter().any(|pattern| pattern.is_match(&display_name))
+        })
+        .flat_map(|(_, stack)| stack.into_iter())
+        .collect();
+
+    if collected.is_empty() {
+        return;
+    }
+    ecs.write_storage::<WantsToPickupItem>()
+        .insert(
+            *player_entity,
+            WantsToPickupItem {
+                collected_by: *player_entity,
+                items: collected,
+                auto: true,
+            },
+        )
+        .expect("Unable to insert item pickup into ecs");
+}
+
+/// Turns auto-pickup on or off for the rest of the session.
+fn toggle_auto_pickup() {
+    let mut auto_pickup = AUTO_PICKUP.lock().unwrap();
+    auto_pickup.enabled = !auto_pickup.enabled;
+    Logger::new()
+        .append("Auto-pickup is now ")
+        .append_with_color(
+            if auto_pickup.enabled { Color::Green } else { Color::Red },
+            if auto_pickup.enabled { "on." } else { "off." },
+        )
+        .log();
+}
+
+///
+/// S H R I N E S
+/// 
+fn shrine_at(ecs: &World, delta_x: i32, delta_y: i32) -> Option<Entity> {
+    let map = ecs.fetch::<Map>();
+    let shrines = ecs.read_storage::<Shrine>();
+    let player_position = ecs.fetch::<Point>();
+    let (x, y) = (player_position.x + delta_x, player_position.y + delta_y);
+    if x < 0 || y < 0 || x >= map.width || y >= map.height {
+        return None;
+    }
+    return map.tile_content[map.xy_idx(x, y)]
+        .iter()
+        .find(|entity| shrines.contains(**entity))
+        .copied();
+}
+
+/// Lifts the curses from the player's equipment, after which the shrine goes dark.
+/// Praying without any curses to lift leaves the shrine untouched for later.
+fn pray_at_shrine(ecs: &World, shrine_entity: Entity) {
+    let player_entity = *ecs.fetch::<Entity>();
+    {
+        let shrines = ecs.read_storage::<Shrine>();
+        if shrines.get(shrine_entity).is_some_and(|shrine| shrine.used) {
+            Logger::new().append("The shrine is cold and silent.").log();
+            return;
+        }
+    }
+
+    let uncursed = lift_curses(ecs, player_entity);
+    if uncursed.is_empty() {
+        Logger::new()
+            .append("You kneel at the shrine, but you carry no curse for it to lift.")
+            .log();
+        return;
+    }
+    for item_name in uncursed {
+        Logger::new()
+            .append("You pray at the shrine, and the curse on the ")
+            .append_with_color(Color::Green, item_name)
+            .append(" is lifted.")
+            .log();
+    }
+    if let Some(shrine) = ecs.write_storage::<Shrine>().get_mut(shrine_entity) {
+        shrine.used = true;
+    }
+    if let Some(renderable) = ecs.write_storage::<Renderable>().get_mut(shrine_entity) {
+        renderable.fg = Color::DarkGray;
+    }
+}
+
 ///
 /// D E S C E N D  /  A S C E N D
 /// 
@@ -219,32 +481,29 @@ fn try_next_level(ecs: &mut World, is_descending: bool) -> Option<RunState> {
 ///
 /// I T E M  P I C K U P
 /// 
-fn try_get_item(ecs: &mut World) -> Option<RunState> {
-    let player_pos = ecs.fetch::<Point>();
-    let player_entity = ecs.fetch::<Entity>();
-    let entities = ecs.entities();
-    let items = ecs.read_storage::<Item>();
-    let positions = ecs.read_storage::<Position>();
-
-    let mut target_item: Option<Entity> = None;
-    for (item_entity, _item, position) in (&entities, &items, &positions).join() {
-        if position.x == player_pos.x && position.y == player_pos.y {
-            target_item = Some(item_entity);
-        }
+/// Picks up whatever is lying on the player's tile.
+/// A single item (or a single stack of them) is picked up right away,
+/// anything more opens the pick-up overlay so the player can choose.
+fn try_get_item(app: &mut App) -> Option<RunState> {
+    let player_pos = *app.ecs.fetch::<Point>();
+    let tile_items = get_tile_items(&app.ecs, player_pos.x, player_pos.y);
+    if tile_items.len() > 1 {
+        return try_open_pickup(app, player_pos.x, player_pos.y);
     }
-
-    match target_item {
+    match tile_items.into_values().next() {
         None => Logger::new()
             .append("There is nothing here to pick up.")
             .log(),
-        Some(item) => {
-            let mut pickup = ecs.write_storage::<WantsToPickupItem>();
+        Some(items) => {
+            let player_entity = app.ecs.fetch::<Entity>();
+            let mut pickup = app.ecs.write_storage::<WantsToPickupItem>();
             pickup
                 .insert(
                     *player_entity,
                     WantsToPickupItem {
                         collected_by: *player_entity,
-                        items: vec![item],
+                        items,
+                        auto: false,
                     },
                 )
                 .expect("Unable to insert item pickup into ecs");
@@ -386,6 +645,11 @@ fn try_cycle_targeting(ecs: &mut World) -> Option<RunState> {
 /// E N T E R  F R E E  A I M
 /// 
 fn try_free_aim(app: &mut App) -> Option<RunState> {
+    // Putting away a readied throw
+    if is_throwing(&app.ecs) {
+        return try_put_away_throw(&mut app.ecs);
+    }
+
     let map = app.ecs.fetch::<Map>();
     let player_pos = app.ecs.fetch::<Point>();
     let player_entity = app.ecs.fetch::<Entity>();
@@ -415,6 +679,19 @@ fn try_move_free_aim(app: &mut App, delta_x: i32, delta_y: i32) -> Option<RunSta
             let map = app.ecs.fetch::<Map>();
             let player_pos = app.ecs.fetch::<Point>();
             let player_entity = app.ecs.fetch::<Entity>();
+            let (x, y) = map.idx_xy(index);
+            let target_index = map.xy_idx(x + delta_x, y + delta_y);
+
+            // Throws are limited by the item being thrown rather than the equipped weapon
+            if let Some(throwing) = app.ecs.read_storage::<Throwing>().get(*player_entity) {
+                let range = throw_range(&app.ecs, *player_entity, throwing.item);
+                let eligible_tiles = get_eligible_ranged_tiles(&map, &player_pos, range);
+                if eligible_tiles.contains(&target_index) {
+                    return Some(RunState::FreeAiming { index: target_index });
+                }
+                return None;
+            }
+
             let entities = app.ecs.entities();
             let equipped = app.ecs.read_storage::<Equipped>();
             let ranged_weapons = app.ecs.read_storage::<RangedWeapon>();
@@ -426,8 +703,6 @@ fn try_move_free_aim(app: &mut App, delta_x: i32, delta_y: i32) -> Option<RunSta
                 let magic = magic_weapons.get(entity);
                 let range = ranged.map(|r| r.range).unwrap_or_else(|| magic.map(|m| m.range).unwrap_or(0));
                 if equipped.slot == EquipmentSlot::Weapon && equipped.owner == *player_entity {
-                    let (x, y) = map.idx_xy(index);
-                    let target_index = map.xy_idx(x + delta_x, y + delta_y);
                     let eligible_tiles = get_eligible_ranged_tiles(&map, &player_pos, range);
                     if eligible_tiles.contains(&target_index) {
                         return Some(RunState::FreeAiming { index: target_index });
@@ -453,18 +728,27 @@ fn try_ranged_target(app: &mut App) -> Option<RunState> {
     let spell_knowledge = app.ecs.read_storage::<SpellKnowledge>();
     let mut ranged_weapons = app.ecs.write_storage::<RangedWeapon>();
     let mut magic_weapons = app.ecs.write_storage::<MagicWeapon>();
+    let ammunition = app.ecs.read_storage::<Ammunition>();
     let mut attacks = app.ecs.write_storage::<Attack>();
     let ranged_mask = ranged_weapons.mask().clone() | magic_weapons.mask().clone();
 
-    for (entity, equipped, _) in
+    for (entity, weapon, _) in
         (&entities, &equipped, &ranged_mask).join()
     {
-        if equipped.slot != EquipmentSlot::Weapon || equipped.owner != *player_entity {
+        if weapon.slot != EquipmentSlot::Weapon || weapon.owner != *player_entity {
             continue;
         }
         let ranged_weapon = ranged_weapons.get_mut(entity);
         let magic_weapon = magic_weapons.get_mut(entity);
         let attack_type = if ranged_weapon.is_some() { AttackType::Ranged } else { AttackType::Magic };
+        if let Some(ammo_type) = ranged_weapon.as_ref().and_then(|r| r.ammo) {
+            if get_quivered_ammo(*player_entity, ammo_type, &entities, &equipped, &ammunition).is_none() {
+                Logger::new()
+                    .append(format!("You have no {:?} ammunition in your quiver!", ammo_type))
+                    .log();
+                return None;
+            }
+        }
         let target = ranged_weapon.map(|r| r.target)
             .unwrap_or_else(|| magic_weapon.map(|m| m.target).unwrap_or(None));
 
@@ -472,38 +756,42 @@ fn try_ranged_target(app: &mut App) -> Option<RunState> {
             AttackType::Magic => {
                 spell_knowledge.get(*player_entity)
                     .expect("uhhh")
-                    .spells
-                    .first()
+                    .active_spell()
                     .map(|s| s.clone())
             }
             _ => None
         };
+        let is_area_spell = spell.as_ref().is_some_and(|s| s.area.is_some());
 
         match app.runstate {                
             RunState::FreeAiming { index } => {
-                match map.tile_content[index].iter().next() {
-                    Some(entity) => {
+                let (aim_x, aim_y) = map.idx_xy(index);
+                match (map.tile_content[index].iter().next(), is_area_spell) {
+                    (None, false) => return None,
+                    (entity, _) => {
                         attacks.insert(
                             *player_entity,
                             Attack {
                                 attack_type: attack_type,
-                                target: *entity,
+                                target: entity.copied(),
+                                aim: Some(Point::new(aim_x, aim_y)),
                                 spell: spell,
                             }
                         ).expect("Unable to add attack");
                         return Some(RunState::PlayerTurn);
                     },
-                    None => return None,
                 }
             }
             _ => match target {
                 Some(target) => {
+                    let positions = app.ecs.read_storage::<Position>();
                     attacks
                         .insert(
                             *player_entity,
                             Attack {
                                 attack_type: attack_type,
-                                target: target,
+                                target: Some(target),
+                                aim: positions.get(target).map(|p| Point::new(p.x, p.y)),
                                 spell: spell,
                             },
                         )
@@ -517,6 +805,48 @@ fn try_ranged_target(app: &mut App) -> Option<RunState> {
     return None;
 }
 
+///
+/// T H R O W I N G
+/// 
+pub fn is_throwing(ecs: &World) -> bool {
+    let player_entity = ecs.fetch::<Entity>();
+    ecs.read_storage::<Throwing>().contains(*player_entity)
+}
+
+/// Puts away a readied throw, e.g. when switching screens, so that the item
+/// can't be dropped or destroyed while it is still waiting to be thrown.
+fn try_put_away_throw(ecs: &mut World) -> Option<RunState> {
+    if !is_throwing(ecs) {
+        return None;
+    }
+    ecs.write_storage::<Throwing>().clear();
+    return Some(RunState::AwaitingInput);
+}
+
+/// Throws the readied item at the free aim reticle.
+fn try_throw_target(app: &mut App) -> Option<RunState> {
+    let RunState::FreeAiming { index } = app.runstate else {
+        return None;
+    };
+    let map = app.ecs.fetch::<Map>();
+    let player_entity = app.ecs.fetch::<Entity>();
+    let mut throwing = app.ecs.write_storage::<Throwing>();
+    let mut wants_throw = app.ecs.write_storage::<WantsToThrowItem>();
+
+    let throwing_item = throwing.remove(*player_entity)?;
+    let (x, y) = map.idx_xy(index);
+    wants_throw
+        .insert(
+            *player_entity,
+            WantsToThrowItem {
+                item: throwing_item.item,
+                target: Point::new(x, y),
+            },
+        )
+        .expect("Unable to insert item throw into ecs");
+    return Some(RunState::PlayerTurn);
+}
+
 ///
 /// M A G I C  A T T A C K
 /// 
@@ -526,22 +856,25 @@ fn try_magic_target(app: &mut App) -> Option<RunState> {
     let spell_knowledge = app.ecs.read_storage::<SpellKnowledge>();
     let mut attacks = app.ecs.write_storage::<Attack>();
     if let Some(spells) = spell_knowledge.get(*player_entity) {
-        let spell = spells.spells.first().expect("uhh");
+        let spell = spells.active_spell()?;
         match app.runstate {
             RunState::FreeAiming { index } => {
-                match map.tile_content[index].iter().next() {
-                    Some(entity) => {
+                // Area spells can be aimed at an empty tile, everything else needs a target
+                let (aim_x, aim_y) = map.idx_xy(index);
+                match (map.tile_content[index].iter().next(), spell.area.is_some()) {
+                    (None, false) => return None,
+                    (entity, _) => {
                         attacks.insert(
                             *player_entity,
                             Attack {
                                 attack_type: AttackType::Magic,
-                                target: *entity,
+                                target: entity.copied(),
+                                aim: Some(Point::new(aim_x, aim_y)),
                                 spell: Some(spell.clone()),
                             }
                         ).expect("Unable to add magic attack");
                         return Some(RunState::PlayerTurn);
                     },
-                    _ => return None
                 }
             }
             _ => return None
@@ -550,3 +883,19 @@ fn try_magic_target(app: &mut App) -> Option<RunState> {
     }
     return None;
 }
+
+/// Readies whichever spell is bound to the given hotkey, so that it will be
+/// used by the next magic attack. Works while free aiming, too.
+fn try_ready_spell_hotkey(ecs: &mut World, key: char) -> bool {
+    let index = {
+        let player_entity = ecs.fetch::<Entity>();
+        let spell_knowledge = ecs.read_storage::<SpellKnowledge>();
+        spell_knowledge
+            .get(*player_entity)
+            .and_then(|known| known.hotkeys.get(&key).copied())
+    };
+    match index {
+        Some(index) => try_ready_spell(ecs, index),
+        None => false,
+    }
+}
@@ -3,13 +3,41 @@ use specs::prelude::*;
 
 use crate::{
     App, RunState, Screen,
-    component::{Inventory, Stats, WantsToConsumeItem},
+    component::{
+        Consumable, Cursed, Equipped, Inventory, InventoryView, Name, PickingItem, Position, Stats,
+        Throwing, WantsToConsumeItem, WantsToDestroyItem, WantsToDropItem,
+    },
+    generate::{
+        character::{can_choose_feat, choose_feat},
+        identification::IdentificationTable,
+        map::Map,
+        spawn::FEATS,
+    },
+    logbook::logbook::Logger,
+    system::inventory_system::{get_highlighted_stack, get_inventory_view, pick_from_stack},
 };
 
 pub fn handle_main_inventory_key_event(app: &mut App, key_event: KeyEvent) -> Option<RunState> {
     if let RunState::LevelUp { index } = app.runstate {
         return handle_main_level_up_key_event(app, index, key_event);
     }
+    if let RunState::FeatSelection { index } = app.runstate {
+        return handle_main_feat_selection_key_event(app, index, key_event);
+    }
+    let player_entity = *app.ecs.fetch::<Entity>();
+    let filtering = app.ecs.read_storage::<Inventory>().get(player_entity).is_some_and(|inventory| inventory.view.filtering);
+    if filtering {
+        return handle_main_inventory_filter_key_event(app, key_event);
+    }
+
+    /*
+     * While picking the item for a scroll of identify (or the like), the scroll is
+     * waiting to be used, so nothing may be dropped, destroyed or thrown meanwhile.
+     */
+    let picking = app.ecs.read_storage::<PickingItem>().contains(player_entity);
+    if picking && matches!(key_event.code, KeyCode::Char('d' | 'D' | 'x' | 'X' | 't' | 'v')) {
+        return None;
+    }
 
     match key_event.code {
         KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('k') => {
@@ -27,18 +55,61 @@ pub fn handle_main_inventory_key_event(app: &mut App, key_event: KeyEvent) -> Op
             let player_entity = app.ecs.fetch::<Entity>();
             let mut inventories = app.ecs.write_storage::<Inventory>();
             if let Some(inventory) = inventories.get_mut(*player_entity) {
-                if inventory.index + 1 < inventory.items.len() {
+                if inventory.index + 1 < get_inventory_view(&app.ecs, inventory).len() {
                     inventory.index += 1;
                 }
             }
             return None;
         }
 
+        // Put the scroll away again rather than closing the inventory
+        KeyCode::Esc if picking => {
+            app.ecs.write_storage::<PickingItem>().remove(player_entity);
+            Logger::new().append("You put the scroll away.").log();
+            return None;
+        }
+
         KeyCode::Char('i') | KeyCode::Esc => {
+            app.ecs.write_storage::<PickingItem>().remove(player_entity);
+            let player_entity = app.ecs.fetch::<Entity>();
+            if let Some(inventory) = app.ecs.write_storage::<Inventory>().get_mut(*player_entity) {
+                inventory.selected.clear();
+            }
             app.screen = Screen::Explore;
             return None;
         }
 
+        // Mark or unmark the current stack for dropping or destroying in bulk
+        KeyCode::Char('v') => {
+            let player_entity = app.ecs.fetch::<Entity>();
+            let mut inventories = app.ecs.write_storage::<Inventory>();
+            if let Some(inventory) = inventories.get_mut(*player_entity) {
+                if let Some(signature) = get_highlighted_stack(&app.ecs, inventory).map(|(signature, _)| signature.clone()) {
+                    if !inventory.selected.remove(&signature) {
+                        inventory.selected.insert(signature);
+                    }
+                }
+            }
+            return None;
+        }
+
+        KeyCode::Char('d') => {
+            try_discard_items(&mut app.ecs, false, false);
+            return None;
+        }
+        KeyCode::Char('D') => {
+            try_discard_items(&mut app.ecs, true, false);
+            return None;
+        }
+        KeyCode::Char('x') => {
+            try_discard_items(&mut app.ecs, false, true);
+            return None;
+        }
+        KeyCode::Char('X') => {
+            try_discard_items(&mut app.ecs, true, true);
+            return None;
+        }
+
         // Consume without leaving inventory screen
         KeyCode::Char(' ') => {
             try_consume_item(&mut app.ecs);
@@ -47,14 +118,74 @@ pub fn handle_main_inventory_key_event(app: &mut App, key_event: KeyEvent) -> Op
 
         // Consume and return to explore screen
         KeyCode::Enter => {
-            try_consume_item(&mut app.ecs);
-            app.screen = Screen::Explore;
+            if try_consume_item(&mut app.ecs) {
+                app.screen = Screen::Explore;
+            }
+            return None;
+        }
+
+        // Ready the item for throwing and aim it on the explore screen
+        KeyCode::Char('t') => {
+            let runstate = try_ready_throw(&mut app.ecs);
+            if runstate.is_some() {
+                app.screen = Screen::Explore;
+            }
+            return runstate;
+        }
+
+        KeyCode::Tab | KeyCode::Right => {
+            change_view(&mut app.ecs, |view| view.tab = view.tab.cycle(true));
+            return None;
+        }
+        KeyCode::BackTab | KeyCode::Left => {
+            change_view(&mut app.ecs, |view| view.tab = view.tab.cycle(false));
+            return None;
+        }
+        KeyCode::Char('o') => {
+            change_view(&mut app.ecs, |view| view.sort = view.sort.next());
+            return None;
+        }
+        KeyCode::Char('/') => {
+            change_view(&mut app.ecs, |view| view.filtering = true);
             return None;
         }
         _ => None,
     }
 }
 
+/// Handles typing into the inventory search box.
+/// Enter keeps the search text, escape throws it away.
+fn handle_main_inventory_filter_key_event(app: &mut App, key_event: KeyEvent) -> Option<RunState> {
+    match key_event.code {
+        KeyCode::Char(c) => change_view(&mut app.ecs, |view| view.filter.push(c)),
+        KeyCode::Backspace => change_view(&mut app.ecs, |view| {
+            view.filter.pop();
+        }),
+        KeyCode::Enter => change_view(&mut app.ecs, |view| view.filtering = false),
+        KeyCode::Esc => change_view(&mut app.ecs, |view| {
+            view.filtering = false;
+            view.filter.clear();
+        }),
+        _ => {}
+    }
+    return None;
+}
+
+/// Applies a change to the player's inventory view, keeping the highlighted
+/// stack highlighted if it is still listed afterwards.
+fn change_view(ecs: &mut World, change: impl FnOnce(&mut InventoryView)) {
+    let player_entity = ecs.fetch::<Entity>();
+    let mut inventories = ecs.write_storage::<Inventory>();
+    let Some(inventory) = inventories.get_mut(*player_entity) else {
+        return;
+    };
+    let highlighted = get_inventory_view(ecs, inventory).get(inventory.index).copied();
+    change(&mut inventory.view);
+    inventory.index = highlighted
+        .and_then(|highlighted| get_inventory_view(ecs, inventory).iter().position(|index| *index == highlighted))
+        .unwrap_or(0);
+}
+
 fn handle_main_level_up_key_event(app: &mut App, index: usize, key_event: KeyEvent) -> Option<RunState> {
     match key_event.code {
         KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('k') => {
@@ -72,38 +203,180 @@ fn handle_main_level_up_key_event(app: &mut App, index: usize, key_event: KeyEve
             }
         }
         KeyCode::Enter => {
-            let mut stats = app.ecs.write_storage::<Stats>();
-            let player = app.ecs.fetch::<Entity>();
-            if let Some(stat) = stats.get_mut(*player) {
-                match index {
-                    0 => stat.strength += 1,
-                    1 => stat.dexterity += 1,
-                    2 => stat.constitution += 1,
-                    3 => stat.intelligence += 1,
-                    4 => stat.wisdom += 1,
-                    5 => stat.charisma += 1,
-                    _ => panic!("ahhh"),
+            let player = *app.ecs.fetch::<Entity>();
+            {
+                let mut stats = app.ecs.write_storage::<Stats>();
+                if let Some(stat) = stats.get_mut(player) {
+                    match index {
+                        0 => stat.strength += 1,
+                        1 => stat.dexterity += 1,
+                        2 => stat.constitution += 1,
+                        3 => stat.intelligence += 1,
+                        4 => stat.wisdom += 1,
+                        5 => stat.charisma += 1,
+                        _ => panic!("ahhh"),
+                    }
                 }
             }
-            Some(RunState::AwaitingInput)
+            // Every few levels, the attribute increase is followed by a feat
+            if can_choose_feat(&app.ecs, player) {
+                Some(RunState::FeatSelection { index: 0 })
+            } else {
+                Some(RunState::AwaitingInput)
+            }
+        }
+        _ => None,
+    }
+}
+
+fn handle_main_feat_selection_key_event(app: &mut App, index: usize, key_event: KeyEvent) -> Option<RunState> {
+    let feat_count = FEATS.lock().unwrap().len();
+    match key_event.code {
+        KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('k') => {
+            if index > 0 {
+                Some(RunState::FeatSelection { index: index - 1 })
+            } else {
+                None
+            }
+        }
+        KeyCode::Down | KeyCode::Char('s') | KeyCode::Char('j') => {
+            if index + 1 < feat_count {
+                Some(RunState::FeatSelection { index: index + 1 })
+            } else {
+                None
+            }
+        }
+        KeyCode::Enter => {
+            let player = *app.ecs.fetch::<Entity>();
+            if choose_feat(&mut app.ecs, player, index) {
+                Some(RunState::AwaitingInput)
+            } else {
+                None
+            }
         }
         _ => None,
     }
 }
 
+/// Uses the highlighted item. Equipped items come first, so using a stack
+/// that holds the player's equipped item takes it off again.
+///
+/// Consumables that act on another item, e.g. a scroll of identify, first have the
+/// player pick that item from the inventory. Returns false while waiting for the pick.
 fn try_consume_item(ecs: &mut World) -> bool {
     let player_entity = ecs.fetch::<Entity>();
     let inventories = ecs.read_storage::<Inventory>();
+    let equipment = ecs.read_storage::<Equipped>();
+    let consumables = ecs.read_storage::<Consumable>();
+    let mut picking = ecs.write_storage::<PickingItem>();
     let mut wants_consume = ecs.write_storage::<WantsToConsumeItem>();
 
-    if let Some(inventory) = inventories.get(*player_entity) {
-        if let Some(item_stack) = inventory.items.get_index(inventory.index) {
-            if let Some(item) = item_stack.1.get(0) {
-                wants_consume
-                    .insert(*player_entity, WantsToConsumeItem { item: *item })
-                    .expect("Unable to insert item consumption into ecs");
-            }
+    let highlighted = inventories
+        .get(*player_entity)
+        .and_then(|inventory| get_highlighted_stack(ecs, inventory))
+        .and_then(|(_, item_stack)| pick_from_stack(item_stack, &equipment, true));
+    let Some(highlighted) = highlighted else {
+        return true;
+    };
+
+    let acts_on_item = consumables
+        .get(highlighted)
+        .is_some_and(|consumable| consumable.effects.iter().any(|effect| effect.acts_on_item()));
+    let (item, target) = match picking.remove(*player_entity) {
+        Some(picked_for) => (picked_for.item, Some(highlighted)),
+        None if acts_on_item => {
+            picking
+                .insert(*player_entity, PickingItem { item: highlighted })
+                .expect("Unable to insert item picking into ecs");
+            Logger::new()
+                .append("Pick the item to use it on.")
+                .log();
+            return false;
+        }
+        None => (highlighted, None),
+    };
+    wants_consume
+        .insert(*player_entity, WantsToConsumeItem { item, slot: None, target })
+        .expect("Unable to insert item consumption into ecs");
+    return true;
+}
+
+/// Readies the selected item for throwing, preferring one that isn't equipped.
+/// Cursed items that are stuck to the player can't be thrown.
+fn try_ready_throw(ecs: &mut World) -> Option<RunState> {
+    let player_entity = ecs.fetch::<Entity>();
+    let map = ecs.fetch::<Map>();
+    let positions = ecs.read_storage::<Position>();
+    let inventories = ecs.read_storage::<Inventory>();
+    let equipment = ecs.read_storage::<Equipped>();
+    let mut curses = ecs.write_storage::<Cursed>();
+    let mut throwing = ecs.write_storage::<Throwing>();
+    let names = ecs.read_storage::<Name>();
+    let identification = ecs.fetch::<IdentificationTable>();
+
+    let inventory = inventories.get(*player_entity)?;
+    let (_, stack) = get_highlighted_stack(ecs, inventory)?;
+    let item = pick_from_stack(stack, &equipment, false)?;
+    if let Some(curse) = curses.get_mut(item).filter(|_| equipment.contains(item)) {
+        curse.known = true;
+        let item_name = names.get(item).map(|n| identification.display_name(&n.name)).unwrap_or_default();
+        Logger::new()
+            .append(format!("You can't let go of the {}, it's cursed!", item_name))
+            .log();
+        return None;
+    }
+
+    let player_pos = positions.get(*player_entity)?;
+    throwing
+        .insert(*player_entity, Throwing { item })
+        .expect("Unable to ready item for throwing");
+    return Some(RunState::FreeAiming { index: map.xy_idx(player_pos.x, player_pos.y) });
+}
+
+/// Drops or destroys items from the player's inventory.
+///
+/// Any marked stacks are discarded in their entirety, otherwise it's either
+/// a single item or the whole of the highlighted stack. Single items prefer
+/// whatever isn't currently equipped.
+fn try_discard_items(ecs: &mut World, whole_stack: bool, destroy: bool) -> bool {
+    let player_entity = ecs.fetch::<Entity>();
+    let equipment = ecs.read_storage::<Equipped>();
+    let mut inventories = ecs.write_storage::<Inventory>();
+    let mut wants_drop = ecs.write_storage::<WantsToDropItem>();
+    let mut wants_destroy = ecs.write_storage::<WantsToDestroyItem>();
+
+    let Some(inventory) = inventories.get_mut(*player_entity) else {
+        return false;
+    };
+    let items: Vec<Entity> = if !inventory.selected.is_empty() {
+        inventory
+            .items
+            .iter()
+            .filter(|(signature, _)| inventory.selected.contains(*signature))
+            .flat_map(|(_, stack)| stack.iter().copied())
+            .collect()
+    } else if let Some((_, stack)) = get_highlighted_stack(ecs, inventory) {
+        if whole_stack {
+            stack.clone()
+        } else {
+            pick_from_stack(stack, &equipment, false).into_iter().collect()
         }
+    } else {
+        Vec::new()
+    };
+    inventory.selected.clear();
+    if items.is_empty() {
+        return false;
+    }
+
+    if destroy {
+        wants_destroy
+            .insert(*player_entity, WantsToDestroyItem { items })
+            .expect("Unable to insert item destruction into ecs");
+    } else {
+        wants_drop
+            .insert(*player_entity, WantsToDropItem { items })
+            .expect("Unable to insert item drop into ecs");
     }
     return true;
 }
@@ -7,12 +7,13 @@ use crossterm::event::{KeyCode, KeyEvent};
 
 use crate::{
     App, RunState, Screen,
-    component::{Position, Stats, WantsToPickupItem},
+    component::{DerivedStats, Position, Stats, WantsToPickupItem},
     generate::{
         generate::{switch_floor},
         spawn::{ITEMS, spawn_item},
     },
     logbook::logbook::{self, LOG_INDEX, Logger},
+    system::derived_stats_system::effective_max_hp,
 };
 
 pub fn handle_main_log_key_event(app: &mut App, key_event: KeyEvent) -> Option<RunState> {
@@ -103,8 +104,9 @@ pub fn process_command(input: String, app: &mut App) {
     if input == "/health" {
         let player_entity = app.ecs.read_resource::<Entity>();
         let mut stats = app.ecs.write_storage::<Stats>();
+        let derived_stats = app.ecs.read_storage::<DerivedStats>();
         if let Some(stat) = stats.get_mut(*player_entity) {
-            stat.hp.current = stat.hp.max;
+            stat.hp.current = effective_max_hp(stat, derived_stats.get(*player_entity));
             Logger::new()
                 .append_with_color(Color::Yellow, "You were healed!")
                 .log();
@@ -136,6 +138,7 @@ pub fn process_command(input: String, app: &mut App) {
                 WantsToPickupItem {
                     collected_by: player_entity,
                     items: item_entities,
+                    auto: false,
                 },
             )
             .expect("Unable to spawn items via command");
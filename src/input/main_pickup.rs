@@ -0,0 +1,110 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use rltk::Point;
+use specs::prelude::*;
+
+use crate::{
+    App, RunState, Screen, component::WantsToPickupItem, logbook::logbook::Logger,
+    system::inventory_system::get_tile_items,
+};
+
+pub fn handle_main_pickup_key_event(app: &mut App, key_event: KeyEvent) -> Option<RunState> {
+    let Screen::Pickup { x, y, index, selected } = &mut app.screen else {
+        return None;
+    };
+    let (x, y) = (*x, *y);
+    let tile_items = get_tile_items(&app.ecs, x, y);
+
+    match key_event.code {
+        KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('k') => {
+            if *index > 0 {
+                *index -= 1;
+            }
+            return None;
+        }
+
+        KeyCode::Down | KeyCode::Char('s') | KeyCode::Char('j') => {
+            if *index + 1 < tile_items.len() {
+                *index += 1;
+            }
+            return None;
+        }
+
+        KeyCode::Char('g') | KeyCode::Esc => {
+            app.screen = Screen::Explore;
+            return None;
+        }
+
+        // Mark or unmark the current pile entry
+        KeyCode::Char('v') | KeyCode::Char(' ') => {
+            if let Some((signature, _)) = tile_items.get_index(*index) {
+                if !selected.remove(signature) {
+                    selected.insert(signature.clone());
+                }
+            }
+            return None;
+        }
+
+        // Take the marked entries, or the current entry if nothing is marked
+        KeyCode::Enter => {
+            let items: Vec<Entity> = if selected.is_empty() {
+                tile_items.get_index(*index).map(|(_, stack)| stack.clone()).unwrap_or_default()
+            } else {
+                tile_items
+                    .iter()
+                    .filter(|(signature, _)| selected.contains(*signature))
+                    .flat_map(|(_, stack)| stack.iter().copied())
+                    .collect()
+            };
+            return try_pickup_items(app, x, y, items);
+        }
+
+        // Take everything on the tile
+        KeyCode::Char('a') => {
+            let items: Vec<Entity> = tile_items.values().flatten().copied().collect();
+            return try_pickup_items(app, x, y, items);
+        }
+        _ => None,
+    }
+}
+
+/// Opens the pick-up overlay for the given tile, or logs that there is nothing there.
+pub fn try_open_pickup(app: &mut App, x: i32, y: i32) -> Option<RunState> {
+    if get_tile_items(&app.ecs, x, y).is_empty() {
+        Logger::new().append("There is nothing here to pick up.").log();
+        return Some(RunState::AwaitingInput);
+    }
+    app.screen = Screen::Pickup {
+        x,
+        y,
+        index: 0,
+        selected: Default::default(),
+    };
+    return Some(RunState::AwaitingInput);
+}
+
+/// Queues the given items for pick up and returns to the explore screen.
+/// Items can only be picked up from the tile the player is standing on.
+fn try_pickup_items(app: &mut App, x: i32, y: i32, items: Vec<Entity>) -> Option<RunState> {
+    if items.is_empty() {
+        return None;
+    }
+    let player_pos = *app.ecs.fetch::<Point>();
+    if player_pos.x != x || player_pos.y != y {
+        Logger::new().append("You can't reach that from here.").log();
+        return None;
+    }
+    let player_entity = *app.ecs.fetch::<Entity>();
+    app.ecs
+        .write_storage::<WantsToPickupItem>()
+        .insert(
+            player_entity,
+            WantsToPickupItem {
+                collected_by: player_entity,
+                items,
+                auto: false,
+            },
+        )
+        .expect("Unable to insert item pickup into ecs");
+    app.screen = Screen::Explore;
+    return Some(RunState::PlayerTurn);
+}
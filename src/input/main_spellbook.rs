@@ -0,0 +1,82 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::style::Color;
+use specs::prelude::*;
+
+use crate::{
+    App, RunState, Screen,
+    component::{SPELL_HOTKEYS, SpellKnowledge},
+    logbook::logbook::Logger,
+};
+
+pub fn handle_main_spellbook_key_event(app: &mut App, index: usize, key_event: KeyEvent) -> Option<RunState> {
+    let spell_count = {
+        let player_entity = app.ecs.fetch::<Entity>();
+        let spell_knowledge = app.ecs.read_storage::<SpellKnowledge>();
+        spell_knowledge.get(*player_entity).map(|s| s.spells.len()).unwrap_or(0)
+    };
+
+    match key_event.code {
+        KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('k') => {
+            if index > 0 {
+                app.screen = Screen::Spellbook { index: index - 1 };
+            }
+            return None;
+        }
+
+        KeyCode::Down | KeyCode::Char('s') | KeyCode::Char('j') => {
+            if index + 1 < spell_count {
+                app.screen = Screen::Spellbook { index: index + 1 };
+            }
+            return None;
+        }
+
+        KeyCode::Char('m') | KeyCode::Esc => {
+            app.screen = Screen::Explore;
+            return None;
+        }
+
+        // Ready the spell without leaving the spellbook
+        KeyCode::Char(' ') => {
+            try_ready_spell(&mut app.ecs, index);
+            return None;
+        }
+
+        // Ready the spell and return to explore screen
+        KeyCode::Enter => {
+            try_ready_spell(&mut app.ecs, index);
+            app.screen = Screen::Explore;
+            return None;
+        }
+
+        KeyCode::Char(key) if SPELL_HOTKEYS.contains(&key) => {
+            let player_entity = app.ecs.fetch::<Entity>();
+            let mut spell_knowledge = app.ecs.write_storage::<SpellKnowledge>();
+            if let Some(known) = spell_knowledge.get_mut(*player_entity) {
+                if index < known.spells.len() {
+                    known.bind_hotkey(key, index);
+                }
+            }
+            return None;
+        }
+        _ => None,
+    }
+}
+
+/// Makes the spell at the given spellbook index the one that will be
+/// cast the next time the player casts a spell.
+pub fn try_ready_spell(ecs: &mut World, index: usize) -> bool {
+    let player_entity = ecs.fetch::<Entity>();
+    let mut spell_knowledge = ecs.write_storage::<SpellKnowledge>();
+    let Some(known) = spell_knowledge.get_mut(*player_entity) else {
+        return false;
+    };
+    let Some(spell) = known.spells.get(index) else {
+        return false;
+    };
+    Logger::new()
+        .append("You ready the spell ")
+        .append_with_color(Color::Green, format!("{}.", spell.name))
+        .log();
+    known.active = index;
+    return true;
+}
@@ -3,7 +3,54 @@ use ratatui::style::Color;
 use specs::prelude::*;
 use crossterm::event::{KeyCode, KeyEvent};
 
-use crate::{App, RunState, Screen, component::{Inventory, Item, Name, Vendor, WantsToPickupItem}, logbook::logbook::Logger};
+use crate::{App, RunState, Screen, component::{Cursed, DerivedStats, Durability, Equipped, InBackpack, Inventory, Item, Name, Vendor, WantsToPickupItem}, generate::identification::{IdentificationTable, identify_item_type}, logbook::logbook::Logger, system::inventory_system::pick_from_stack};
+
+/// The price a vendor charges to identify a single item type.
+pub const IDENTIFY_FEE: i32 = 25;
+
+/// The price a vendor charges to lift the curse from a single item.
+pub const UNCURSE_FEE: i32 = 75;
+
+/// The price a vendor charges for each point of durability restored to an item.
+pub const REPAIR_FEE_PER_POINT: i32 = 2;
+
+/// Vendors pay this percentage of an item's value, before any price modifier.
+pub const SELL_PERCENT: i32 = 40;
+
+/// The price modifier is capped either way, so that no amount of charm
+/// lets the player sell an item for more than it costs to buy back.
+pub const MAX_PRICE_MODIFIER: i32 = 30;
+
+/// A vendor only holds on to this many of the items the player sold them.
+pub const BUYBACK_LIMIT: usize = 10;
+
+/// The percentage of an item's value the player pays a vendor for it.
+/// A positive price modifier is a discount, see `DerivedStats::price_modifier`.
+pub fn buy_percent(derived: Option<&DerivedStats>) -> i32 {
+    return 100 - price_modifier(derived);
+}
+
+/// The percentage of an item's value a vendor pays the player for it.
+/// Only half of the price modifier applies, keeping this well below `buy_percent`.
+pub fn sell_percent(derived: Option<&DerivedStats>) -> i32 {
+    return SELL_PERCENT + price_modifier(derived) / 2;
+}
+
+fn price_modifier(derived: Option<&DerivedStats>) -> i32 {
+    let modifier = derived.map(|d| d.price_modifier).unwrap_or(0);
+    return modifier.clamp(-MAX_PRICE_MODIFIER, MAX_PRICE_MODIFIER);
+}
+
+/// The price the player pays a vendor for an item.
+pub fn buy_price(base_value: i32, derived: Option<&DerivedStats>) -> i32 {
+    i32::max(1, base_value * buy_percent(derived) / 100)
+}
+
+/// The price a vendor pays the player for an item, which is also
+/// what the vendor asks to sell it back from their buyback list.
+pub fn sell_price(base_value: i32, derived: Option<&DerivedStats>) -> i32 {
+    i32::max(0, base_value * sell_percent(derived) / 100)
+}
 
 pub fn handle_main_trading_key_event(
     app: &mut App,
@@ -12,6 +59,7 @@ pub fn handle_main_trading_key_event(
     vendor_index: usize,
     player_index: usize,
     is_buying: bool,
+    is_buyback: bool,
 ) -> Option<RunState> {
     match key_event.code {
         KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('k') => {
@@ -23,6 +71,7 @@ pub fn handle_main_trading_key_event(
                             vendor_index: vendor_index - 1,
                             player_index: player_index,
                             is_buying: is_buying,
+                            is_buyback: is_buyback,
                         };
                     }
                 }
@@ -33,6 +82,7 @@ pub fn handle_main_trading_key_event(
                             vendor_index: vendor_index,
                             player_index: player_index - 1,
                             is_buying: is_buying,
+                            is_buyback: is_buyback,
                         };
                     }
                 }
@@ -46,14 +96,16 @@ pub fn handle_main_trading_key_event(
             let inventories = app.ecs.read_storage::<Inventory>();
             let vendor = vendors.get(vendor_entity).expect("Unable to access given vendor");
             let inventory = inventories.get(*player_entity).expect("Unable to retrieve the player's inventory!");
+            let vendor_items = if is_buyback { &vendor.buyback } else { &vendor.items };
             match is_buying {
                 true => {
-                    if vendor_index + 1 < vendor.items.len() {
+                    if vendor_index + 1 < vendor_items.len() {
                         app.screen = Screen::Trading {
                             vendor: vendor_entity,
                             vendor_index: vendor_index + 1,
                             player_index: player_index,
                             is_buying: is_buying,
+                            is_buyback: is_buyback,
                         };
                     }
                 }
@@ -64,6 +116,7 @@ pub fn handle_main_trading_key_event(
                             vendor_index: vendor_index,
                             player_index: player_index + 1,
                             is_buying: is_buying,
+                            is_buyback: is_buyback,
                         };
                     }
                 }
@@ -79,7 +132,22 @@ pub fn handle_main_trading_key_event(
                 vendor: vendor_entity,
                 vendor_index: vendor_index,
                 player_index: player_index,
-                is_buying: !is_buying
+                is_buying: !is_buying,
+                is_buyback: is_buyback,
+            };
+            None
+        }
+
+        /*
+         * Switch the vendor's list between their stock and what the player has sold them
+         */
+        KeyCode::Char('b') => {
+            app.screen = Screen::Trading {
+                vendor: vendor_entity,
+                vendor_index: 0,
+                player_index: player_index,
+                is_buying: true,
+                is_buyback: !is_buyback,
             };
             None
         }
@@ -88,7 +156,37 @@ pub fn handle_main_trading_key_event(
          * Attempt to buy the currently selected item
          */
         KeyCode::Enter | KeyCode::Char(' ') => {
-            try_buy_item(app, vendor_entity, vendor_index, player_index, is_buying)
+            try_buy_item(app, vendor_entity, vendor_index, player_index, is_buying, is_buyback)
+        }
+
+        /*
+         * Pay the vendor to identify the currently selected item in the player's inventory
+         */
+        KeyCode::Char('i') => {
+            if !is_buying {
+                try_identify_item(app, vendor_entity, player_index);
+            }
+            None
+        }
+
+        /*
+         * Pay the vendor to lift the curse from the currently selected item in the player's inventory
+         */
+        KeyCode::Char('u') => {
+            if !is_buying {
+                try_uncurse_item(app, vendor_entity, player_index);
+            }
+            None
+        }
+
+        /*
+         * Pay the vendor to repair the currently selected item in the player's inventory
+         */
+        KeyCode::Char('r') => {
+            if !is_buying {
+                try_repair_item(app, vendor_entity, player_index);
+            }
+            None
         }
 
         /*
@@ -108,74 +206,91 @@ pub fn handle_main_trading_key_event(
 /// 
 /// If so, add  a `WantsToPickupItem` component to the player and decrement
 /// their gold. Lastly, remove the item from the vendor's inventory and reset
-/// the trading index.
+/// the trading index. Items on the buyback list go for what the vendor paid.
 /// 
 /// If not, add a logbook message regarding insufficient funds.
+///
+/// Selling works the other way around, as long as the vendor can afford the item.
+/// Sold items go on the vendor's buyback list rather than disappearing.
 fn try_buy_item(
     app: &mut App,
     vendor_entity: Entity,
     vendor_index: usize,
     player_index: usize,
     is_buying: bool,
+    is_buyback: bool,
 ) -> Option<RunState> {
     let names = app.ecs.read_storage::<Name>();
-    let mut items = app.ecs.write_storage::<Item>();
+    let identification = app.ecs.fetch::<IdentificationTable>();
+    let items = app.ecs.read_storage::<Item>();
     let mut pickups = app.ecs.write_storage::<WantsToPickupItem>();
     let player_entity = app.ecs.fetch::<Entity>();
+    let derived_stats = app.ecs.read_storage::<DerivedStats>();
 
     // This is done in a scope to prevent borrow errors later when we mutate these components
     let item_entity = {
         let vendors = app.ecs.read_storage::<Vendor>();
         let inventories = app.ecs.read_storage::<Inventory>();
+        let equipment = app.ecs.read_storage::<Equipped>();
+        let vendor = vendors.get(vendor_entity).expect("Unable to access vendor component during trading");
+        let vendor_items = if is_buyback { &vendor.buyback } else { &vendor.items };
         match is_buying {
-            true => vendors.get(vendor_entity)
-                .expect("Unable to access vendor component during trading")
-                .items.get(vendor_index)
-                .copied(),
+            true => vendor_items.get(vendor_index).copied(),
             false => inventories.get(*player_entity)
                 .expect("Unable to access player inventory during trading")
                 .items.get_index(player_index)
-                .map(|entry| entry.1.first().expect("ahhhh"))
-                .copied()
+                .and_then(|(_, stack)| pick_from_stack(stack, &equipment, false))
         }
     };
-    if item_entity.is_none() {
-        return None;
-    }
+    let item_entity = item_entity?;
 
     let mut vendors = app.ecs.write_storage::<Vendor>();
     let mut inventories = app.ecs.write_storage::<Inventory>();
-    let item_name = names.get(item_entity.unwrap()).expect("Unable to access name item name during trading").name.clone();
-    let item = items.get(item_entity.unwrap()).expect("Unable to access item component during trading");
-    let player_inventory = inventories.get_mut(*player_entity).expect("Unable to access player inventory during trading");
+    let item_name = identification.display_name(&names.get(item_entity).expect("Unable to access name item name during trading").name);
+    let vendor_name = names.get(vendor_entity).map(|n| n.name.clone()).unwrap_or("vendor".to_string());
+    let item = items.get(item_entity).expect("Unable to access item component during trading");
     let vendor = vendors.get_mut(vendor_entity).expect("Unable to access vendor component during trading");
+    let vendor_gold = inventories.get(vendor_entity).map(|inventory| inventory.gold).unwrap_or(0);
 
     match is_buying {
         true => {
-            if player_inventory.gold >= item.base_value {
+            let price = match is_buyback {
+                true => sell_price(item.base_value, derived_stats.get(*player_entity)),
+                false => buy_price(item.base_value, derived_stats.get(*player_entity)),
+            };
+            let player_inventory = inventories.get_mut(*player_entity).expect("Unable to access player inventory during trading");
+            if player_inventory.gold >= price {
                 info!(
                     "Purchasing item {} from vendor, item costs {} and player has {} gold",
                     item_name,
-                    item.base_value,
+                    price,
                     player_inventory.gold,
                 );
-                player_inventory.gold -= item.base_value;
+                player_inventory.gold -= price;
                 pickups.insert(*player_entity, WantsToPickupItem {
                     collected_by: *player_entity,
-                    items: vec![item_entity.unwrap()],
+                    items: vec![item_entity],
+                    auto: false,
                 }).expect("uhhh");
-                vendor.items.remove(vendor_index);
+                match is_buyback {
+                    true => vendor.buyback.remove(vendor_index),
+                    false => vendor.items.remove(vendor_index),
+                };
+                if let Some(vendor_inventory) = inventories.get_mut(vendor_entity) {
+                    vendor_inventory.gold += price;
+                }
                 app.screen = Screen::Trading {
                     vendor: vendor_entity,
                     vendor_index: 0,
                     player_index: player_index,
                     is_buying: is_buying,
+                    is_buyback: is_buyback,
                 };
                 Logger::new()
                     .append("You buy the ")
                     .append_with_color(Color::Blue, item_name)
                     .append(" for ")
-                    .append_with_color(Color::Yellow, format!("{} gold.", item.base_value))
+                    .append_with_color(Color::Yellow, format!("{} gold.", price))
                     .log();
             } else {
                 Logger::new()
@@ -186,38 +301,267 @@ fn try_buy_item(
         }
 
         false => {
+            let mut equipment = app.ecs.write_storage::<Equipped>();
+            let mut backpacks = app.ecs.write_storage::<InBackpack>();
+            let curses = app.ecs.read_storage::<Cursed>();
+            if equipment.contains(item_entity) && curses.contains(item_entity) {
+                Logger::new()
+                    .append("You can't take off the ")
+                    .append_with_color(Color::Red, item_name)
+                    .append(" to sell it, it's cursed!")
+                    .log();
+                return None;
+            }
+            let price = sell_price(item.base_value, derived_stats.get(*player_entity));
+            if vendor_gold < price {
+                Logger::new()
+                    .append(format!("The {} can't afford the ", vendor_name))
+                    .append_with_color(Color::Blue, item_name)
+                    .append(format!(", they only have {} gold left.", vendor_gold))
+                    .log();
+                return None;
+            }
             info!(
                 "Selling item {} to vendor for {} gold",
                 item_name,
-                item.base_value,
+                price,
             );
-            player_inventory.gold += item.base_value;
+            let player_inventory = inventories.get_mut(*player_entity).expect("Unable to access player inventory during trading");
+            player_inventory.gold += price;
             player_inventory.index = 0;
-            match player_inventory.items.entry(item_name.clone()) {
-                indexmap::map::Entry::Occupied(mut entry) => {
-                    let stack = entry.get_mut();
-                    let _ = stack.remove(0);
-                    if stack.is_empty() {
-                        entry.shift_remove();
-                    }
-                }
-                indexmap::map::Entry::Vacant(_) => {}
+            player_inventory.remove_item(item_entity);
+            if let Some(vendor_inventory) = inventories.get_mut(vendor_entity) {
+                vendor_inventory.gold -= price;
+            }
+            equipment.remove(item_entity);
+            backpacks.remove(item_entity);
+
+            /* The vendor only keeps so many of the player's castoffs around to sell back */
+            vendor.buyback.push(item_entity);
+            if vendor.buyback.len() > BUYBACK_LIMIT {
+                let oldest = vendor.buyback.remove(0);
+                app.ecs.entities().delete(oldest).expect("Unable to delete old buyback item");
             }
-            let item_base_value = item.base_value;
-            items.remove(item_entity.unwrap());
             app.screen = Screen::Trading {
                 vendor: vendor_entity,
                 vendor_index: vendor_index,
                 player_index: 0,
                 is_buying: is_buying,
+                is_buyback: is_buyback,
             };
             Logger::new()
                 .append("You sell the ")
                 .append_with_color(Color::Blue, format!("{} ", item_name))
                 .append("for ")
-                .append_with_color(Color::Yellow, format!("{} gold.", item_base_value))
+                .append_with_color(Color::Yellow, format!("{} gold.", price))
                 .log();
         }
     }
     return None;
 }
+
+/// Identifies the item type at the given index of the player's inventory in exchange
+/// for `IDENTIFY_FEE` gold, re-keying any matching inventory stacks afterwards.
+fn try_identify_item(app: &mut App, vendor_entity: Entity, player_index: usize) {
+    let item_name = {
+        let names = app.ecs.read_storage::<Name>();
+        let identification = app.ecs.fetch::<IdentificationTable>();
+        let mut inventories = app.ecs.write_storage::<Inventory>();
+        let player_entity = app.ecs.fetch::<Entity>();
+        let player_inventory = inventories.get_mut(*player_entity).expect("Unable to access player inventory during trading");
+
+        let item_name = match player_inventory.items.get_index(player_index) {
+            Some((_, stack)) => names.get(*stack.first().expect("Unable to retrieve inventory item entity (top of stack)"))
+                .expect("Unable to access item name during trading")
+                .name
+                .clone(),
+            None => return,
+        };
+
+        if identification.is_identified(&item_name) {
+            Logger::new()
+                .append("The merchant shrugs, you already know what that is.")
+                .log();
+            return;
+        }
+
+        if player_inventory.gold < IDENTIFY_FEE {
+            Logger::new()
+                .append("You don't have enough gold to identify the ")
+                .append_with_color(Color::Blue, format!("{}.", identification.display_name(&item_name)))
+                .log();
+            return;
+        }
+        player_inventory.gold -= IDENTIFY_FEE;
+        if let Some(vendor_inventory) = inventories.get_mut(vendor_entity) {
+            vendor_inventory.gold += IDENTIFY_FEE;
+        }
+        item_name
+    };
+
+    if let Some(appearance) = identify_item_type(&app.ecs, &item_name) {
+        info!("Identifying {} as {} for {} gold", appearance, item_name, IDENTIFY_FEE);
+        Logger::new()
+            .append("For ")
+            .append_with_color(Color::Yellow, format!("{} gold", IDENTIFY_FEE))
+            .append(", the merchant identifies the ")
+            .append_with_color(Color::Blue, appearance)
+            .append(" as a ")
+            .append_with_color(Color::Green, format!("{}.", item_name))
+            .log();
+    }
+}
+
+/// Lifts the curse from the item at the given index of the player's inventory
+/// in exchange for `UNCURSE_FEE` gold. Merchants can sense curses the player
+/// hasn't discovered yet, so this also works on items that aren't known to be cursed.
+fn try_uncurse_item(app: &mut App, vendor_entity: Entity, player_index: usize) {
+    let names = app.ecs.read_storage::<Name>();
+    let mut curses = app.ecs.write_storage::<Cursed>();
+    let mut inventories = app.ecs.write_storage::<Inventory>();
+    let player_entity = app.ecs.fetch::<Entity>();
+    let player_inventory = inventories.get_mut(*player_entity).expect("Unable to access player inventory during trading");
+
+    /* Merchants go for the cursed item in a stack, since that's the one worth paying to fix */
+    let Some(item_entity) = player_inventory.items.get_index(player_index).and_then(|(_, stack)| {
+        stack.iter().find(|item| curses.contains(**item)).or(stack.first()).copied()
+    }) else {
+        return;
+    };
+    let item_name = names.get(item_entity).expect("Unable to access item name during trading").name.clone();
+
+    if !curses.contains(item_entity) {
+        Logger::new()
+            .append("The merchant finds no curse on the ")
+            .append_with_color(Color::Blue, format!("{}.", item_name))
+            .log();
+        return;
+    }
+
+    if player_inventory.gold < UNCURSE_FEE {
+        Logger::new()
+            .append("You don't have enough gold to lift the curse on the ")
+            .append_with_color(Color::Red, format!("{}.", item_name))
+            .log();
+        return;
+    }
+    player_inventory.gold -= UNCURSE_FEE;
+    if let Some(vendor_inventory) = inventories.get_mut(vendor_entity) {
+        vendor_inventory.gold += UNCURSE_FEE;
+    }
+    curses.remove(item_entity);
+
+    info!("Uncursing {} for {} gold", item_name, UNCURSE_FEE);
+    Logger::new()
+        .append("For ")
+        .append_with_color(Color::Yellow, format!("{} gold", UNCURSE_FEE))
+        .append(", the merchant lifts the curse on the ")
+        .append_with_color(Color::Green, format!("{}.", item_name))
+        .log();
+}
+
+/// Restores the item at the given index of the player's inventory to full durability,
+/// for `REPAIR_FEE_PER_POINT` gold per point of wear. Merchants go for the equipped
+/// item in a stack, since that's the one taking the beating.
+fn try_repair_item(app: &mut App, vendor_entity: Entity, player_index: usize) {
+    let names = app.ecs.read_storage::<Name>();
+    let equipment = app.ecs.read_storage::<Equipped>();
+    let mut durabilities = app.ecs.write_storage::<Durability>();
+    let mut inventories = app.ecs.write_storage::<Inventory>();
+    let player_entity = app.ecs.fetch::<Entity>();
+    let player_inventory = inventories.get_mut(*player_entity).expect("Unable to access player inventory during trading");
+
+    let Some(item_entity) = player_inventory
+        .items
+        .get_index(player_index)
+        .and_then(|(_, stack)| pick_from_stack(stack, &equipment, true))
+    else {
+        return;
+    };
+    let item_name = names.get(item_entity).expect("Unable to access item name during trading").name.clone();
+
+    let Some(durability) = durabilities.get_mut(item_entity) else {
+        Logger::new()
+            .append("The merchant shakes their head, the ")
+            .append_with_color(Color::Blue, item_name)
+            .append(" can't be repaired.")
+            .log();
+        return;
+    };
+    if durability.current >= durability.max {
+        Logger::new()
+            .append("The merchant shrugs, the ")
+            .append_with_color(Color::Blue, item_name)
+            .append(" is in perfect condition.")
+            .log();
+        return;
+    }
+
+    let fee = (durability.max - durability.current) * REPAIR_FEE_PER_POINT;
+    if player_inventory.gold < fee {
+        Logger::new()
+            .append("You don't have enough gold to repair the ")
+            .append_with_color(Color::Blue, item_name)
+            .append(format!(", it would cost {} gold.", fee))
+            .log();
+        return;
+    }
+    player_inventory.gold -= fee;
+    if let Some(vendor_inventory) = inventories.get_mut(vendor_entity) {
+        vendor_inventory.gold += fee;
+    }
+    durability.current = durability.max;
+
+    info!("Repairing {} for {} gold", item_name, fee);
+    Logger::new()
+        .append("For ")
+        .append_with_color(Color::Yellow, format!("{} gold", fee))
+        .append(", the merchant repairs the ")
+        .append_with_color(Color::Green, format!("{}.", item_name))
+        .log();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_modifier(price_modifier: i32) -> DerivedStats {
+        DerivedStats { price_modifier, ..Default::default() }
+    }
+
+    #[test]
+    fn prices_without_modifier() {
+        assert_eq!(buy_price(100, None), 100);
+        assert_eq!(sell_price(100, None), SELL_PERCENT);
+        assert_eq!(buy_price(100, Some(&with_modifier(0))), 100);
+    }
+
+    #[test]
+    fn price_modifier_is_clamped() {
+        let charming = with_modifier(MAX_PRICE_MODIFIER + 50);
+        assert_eq!(buy_percent(Some(&charming)), 100 - MAX_PRICE_MODIFIER);
+        assert_eq!(sell_percent(Some(&charming)), SELL_PERCENT + MAX_PRICE_MODIFIER / 2);
+
+        let repulsive = with_modifier(-MAX_PRICE_MODIFIER - 50);
+        assert_eq!(buy_percent(Some(&repulsive)), 100 + MAX_PRICE_MODIFIER);
+        assert_eq!(sell_percent(Some(&repulsive)), SELL_PERCENT - MAX_PRICE_MODIFIER / 2);
+    }
+
+    #[test]
+    fn selling_never_beats_buying() {
+        for modifier in -100..=100 {
+            let derived = with_modifier(modifier);
+            assert!(sell_percent(Some(&derived)) < buy_percent(Some(&derived)));
+            for base_value in [0, 1, 7, 25, 600] {
+                assert!(sell_price(base_value, Some(&derived)) <= buy_price(base_value, Some(&derived)));
+            }
+        }
+    }
+
+    #[test]
+    fn prices_have_a_floor() {
+        assert_eq!(buy_price(0, None), 1);
+        assert_eq!(sell_price(0, None), 0);
+        assert_eq!(sell_price(1, Some(&with_modifier(-100))), 0);
+    }
+}
@@ -3,7 +3,7 @@ use rand::Rng;
 
 use crate::{
     App, RootScreen, RunState, Screen,
-    generate::generate::generate_floor,
+    generate::{character::CharacterCreation, generate::generate_floor},
     logbook::logbook::{self, Logger},
     reinitialize_world,
 };
@@ -27,15 +27,8 @@ pub fn handle_menu_key_event(app: &mut App, key_event: KeyEvent) -> Option<RunSt
         }
         KeyCode::Enter => match app.menu_index {
             0 => {
-                app.ecs = reinitialize_world();
-                app.dungeon
-                    .add_map(&generate_floor(rand::rng().random(), 0, &mut app.ecs));
-                app.root_screen = RootScreen::Main;
-                app.screen = Screen::Explore;
-                logbook::clear();
-                Logger::new()
-                    .append("You begin your adventure in a smallish room...")
-                    .log();
+                app.character = CharacterCreation::new();
+                app.root_screen = RootScreen::CharacterCreation;
             }
             1 => app.exit(),
             _ => {}
@@ -44,3 +37,16 @@ pub fn handle_menu_key_event(app: &mut App, key_event: KeyEvent) -> Option<RunSt
     }
     return None;
 }
+
+/// Resets the world and generates the first floor for a brand new run.
+pub fn start_new_game(app: &mut App) {
+    app.ecs = reinitialize_world();
+    app.dungeon
+        .add_map(&generate_floor(rand::rng().random(), 0, &mut app.ecs));
+    app.root_screen = RootScreen::Main;
+    app.screen = Screen::Explore;
+    logbook::clear();
+    Logger::new()
+        .append("You begin your adventure in a smallish room...")
+        .log();
+}
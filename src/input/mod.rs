@@ -1,7 +1,11 @@
+pub mod character_creation;
 pub mod game_over;
+pub mod main_equipment;
 pub mod main_explore;
 pub mod main_inventory;
 pub mod main_log;
+pub mod main_pickup;
 pub mod main_quit;
+pub mod main_spellbook;
 pub mod main_trading;
 pub mod menu;
@@ -1,9 +1,9 @@
-use std::{fs::File, io, time::Duration};
+use std::{collections::HashSet, fs::File, io, time::Duration};
 
 use color_eyre::Result;
 use crossterm::event::{self, Event, KeyEvent, KeyEventKind};
 use log::LevelFilter;
-use ratatui::{DefaultTerminal, Frame, layout::Size};
+use ratatui::{DefaultTerminal, Frame, layout::Size, style::Color};
 use simplelog::{CombinedLogger, Config, WriteLogger};
 use specs::prelude::*;
 
@@ -14,6 +14,8 @@ mod input;
 mod logbook;
 mod render;
 mod system;
+#[cfg(test)]
+mod scratch_test;
 
 use input::menu::handle_menu_key_event;
 use render::game_over::render_game_over;
@@ -26,28 +28,48 @@ use system::{
 
 use crate::{
     component::{
-        Armor, Attack, BlocksTile, Damage, Equippable, Equipped, Experience, Hidden, InBackpack,
-        Inventory, Item, Lifetime, MagicMapper, MagicWeapon, MeleeWeapon, Monster, Name, Npc,
-        OtherLevelPosition, Player, Position, Potion, RangedWeapon, Renderable, Spell,
-        SpellKnowledge, Stats, Triggerable, Vendor, Viewshed, WantsToConsumeItem,
-        WantsToPickupItem,
+        Alertness, Ammunition, Armor, Attack, BlocksTile, Consumable, Cursed, Damage, DerivedStats,
+        Durability, Equippable, Equipped, Experience, Feats, Fragile, Gold, Hidden, InBackpack,
+        Inventory, Item, ItemSignature, Lifetime, MagicWeapon, MeleeWeapon, Modifiers, Monster,
+        Name, Noise, Npc, OtherLevelPosition, PickingItem, Player, Position, RangedWeapon, Rarity,
+        Renderable, Shrine, Spell, SpellKnowledge, Stats, Statuses, Throwable, Throwing,
+        Triggerable, Vendor, Viewshed, WantsToConsumeItem, WantsToDestroyItem, WantsToDropItem,
+        WantsToPickupItem, WantsToThrowItem,
     },
     damage_system::DamageSystem,
     effect::effect::process_effects,
-    generate::{dungeon::Dungeon, generate::switch_floor, spawn::initialize_config},
+    generate::{
+        character::CharacterCreation, dungeon::Dungeon, generate::switch_floor,
+        spawn::initialize_config,
+    },
     input::{
-        game_over::handle_game_over_key_event, main_explore::handle_main_explore_key_event,
-        main_inventory::handle_main_inventory_key_event, main_log::handle_main_log_key_event,
-        main_quit::handle_main_quit_key_event, main_trading::handle_main_trading_key_event,
+        character_creation::handle_character_creation_key_event,
+        game_over::handle_game_over_key_event,
+        main_equipment::handle_main_equipment_key_event,
+        main_explore::handle_main_explore_key_event,
+        main_explore::{is_fully_rested, visible_monster},
+        main_inventory::handle_main_inventory_key_event,
+        main_log::handle_main_log_key_event,
+        main_pickup::handle_main_pickup_key_event,
+        main_quit::handle_main_quit_key_event,
+        main_spellbook::handle_main_spellbook_key_event,
+        main_trading::handle_main_trading_key_event,
     },
     inventory_system::InventorySystem,
+    logbook::logbook::Logger,
     map_indexing_system::MapIndexingSystem,
     melee_combat_system::MeleeCombatSystem,
     monster_system::MonsterSystem,
-    render::{game::render_game, log::render_log, quit::render_quit, trading::render_trading},
+    render::{
+        character_creation::render_character_creation, equipment::render_equipment,
+        game::render_game, log::render_log, pickup::render_pickup, quit::render_quit,
+        spellbook::render_spellbook, trading::render_trading,
+    },
     system::{
+        alertness_system::AlertnessSystem, derived_stats_system::DerivedStatsSystem,
         experience_system::ExperienceSystem, particle_system::ParticleSystem,
-        ranged_combat_system::RangedCombatSystem, trigger_system::TriggerSystem,
+        ranged_combat_system::RangedCombatSystem, regeneration_system::RegenerationSystem,
+        status_system::StatusSystem, throw_system::ThrowSystem, trigger_system::TriggerSystem,
     },
     visibility_system::VisibilitySystem,
 };
@@ -55,6 +77,7 @@ use crate::{
 #[derive(Debug)]
 pub enum RootScreen {
     Menu,
+    CharacterCreation,
     Main,
     GameOver,
 }
@@ -78,15 +101,41 @@ pub enum Screen {
      */
     Inventory,
 
+    /**
+     * A non-combat screen that lists the spells the player knows.
+     * Allows players to ready a spell and bind spells to hotkeys.
+     */
+    Spellbook { index: usize },
+
+    /**
+     * A non-combat screen that lays out every equipment slot and what fills it.
+     * Allows players to unequip items, or equip matching items from their backpack.
+     * While choosing from the backpack, `candidate` is the highlighted backpack item.
+     */
+    Equipment { index: usize, candidate: Option<usize> },
+
     /**
      * A non-combat screen that appears when interacting with vendors.
      * Allows players to exchange (buy and sell) items with vendors.
+     * While `is_buyback` is set, the vendor lists what the player sold them instead of their stock.
      */
     Trading {
         vendor: Entity,
         vendor_index: usize,
         player_index: usize,
         is_buying: bool,
+        is_buyback: bool,
+    },
+
+    /**
+     * An overlay over the explore view listing every item on a tile.
+     * Allows players to pick up several items, or all of them, at once.
+     */
+    Pickup {
+        x: i32,
+        y: i32,
+        index: usize,
+        selected: HashSet<ItemSignature>,
     },
 
     /**
@@ -100,6 +149,7 @@ pub enum RunState {
     AwaitingInput,
     Examining { index: usize },
     LevelUp { index: usize },
+    FeatSelection { index: usize },
     FreeAiming { index: usize },
     Dialogue { npc: Entity },
     PlayerTurn,
@@ -108,6 +158,9 @@ pub enum RunState {
     Ascending,
 }
 
+/// Energy the player must bank from speed to take an extra turn, see `App::gains_extra_turn`.
+pub const TURN_ENERGY: i32 = 100;
+
 pub struct App {
     pub ecs: World,
     pub dispatcher: Dispatcher<'static, 'static>,
@@ -120,6 +173,9 @@ pub struct App {
     floor_index: u32,
     log_index: u8,
     logbook_input: String,
+    character: CharacterCreation,
+    resting: bool,
+    energy: i32,
     exit: bool,
 }
 
@@ -147,16 +203,30 @@ impl App {
             let mut next_runstate = self.handle_events()?;
             match self.root_screen {
                 RootScreen::Menu => {}
+                RootScreen::CharacterCreation => {}
                 RootScreen::GameOver => {}
                 RootScreen::Main => {
                     match self.runstate {
                         RunState::AwaitingInput => {}
                         RunState::Examining { index: _ } => {}
                         RunState::LevelUp { index: _ } => {}
+                        RunState::FeatSelection { index: _ } => {}
                         RunState::FreeAiming { index: _ } => {}
                         RunState::Dialogue { npc: _ } => {}
-                        RunState::PlayerTurn => next_runstate = RunState::MonsterTurn,
-                        RunState::MonsterTurn => next_runstate = RunState::AwaitingInput,
+                        RunState::PlayerTurn => {
+                            next_runstate = if self.gains_extra_turn() {
+                                self.next_resting_runstate()
+                            } else {
+                                RunState::MonsterTurn
+                            }
+                        }
+                        RunState::MonsterTurn => {
+                            next_runstate = if self.loses_turn() {
+                                RunState::MonsterTurn
+                            } else {
+                                self.next_resting_runstate()
+                            }
+                        }
                         RunState::Descending => next_runstate = switch_floor(self, self.floor_index + 1),
                         RunState::Ascending => next_runstate = switch_floor(self, self.floor_index - 1),
                     }
@@ -174,6 +244,7 @@ impl App {
                     self.dispatcher.dispatch(&self.ecs);
                     if damage_system::is_game_over(&mut self.ecs) {
                         self.root_screen = RootScreen::GameOver;
+                        self.resting = false;
                     }
                     damage_system::cleanup_dead_entities(&mut self.ecs);
                     process_effects(self);
@@ -181,7 +252,9 @@ impl App {
             }
             self.ecs.maintain();
             terminal.draw(|frame| self.draw(frame))?;
-            std::thread::sleep(Duration::from_millis(16));
+            if !self.resting {
+                std::thread::sleep(Duration::from_millis(16));
+            }
         }
         Ok(())
     }
@@ -193,6 +266,11 @@ impl App {
         let mut runstate: Option<RunState> = Some(self.runstate.clone());
         if event::poll(Duration::from_millis(0))? {
             match event::read()? {
+                // Any key press interrupts resting, and is otherwise ignored
+                Event::Key(key_event) if key_event.kind == KeyEventKind::Press && self.resting => {
+                    self.resting = false;
+                    Logger::new().append("You stop resting.").log();
+                }
                 Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
                     runstate = self.handle_key_event(key_event);
                 }
@@ -213,15 +291,21 @@ impl App {
     fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<RunState> {
         match self.root_screen {
             RootScreen::Menu => handle_menu_key_event(self, key_event),
+            RootScreen::CharacterCreation => handle_character_creation_key_event(self, key_event),
             RootScreen::Main => match self.screen {
                 Screen::Explore => handle_main_explore_key_event(self, self.runstate, key_event),
                 Screen::Log => handle_main_log_key_event(self, key_event),
                 Screen::Inventory => handle_main_inventory_key_event(self, key_event),
+                Screen::Spellbook { index } => handle_main_spellbook_key_event(self, index, key_event),
+                Screen::Equipment { index, candidate } => {
+                    handle_main_equipment_key_event(self, index, candidate, key_event)
+                }
                 Screen::Trading {
                     vendor,
                     vendor_index,
                     player_index,
                     is_buying,
+                    is_buyback,
                 } => handle_main_trading_key_event(
                     self,
                     key_event,
@@ -229,7 +313,9 @@ impl App {
                     vendor_index,
                     player_index,
                     is_buying,
+                    is_buyback,
                 ),
+                Screen::Pickup { .. } => handle_main_pickup_key_event(self, key_event),
                 Screen::Quit { quit } => handle_main_quit_key_event(self, quit, key_event),
             },
             RootScreen::GameOver => handle_game_over_key_event(self, key_event),
@@ -243,17 +329,23 @@ impl App {
     fn draw(&mut self, frame: &mut Frame) {
         match self.root_screen {
             RootScreen::Menu => render_menu(frame, self.menu_index),
+            RootScreen::CharacterCreation => render_character_creation(frame, &self.character),
             RootScreen::Main => match self.screen {
                 Screen::Explore => {
                     render_game(&mut self.ecs, frame, self.floor_index, self.terminal)
                 }
                 Screen::Log => render_log(self, frame),
                 Screen::Inventory => render_inventory(&mut self.ecs, self.runstate, frame),
+                Screen::Spellbook { index } => render_spellbook(&mut self.ecs, index, frame),
+                Screen::Equipment { index, candidate } => {
+                    render_equipment(&mut self.ecs, index, candidate, frame)
+                }
                 Screen::Trading {
                     vendor,
                     vendor_index,
                     player_index,
                     is_buying,
+                    is_buyback,
                 } => render_trading(
                     &mut self.ecs,
                     frame,
@@ -261,13 +353,89 @@ impl App {
                     vendor_index,
                     player_index,
                     is_buying,
+                    is_buyback,
                 ),
+                Screen::Pickup {
+                    x,
+                    y,
+                    index,
+                    ref selected,
+                } => {
+                    render_game(&mut self.ecs, frame, self.floor_index, self.terminal);
+                    render_pickup(&mut self.ecs, frame, x, y, index, selected);
+                }
                 Screen::Quit { quit } => render_quit(&mut self.ecs, quit, frame),
             },
             RootScreen::GameOver => render_game_over(frame),
         }
     }
 
+    /**
+     * Determines what follows the monster turn.
+     *
+     * Normally control goes back to the player, but while resting we keep
+     * passing turns until the player is fully rested or a monster shows up.
+     */
+    fn next_resting_runstate(&mut self) -> RunState {
+        if !self.resting {
+            return RunState::AwaitingInput;
+        }
+        if let Some(monster_name) = visible_monster(&self.ecs) {
+            Logger::new()
+                .append("You stop resting, ")
+                .append_with_color(Color::Red, monster_name)
+                .append(" comes into view.")
+                .log();
+            self.resting = false;
+            return RunState::AwaitingInput;
+        }
+        if is_fully_rested(&self.ecs) {
+            Logger::new().append("You feel fully rested.").log();
+            self.resting = false;
+            return RunState::AwaitingInput;
+        }
+        return RunState::PlayerTurn;
+    }
+
+    /**
+     * Speed from equipment is banked as energy after every player turn.
+     * Once a full turn's worth has built up, the player acts again before
+     * the monsters get to move, e.g. +25 speed is an extra turn in every four.
+     *
+     * Negative speed (e.g. from being encumbered) drains energy instead,
+     * see `loses_turn` for when the monsters get to act twice in a row.
+     */
+    fn gains_extra_turn(&mut self) -> bool {
+        let player_entity = *self.ecs.fetch::<Entity>();
+        let speed = self
+            .ecs
+            .read_storage::<DerivedStats>()
+            .get(player_entity)
+            .map(|d| d.speed)
+            .unwrap_or(0);
+        if speed == 0 {
+            return false;
+        }
+        self.energy += speed;
+        if self.energy >= TURN_ENERGY {
+            self.energy -= TURN_ENERGY;
+            return true;
+        }
+        return false;
+    }
+
+    /**
+     * Once a full turn's worth of energy has been drained by negative speed,
+     * the player loses a turn and the monsters act again straight away.
+     */
+    fn loses_turn(&mut self) -> bool {
+        if self.energy <= -TURN_ENERGY {
+            self.energy += TURN_ENERGY;
+            return true;
+        }
+        return false;
+    }
+
     fn exit(&mut self) {
         self.exit = true;
     }
@@ -289,8 +457,7 @@ fn reinitialize_world() -> World {
     world.register::<Damage>();
     world.register::<Experience>();
     world.register::<Item>();
-    world.register::<Potion>();
-    world.register::<MagicMapper>();
+    world.register::<Consumable>();
     world.register::<InBackpack>();
     world.register::<WantsToPickupItem>();
     world.register::<WantsToConsumeItem>();
@@ -298,6 +465,15 @@ fn reinitialize_world() -> World {
     world.register::<Equipped>();
     world.register::<MeleeWeapon>();
     world.register::<RangedWeapon>();
+    world.register::<Ammunition>();
+    world.register::<Throwable>();
+    world.register::<Fragile>();
+    world.register::<Gold>();
+    world.register::<Throwing>();
+    world.register::<PickingItem>();
+    world.register::<WantsToThrowItem>();
+    world.register::<WantsToDropItem>();
+    world.register::<WantsToDestroyItem>();
     world.register::<MagicWeapon>();
     world.register::<Armor>();
     world.register::<Spell>();
@@ -307,14 +483,30 @@ fn reinitialize_world() -> World {
     world.register::<Triggerable>();
     world.register::<Npc>();
     world.register::<Vendor>();
+    world.register::<Alertness>();
+    world.register::<Noise>();
+    world.register::<Statuses>();
+    world.register::<Feats>();
+    world.register::<DerivedStats>();
+    world.register::<Modifiers>();
+    world.register::<Rarity>();
+    world.register::<Cursed>();
+    world.register::<Durability>();
+    world.register::<Shrine>();
     return world;
 }
 
 fn reinitialize_systems(world: &mut World) -> Dispatcher<'static, 'static> {
     let mut dispatcher = DispatcherBuilder::new()
-        .with(VisibilitySystem {}, "visibility_system", &[])
+        .with(DerivedStatsSystem {}, "derived_stats_system", &[])
+        .with(VisibilitySystem {}, "visibility_system", &["derived_stats_system"])
         .with(InventorySystem {}, "inventory_system", &[])
-        .with(MonsterSystem {}, "monster_system", &["visibility_system"])
+        .with(AlertnessSystem {}, "alertness_system", &["visibility_system"])
+        .with(
+            MonsterSystem {},
+            "monster_system",
+            &["visibility_system", "alertness_system"],
+        )
         .with(
             MapIndexingSystem {},
             "map_indexing_system",
@@ -329,9 +521,24 @@ fn reinitialize_systems(world: &mut World) -> Dispatcher<'static, 'static> {
         .with(
             MeleeCombatSystem {},
             "melee_combat_system",
-            &["map_indexing_system"],
+            &["map_indexing_system", "derived_stats_system"],
+        )
+        .with(
+            ThrowSystem {},
+            "throw_system",
+            &["map_indexing_system", "derived_stats_system"],
+        )
+        .with(StatusSystem {}, "status_system", &[])
+        .with(
+            DamageSystem {},
+            "damage_system",
+            &["melee_combat_system", "throw_system", "status_system"],
+        )
+        .with(
+            RegenerationSystem { turn: 0 },
+            "regeneration_system",
+            &["damage_system"],
         )
-        .with(DamageSystem {}, "damage_system", &["melee_combat_system"])
         .with(
             ExperienceSystem {},
             "experience_system",
@@ -377,6 +584,9 @@ fn main() -> Result<()> {
         floor_index: 0,
         log_index: 0,
         logbook_input: "".to_string(),
+        character: CharacterCreation::new(),
+        resting: false,
+        energy: 0,
         exit: false,
     }
     .run(&mut terminal);
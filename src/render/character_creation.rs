@@ -0,0 +1,173 @@
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style, Stylize, palette::tailwind::SLATE},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, List, ListItem, ListState, Padding, Paragraph, Wrap},
+};
+
+use crate::{
+    generate::{
+        character::{ATTRIBUTE_NAMES, CharacterCreation, CreationStep, class_bonuses},
+        spawn::{CLASSES, bundle_size},
+    },
+    system::derived_stats_system::stat_to_modifier,
+};
+
+/**
+ * Renders the character creation flow that follows "New Game".
+ *
+ * Each step gets its own layout, but all of them share a summary of the
+ * character so far along the top of the screen.
+ */
+pub fn render_character_creation(frame: &mut Frame, character: &CharacterCreation) {
+    let classes = CLASSES.lock().unwrap();
+    let class = classes.get(character.class_index);
+
+    let root = Block::default()
+        .title("Create your character")
+        .title_alignment(Alignment::Center)
+        .title_bottom(match character.step {
+            CreationStep::Name => "[enter] Continue  [esc] Back to menu",
+            CreationStep::Class => "[enter] Continue  [esc] Back",
+            CreationStep::Attributes => "[←/→] Adjust  [r] Roll  [p] Point-buy  [enter] Begin  [esc] Back",
+        })
+        .borders(Borders::ALL)
+        .padding(Padding::uniform(1));
+    let inner = root.inner(frame.area());
+    frame.render_widget(root, frame.area());
+
+    let vertical_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Length(2), Constraint::Fill(1)])
+        .split(inner);
+
+    frame.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled(character.display_name(), Style::new().add_modifier(Modifier::ITALIC)),
+            Span::styled(
+                format!(" the {}", class.map(|c| c.name.as_str()).unwrap_or("???")),
+                Style::new().fg(Color::Gray),
+            ),
+        ])),
+        vertical_layout[0],
+    );
+
+    match character.step {
+        CreationStep::Name => {
+            frame.render_widget(
+                Paragraph::new(Text::from(vec![
+                    Line::from("What is your name, adventurer?"),
+                    Line::from(""),
+                    Line::from(Span::styled(
+                        format!("> {}_", character.name),
+                        Style::new().fg(Color::Cyan),
+                    )),
+                ])),
+                vertical_layout[1],
+            );
+        }
+
+        CreationStep::Class => {
+            let horizontal_layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+                .split(vertical_layout[1]);
+
+            let class_list: Vec<ListItem> = classes
+                .iter()
+                .map(|c| ListItem::from(format!(" {}", c.name)))
+                .collect();
+            let mut state = ListState::default();
+            state.select(Some(character.class_index));
+            frame.render_stateful_widget(
+                List::new(class_list)
+                    .block(Block::new().title("Class").borders(Borders::ALL))
+                    .highlight_style(Style::new().bg(SLATE.c800).add_modifier(Modifier::BOLD))
+                    .highlight_spacing(ratatui::widgets::HighlightSpacing::Never),
+                horizontal_layout[0],
+                &mut state,
+            );
+
+            let mut details: Vec<Line> = Vec::new();
+            if let Some(class) = class {
+                details.push(Line::from(Span::styled(
+                    class.description.trim().to_string(),
+                    Style::default().italic(),
+                )));
+                details.push(Line::from(""));
+                details.push(Line::from(vec![
+                    Span::styled(format!("HP: {}  ", class.hp), Style::new().fg(Color::LightRed)),
+                    Span::styled(format!("MP: {}  ", class.mp), Style::new().fg(Color::Blue)),
+                    Span::styled(format!("Gold: {}", class.gold), Style::new().fg(Color::Yellow)),
+                ]));
+                let bonuses: Vec<String> = class_bonuses(class)
+                    .iter()
+                    .zip(ATTRIBUTE_NAMES.iter())
+                    .filter(|(bonus, _)| **bonus != 0)
+                    .map(|(bonus, name)| format!("{:+} {}", bonus, name))
+                    .collect();
+                details.push(Line::from(format!("Bonuses: {}", bonuses.join(", "))));
+                details.push(Line::from(""));
+                details.push(Line::from(Span::styled(
+                    "Starting kit",
+                    Style::new().add_modifier(Modifier::BOLD),
+                )));
+                for item in class.items.iter() {
+                    let bundle = bundle_size(item);
+                    details.push(Line::from(Span::styled(
+                        if bundle > 1 { format!(" {} x{}", item, bundle) } else { format!(" {}", item) },
+                        Style::new().fg(Color::Cyan),
+                    )));
+                }
+                for spell in class.spells.iter() {
+                    details.push(Line::from(Span::styled(
+                        format!(" Spell: {} ({} mp)", spell.name, spell.mp_cost),
+                        Style::new().fg(Color::LightMagenta),
+                    )));
+                }
+            }
+            frame.render_widget(
+                Paragraph::new(Text::from(details))
+                    .wrap(Wrap { trim: true })
+                    .block(Block::new().borders(Borders::ALL).padding(Padding::horizontal(1))),
+                horizontal_layout[1],
+            );
+        }
+
+        CreationStep::Attributes => {
+            let bonuses = class.map(class_bonuses).unwrap_or_default();
+            let mut lines: Vec<Line> = vec![
+                Line::from(if character.rolled {
+                    Span::styled("Rolled (4d6, drop the lowest)", Style::new().fg(Color::Yellow))
+                } else {
+                    Span::styled(
+                        format!("Points remaining: {}", character.points_remaining()),
+                        Style::new().fg(Color::Yellow),
+                    )
+                }),
+                Line::from(""),
+            ];
+            for (index, name) in ATTRIBUTE_NAMES.iter().enumerate() {
+                let total = character.attributes[index] + bonuses[index];
+                let style = if index == character.attribute_index {
+                    Style::new().bg(SLATE.c800).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{:14}{:>3}", name, character.attributes[index]), style),
+                    Span::styled(
+                        if bonuses[index] != 0 { format!(" {:+}", bonuses[index]) } else { "   ".to_string() },
+                        Style::new().fg(Color::Green),
+                    ),
+                    Span::styled(
+                        format!("  = {:>2} ({:+})", total, stat_to_modifier(total)),
+                        Style::new().fg(Color::Gray),
+                    ),
+                ]));
+            }
+            frame.render_widget(Paragraph::new(Text::from(lines)), vertical_layout[1]);
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use ratatui::{
+    Frame,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Padding, Paragraph},
+};
+use specs::prelude::*;
+
+use crate::{
+    component::{
+        Armor, DamageType, DerivedStats, Equippable, MagicWeapon, MeleeWeapon, Name, RangedWeapon,
+    },
+    generate::{config::DiceExpression, identification::IdentificationTable},
+    render::inventory::{compare_span, preview_equip},
+    system::inventory_system::get_equipped_in_slot,
+};
+
+/// The combat numbers of a single piece of equipment that are worth comparing.
+#[derive(Default)]
+struct ItemProfile {
+    damage: Option<DiceExpression>,
+    damage_type: Option<DamageType>,
+    range: Option<i32>,
+    defense: Option<i32>,
+}
+
+/**
+ * This render function fires alongside the inventory, trading and equipment screens
+ * whenever the highlighted item can be equipped.
+ *
+ * Lines the item up against whatever the player has in the same slot,
+ * followed by every derived stat that would change by swapping the two.
+ */
+pub fn render_comparison(frame: &mut Frame, area: Rect, lines: Vec<Line>) {
+    frame.render_widget(
+        Paragraph::new(lines).block(
+            Block::new()
+                .title("Comparison")
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center)
+                .padding(Padding::horizontal(1)),
+        ),
+        area,
+    );
+}
+
+/// Builds the comparison between the given item and the player's equipment,
+/// or nothing if the item can't be equipped.
+pub fn format_comparison<'a>(ecs: &World, item: Entity) -> Option<Vec<Line<'a>>> {
+    let player_entity = ecs.fetch::<Entity>();
+    let names = ecs.read_storage::<Name>();
+    let equippables = ecs.read_storage::<Equippable>();
+    let derived_stats = ecs.read_storage::<DerivedStats>();
+    let identification = ecs.fetch::<IdentificationTable>();
+
+    let slot = equippables.get(item)?.slot;
+    let derived = derived_stats.get(*player_entity)?.clone();
+    let worn = get_equipped_in_slot(ecs, *player_entity, slot);
+    let display_name = |entity: Entity| {
+        names.get(entity).map(|n| identification.display_name(&n.name)).unwrap_or("???".to_string())
+    };
+
+    /*
+     * An equipped item is compared against an empty slot, i.e. what taking it off would do.
+     */
+    let mut lines: Vec<Line> = Vec::new();
+    let (before, after) = match worn {
+        Some(worn) if worn == item => {
+            lines.push(Line::from(Span::styled("You have this equipped.", Style::new().fg(Color::Green))));
+            (profile(ecs, item), ItemProfile::default())
+        }
+        Some(worn) => {
+            lines.push(Line::from(vec![
+                Span::raw("Compared with your "),
+                Span::styled(display_name(worn), Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+            ]));
+            (profile(ecs, worn), profile(ecs, item))
+        }
+        None => {
+            lines.push(Line::from(format!("Nothing in your {:?} slot.", slot)));
+            (ItemProfile::default(), profile(ecs, item))
+        }
+    };
+
+    let dice = |profile: &ItemProfile| profile.damage.as_ref().map(|damage| damage.to_expression());
+    let average = |profile: &ItemProfile| profile.damage.as_ref().map(|damage| damage.average());
+    lines.extend(compare_line("Damage", dice(&before), dice(&after), None));
+    lines.extend(compare_line(
+        "Average damage",
+        average(&before).map(|average| format!("{:.1}", average)),
+        average(&after).map(|average| format!("{:.1}", average)),
+        better(average(&before), average(&after)),
+    ));
+    lines.extend(compare_line(
+        "Damage type",
+        before.damage_type.map(|damage_type| format!("{:?}", damage_type)),
+        after.damage_type.map(|damage_type| format!("{:?}", damage_type)),
+        None,
+    ));
+    lines.extend(compare_line(
+        "Range",
+        before.range.map(|range| range.to_string()),
+        after.range.map(|range| range.to_string()),
+        better(before.range, after.range),
+    ));
+    lines.extend(compare_line(
+        "Defense",
+        before.defense.map(|defense| defense.to_string()),
+        after.defense.map(|defense| defense.to_string()),
+        better(before.defense, after.defense),
+    ));
+
+    /*
+     * Only the derived stats that actually move are listed, to keep the pane short.
+     */
+    let after_derived = preview_equip(ecs, item)?;
+    let changes: Vec<Line> = [
+        ("Max HP", derived.max_hp, after_derived.max_hp, false),
+        ("Armor class", derived.armor_class, after_derived.armor_class, false),
+        ("Melee to hit", derived.melee_to_hit, after_derived.melee_to_hit, true),
+        ("Ranged to hit", derived.ranged_to_hit, after_derived.ranged_to_hit, true),
+        ("Spell to hit", derived.spell_to_hit, after_derived.spell_to_hit, true),
+        ("Melee damage", derived.melee_damage, after_derived.melee_damage, true),
+        ("Ranged damage", derived.ranged_damage, after_derived.ranged_damage, true),
+        ("Spell power", derived.spell_power, after_derived.spell_power, true),
+        ("Perception", derived.perception, after_derived.perception, true),
+        ("Carry capacity", derived.carry_capacity, after_derived.carry_capacity, false),
+        ("Speed", derived.speed, after_derived.speed, true),
+    ]
+    .into_iter()
+    .filter(|(_, before, after, _)| before != after)
+    .map(|(label, before, after, signed)| Line::from(vec![Span::raw(format!("{}: ", label)), compare_span(before, after, signed)]))
+    .collect();
+
+    lines.push(Line::from(""));
+    if changes.is_empty() {
+        lines.push(Line::from(Span::styled("No change to your stats.", Style::new().fg(Color::Gray))));
+    } else {
+        lines.extend(changes);
+    }
+    return Some(lines);
+}
+
+/// Gathers the comparable numbers of an item from whichever weapon or armor components it has.
+fn profile(ecs: &World, item: Entity) -> ItemProfile {
+    let melee_weapons = ecs.read_storage::<MeleeWeapon>();
+    let ranged_weapons = ecs.read_storage::<RangedWeapon>();
+    let magic_weapons = ecs.read_storage::<MagicWeapon>();
+    let armors = ecs.read_storage::<Armor>();
+
+    let mut profile = ItemProfile {
+        defense: armors.get(item).map(|armor| armor.defense),
+        ..Default::default()
+    };
+    if let Some(melee) = melee_weapons.get(item) {
+        profile.damage = Some(melee.damage.clone());
+        profile.damage_type = Some(melee.damage_type);
+        profile.range = Some(1);
+    }
+    if let Some(ranged) = ranged_weapons.get(item) {
+        profile.damage = Some(ranged.damage.clone());
+        profile.damage_type = Some(ranged.damage_type);
+        profile.range = Some(ranged.range);
+    }
+    if let Some(magic) = magic_weapons.get(item) {
+        profile.range = Some(magic.range);
+    }
+    return profile;
+}
+
+/// Whether `after` is an improvement over `before`, if the two can be compared at all.
+fn better<T: PartialOrd>(before: Option<T>, after: Option<T>) -> Option<bool> {
+    return match (before, after) {
+        (Some(before), Some(after)) if before != after => Some(after > before),
+        _ => None,
+    };
+}
+
+/// A single `label: before -> after` row, skipped when neither item has the value.
+fn compare_line<'a>(label: &str, before: Option<String>, after: Option<String>, better: Option<bool>) -> Option<Line<'a>> {
+    if before.is_none() && after.is_none() {
+        return None;
+    }
+    let before = before.unwrap_or("-".to_string());
+    let after = after.unwrap_or("-".to_string());
+    let value = if before == after {
+        Span::raw(before)
+    } else {
+        Span::styled(
+            format!("{} -> {}", before, after),
+            match better {
+                Some(true) => Style::new().fg(Color::Green),
+                Some(false) => Style::new().fg(Color::Red),
+                None => Style::new().fg(Color::Yellow),
+            },
+        )
+    };
+    return Some(Line::from(vec![Span::raw(format!("{}: ", label)), value]));
+}
@@ -0,0 +1,140 @@
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style, palette::tailwind::SLATE},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Padding},
+};
+use specs::prelude::*;
+
+use crate::{
+    component::{Cursed, Durability, EquipmentSlot, Name, Rarity, RarityTier},
+    generate::identification::IdentificationTable,
+    render::compare::{format_comparison, render_comparison},
+    system::inventory_system::{get_equipped_in_slot, get_slot_candidates},
+};
+
+/**
+ * This render function fires when the player is ingame and viewing their equipment.
+ *
+ * Lays out every `EquipmentSlot` with whatever fills it, alongside the backpack items
+ * that fit the selected slot and a comparison for the item that would be swapped in or out.
+ */
+pub fn render_equipment(ecs: &mut World, index: usize, candidate: Option<usize>, frame: &mut Frame) {
+    let player_entity = *ecs.fetch::<Entity>();
+    let names = ecs.read_storage::<Name>();
+    let rarities = ecs.read_storage::<Rarity>();
+    let curses = ecs.read_storage::<Cursed>();
+    let durabilities = ecs.read_storage::<Durability>();
+    let identification = ecs.fetch::<IdentificationTable>();
+
+    let slot = EquipmentSlot::ALL[index];
+    let candidates = get_slot_candidates(ecs, player_entity, slot);
+    let format_item = |item: Entity| {
+        vec![
+            Span::styled(
+                names.get(item).map(|n| identification.display_name(&n.name)).unwrap_or("???".to_string()),
+                Style::new()
+                    .fg(rarities.get(item).map(|r| r.tier.color()).unwrap_or(RarityTier::Common.color()))
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                if curses.get(item).is_some_and(|curse| curse.known) { " [cursed]" } else { "" },
+                Style::new().fg(Color::Red),
+            ),
+            Span::styled(
+                if durabilities.get(item).is_some_and(|durability| durability.is_broken()) { " [broken]" } else { "" },
+                Style::new().fg(Color::Red),
+            ),
+        ]
+    };
+
+    let slot_list: Vec<ListItem> = EquipmentSlot::ALL
+        .iter()
+        .map(|slot| {
+            let mut spans = vec![Span::styled(format!(" {:8} ", format!("{:?}", slot)), Style::new().fg(Color::Gray))];
+            match get_equipped_in_slot(ecs, player_entity, *slot) {
+                Some(item) => spans.extend(format_item(item)),
+                None => spans.push(Span::styled("__", Style::new().fg(Color::DarkGray))),
+            }
+            ListItem::new(vec![Line::from(""), Line::from(spans)])
+        })
+        .collect();
+
+    let mut candidate_list: Vec<ListItem> = candidates
+        .iter()
+        .map(|item| {
+            let mut spans = vec![Span::raw(" ")];
+            spans.extend(format_item(*item));
+            ListItem::new(vec![Line::from(""), Line::from(spans)])
+        })
+        .collect();
+    if candidate_list.is_empty() {
+        candidate_list.push(ListItem::new(vec![
+            Line::from(""),
+            Line::from(format!(" Nothing in your backpack fits your {:?} slot.", slot)),
+        ]));
+    }
+
+    /*
+     * Compare the backpack item being chosen, otherwise whatever currently fills the slot.
+     */
+    let compared = match candidate {
+        Some(candidate) => candidates.get(candidate).copied(),
+        None => get_equipped_in_slot(ecs, player_entity, slot),
+    };
+    let comparison = compared.and_then(|item| format_comparison(ecs, item));
+
+    let root_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(frame.area());
+
+    let backpack_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Fill(1),
+            Constraint::Length(comparison.as_ref().map(|lines| lines.len() as u16 + 2).unwrap_or(0)),
+        ])
+        .split(root_layout[1]);
+
+    frame.render_stateful_widget(
+        List::new(slot_list)
+            .block(
+                Block::new()
+                    .title("Equipment")
+                    .title_bottom("[enter] Choose  [u] Unequip  [e] Close")
+                    .borders(Borders::ALL)
+                    .title_alignment(Alignment::Center)
+                    .padding(Padding::horizontal(1)),
+            )
+            .highlight_style(if candidate.is_none() {
+                Style::new().bg(SLATE.c800).add_modifier(Modifier::BOLD)
+            } else {
+                Style::new().bg(SLATE.c900)
+            })
+            .highlight_spacing(ratatui::widgets::HighlightSpacing::Never),
+        root_layout[0],
+        &mut ListState::default().with_selected(Some(index)),
+    );
+
+    frame.render_stateful_widget(
+        List::new(candidate_list)
+            .block(
+                Block::new()
+                    .title(format!("Backpack ({:?})", slot))
+                    .title_bottom(if candidate.is_some() { "[enter] Equip  [esc] Back" } else { "" })
+                    .borders(Borders::ALL)
+                    .title_alignment(Alignment::Center)
+                    .padding(Padding::horizontal(1)),
+            )
+            .highlight_style(Style::new().bg(SLATE.c800).add_modifier(Modifier::BOLD))
+            .highlight_spacing(ratatui::widgets::HighlightSpacing::Never),
+        backpack_layout[0],
+        &mut ListState::default().with_selected(candidate),
+    );
+
+    if let Some(lines) = comparison {
+        render_comparison(frame, backpack_layout[1], lines);
+    }
+}
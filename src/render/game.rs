@@ -7,15 +7,34 @@ use ratatui::{
 };
 use rltk::Point;
 use specs::prelude::*;
+use std::collections::HashMap;
 
 use crate::{
-    RunState, component::{
-        EquipmentSlot, Equipped, Hidden, Inventory, Item, MagicWeapon, Monster, Name, Npc, Pool, Position, RangedWeapon, Renderable, Stats
-    }, generate::map::{Map, TileType}, logbook::logbook::format_latest_text, render::base::centered_rect, system::ranged_combat_system::get_eligible_ranged_tiles
+    RunState,
+    component::{
+        AlertState, Alertness, Ammunition, Area, AreaShape, Cursed, DerivedStats, EquipmentSlot,
+        Equipped, Fragile, Hidden, Inventory, Item, MagicWeapon, Monster, Name, Npc, Pool,
+        Position, RangedWeapon, Renderable, Shrine, SpellKnowledge, Stats, StatusType, Statuses,
+        Throwing,
+    },
+    generate::{
+        identification::IdentificationTable,
+        map::{Map, TileType},
+    },
+    logbook::logbook::format_latest_text,
+    render::{base::centered_rect, inventory::format_load},
+    system::{
+        derived_stats_system::effective_max_hp,
+        inventory_system::get_quivered_ammo,
+        ranged_combat_system::{get_area_tiles, get_eligible_ranged_tiles},
+        throw_system::throw_range,
+    },
 };
 
 pub const VIEW_WIDTH: i32 = 80;
 pub const VIEW_HEIGHT: i32 = 50;
+/// Drawn in place of the topmost item on any tile holding more than one item.
+pub const PILE_GLYPH: char = '&';
 
 /**
  * The base render function for the game itself.
@@ -42,10 +61,20 @@ pub fn render_game(ecs: &mut World, frame: &mut Frame, floor_index: u32, _termin
     let inventory = ecs.read_storage::<Inventory>();
     let names = ecs.read_storage::<Name>();
     let items = ecs.read_storage::<Item>();
+    let curses = ecs.read_storage::<Cursed>();
+    let shrines = ecs.read_storage::<Shrine>();
     let monsters = ecs.read_storage::<Monster>();
     let ranged_weapons = ecs.read_storage::<RangedWeapon>();
+    let ammunition = ecs.read_storage::<Ammunition>();
     let magic_weapons = ecs.read_storage::<MagicWeapon>();
     let equipped = ecs.read_storage::<Equipped>();
+    let alertness = ecs.read_storage::<Alertness>();
+    let identification = ecs.fetch::<IdentificationTable>();
+    let statuses = ecs.read_storage::<Statuses>();
+    let spell_knowledge = ecs.read_storage::<SpellKnowledge>();
+    let throwings = ecs.read_storage::<Throwing>();
+    let fragiles = ecs.read_storage::<Fragile>();
+    let derived_stats = ecs.read_storage::<DerivedStats>();
 
     // Define the min (top left), and max (bottom right) of the viewport
     let center = Point {
@@ -106,11 +135,20 @@ pub fn render_game(ecs: &mut World, frame: &mut Frame, floor_index: u32, _termin
      * If the existing span has a background set, we keep that (e.g. bloodstain).
      * Otherwise, we use the renderable's desired background.
      */
-    let mut renderable_entities = (&positions, &renderables, !&hidden)
+    let mut renderable_entities = (&entities, &positions, &renderables, !&hidden)
         .join()
         .collect::<Vec<_>>();
-    renderable_entities.sort_by(|&a, &b| b.1.index.cmp(&a.1.index));
-    for (pos, render, _hidden) in renderable_entities.iter() {
+    renderable_entities.sort_by(|&a, &b| b.2.index.cmp(&a.2.index));
+
+    /*
+     * Tiles holding several items are drawn as a single pile glyph,
+     * anything standing on top of the pile is still drawn over it.
+     */
+    let mut pile_sizes: HashMap<(i32, i32), i32> = HashMap::new();
+    for (_item, pos, _hidden) in (&items, &positions, !&hidden).join() {
+        *pile_sizes.entry((pos.x, pos.y)).or_insert(0) += 1;
+    }
+    for (entity, pos, render, _hidden) in renderable_entities.iter() {
         // Renderable has not yet been revealed by the player
         if !map.revealed_tiles[map.xy_idx(pos.x, pos.y)] {
             continue;
@@ -126,11 +164,12 @@ pub fn render_game(ecs: &mut World, frame: &mut Frame, floor_index: u32, _termin
             y: pos.y - map_min.y,
         };
 
+        let is_pile = items.contains(*entity) && pile_sizes.get(&(pos.x, pos.y)).is_some_and(|size| *size > 1);
         let existing_span = lines[view_pos.y as usize].spans[view_pos.x as usize].clone();
         lines[view_pos.y as usize].spans[view_pos.x as usize] = Span::styled(
-            render.glyph.to_string(),
+            if is_pile { PILE_GLYPH.to_string() } else { render.glyph.to_string() },
             Style::default()
-                .fg(render.fg)
+                .fg(if is_pile { Color::White } else { render.fg })
                 .bg(existing_span.style.bg.unwrap_or_else(|| render.bg)),
         );
     }
@@ -165,31 +204,44 @@ pub fn render_game(ecs: &mut World, frame: &mut Frame, floor_index: u32, _termin
             );
         }
         RunState::FreeAiming { index } => {
-            for (entity, equipped, _) in (&entities, &equipped, &ranged_mask).join() {
-                let ranged = ranged_weapons.get(entity);
-                let magic = magic_weapons.get(entity);
-                let range = ranged.map(|r| r.range).unwrap_or_else(|| magic.map(|m| m.range).unwrap_or(0));
-                if equipped.slot == EquipmentSlot::Weapon && equipped.owner == *player_entity {
-                    let eligible_tiles =
-                        get_eligible_ranged_tiles(&map, &player_position, range);
-                    for tile_index in eligible_tiles.iter() {
-                        let (tile_x, tile_y) = map.idx_xy(*tile_index);
-                        let view_pos = Position {
-                            x: tile_x - map_min.x,
-                            y: tile_y - map_min.y,
-                        };
-                        let existing_span =
-                            lines[view_pos.y as usize].spans[view_pos.x as usize].clone();
-                        lines[view_pos.y as usize].spans[view_pos.x as usize] = Span::styled(
-                            existing_span.content,
-                            Style::default()
-                                .fg(existing_span.style.fg.unwrap_or(Color::White))
-                                .bg(if index == *tile_index {
-                                    Color::Red
-                                } else {
-                                    Color::LightGreen
-                                }),
-                        );
+            /*
+             * Throws reach as far as the thrower can hurl the readied item,
+             * and fragile items preview everything their splash would catch.
+             */
+            if let Some(throwing) = throwings.get(*player_entity) {
+                let range = throw_range(ecs, *player_entity, throwing.item);
+                let eligible_tiles = get_eligible_ranged_tiles(&map, &player_position, range);
+                highlight_aim_tiles(&mut lines, &map, map_min, map_max, &eligible_tiles, index, Color::LightGreen);
+                if let Some(fragile) = fragiles.get(throwing.item) {
+                    let (aim_x, aim_y) = map.idx_xy(index);
+                    let aim = Point::new(aim_x, aim_y);
+                    let area = Area { shape: AreaShape::Ball, size: fragile.radius };
+                    let area_tiles = get_area_tiles(&map, aim, aim, &area);
+                    highlight_aim_tiles(&mut lines, &map, map_min, map_max, &area_tiles, index, Color::LightRed);
+                }
+            } else {
+                for (entity, equipped, _) in (&entities, &equipped, &ranged_mask).join() {
+                    let ranged = ranged_weapons.get(entity);
+                    let magic = magic_weapons.get(entity);
+                    let range = ranged.map(|r| r.range).unwrap_or_else(|| magic.map(|m| m.range).unwrap_or(0));
+                    if equipped.slot == EquipmentSlot::Weapon && equipped.owner == *player_entity {
+                        let eligible_tiles =
+                            get_eligible_ranged_tiles(&map, &player_position, range);
+                        highlight_aim_tiles(&mut lines, &map, map_min, map_max, &eligible_tiles, index, Color::LightGreen);
+
+                        /*
+                         * Area spells additionally preview every tile that would be caught in the blast.
+                         */
+                        let area = spell_knowledge
+                            .get(*player_entity)
+                            .and_then(|known| known.active_spell())
+                            .and_then(|spell| spell.area);
+                        if let (Some(area), Some(_magic)) = (area, magic) {
+                            let (aim_x, aim_y) = map.idx_xy(index);
+                            let area_tiles =
+                                get_area_tiles(&map, *player_position, Point::new(aim_x, aim_y), &area);
+                            highlight_aim_tiles(&mut lines, &map, map_min, map_max, &area_tiles, index, Color::LightRed);
+                        }
                     }
                 }
             }
@@ -242,7 +294,74 @@ pub fn render_game(ecs: &mut World, frame: &mut Frame, floor_index: u32, _termin
         .name
         .clone();
     let player_floor = format!("Floor: {}", floor_index);
-    let pools = format_pools(&player, stats, inventory).expect("Unable to format player pools!");
+    let player_statuses: Vec<Span> = statuses
+        .get(*player)
+        .map(|s| {
+            s.statuses
+                .iter()
+                .map(|status| {
+                    Span::styled(
+                        format!("{:?} ({}) ", status.status_type, status.duration),
+                        Style::new().fg(match status.status_type {
+                            StatusType::Poisoned => Color::LightMagenta,
+                            StatusType::Regenerating => Color::LightGreen,
+                        }),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let player_spell: Line = match spell_knowledge.get(*player).and_then(|known| known.active_spell()) {
+        Some(spell) => {
+            let mana = stats.get(*player).map(|s| s.mp.current).unwrap_or(0);
+            Line::from(vec![
+                Span::styled("Spell: ", Style::new().fg(Color::Gray)),
+                Span::styled(format!("{} ", spell.name), Style::new().fg(Color::Cyan)),
+                if mana >= spell.mp_cost {
+                    Span::styled(format!("({} mp)", spell.mp_cost), Style::new().fg(Color::Blue))
+                } else {
+                    Span::styled(format!("({} mp, not enough mana)", spell.mp_cost), Style::new().fg(Color::Red))
+                },
+            ])
+        }
+        None => Line::from(""),
+    };
+
+    /*
+     * Ranged weapons that need ammunition show what is left in the quiver.
+     */
+    let ammo_type = (&equipped, &ranged_weapons)
+        .join()
+        .find(|(equipped, _)| equipped.slot == EquipmentSlot::Weapon && equipped.owner == *player)
+        .and_then(|(_, ranged)| ranged.ammo);
+    let player_ammo: Line = match ammo_type {
+        Some(ammo_type) => {
+            match get_quivered_ammo(*player, ammo_type, &entities, &equipped, &ammunition) {
+                Some(ammo) => {
+                    let ammo_name = names.get(ammo).map(|n| n.name.clone()).unwrap_or_default();
+                    let count = inventory
+                        .get(*player)
+                        .and_then(|inv| inv.stack_of(ammo))
+                        .map(|stack| stack.len())
+                        .unwrap_or(0);
+                    Line::from(vec![
+                        Span::styled("Quiver: ", Style::new().fg(Color::Gray)),
+                        Span::styled(format!("{} x {}", ammo_name, count), Style::new().fg(Color::Yellow)),
+                    ])
+                }
+                None => Line::from(vec![
+                    Span::styled("Quiver: ", Style::new().fg(Color::Gray)),
+                    Span::styled(format!("no {:?} ammunition", ammo_type), Style::new().fg(Color::Red)),
+                ]),
+            }
+        }
+        None => Line::from(""),
+    };
+    let player_load: Line = match derived_stats.get(*player) {
+        Some(derived) => Line::from(Span::styled(format_load(derived), Style::new().fg(derived.encumbrance.color()))),
+        None => Line::from(""),
+    };
+    let pools = format_pools(&player, stats, &derived_stats, inventory).expect("Unable to format player pools!");
 
     /*
      * Fetch and truncate the most recent logbook entries,
@@ -261,14 +380,26 @@ pub fn render_game(ecs: &mut World, frame: &mut Frame, floor_index: u32, _termin
                         if !serialized_examine.is_empty() {
                             serialized_examine.push('\n');
                         }
-                        serialized_examine.push_str(&name.name);
+                        serialized_examine.push_str(&identification.display_name(&name.name));
                     }
 
                     if let Some(item) = item {
                         if !serialized_examine.is_empty() {
                             serialized_examine.push('\n');
                         }
-                        serialized_examine.push_str(&item.description);
+                        let name = name.map(|n| n.name.as_str()).unwrap_or_default();
+                        serialized_examine.push_str(&identification.display_description(name, &item.description));
+                        if curses.get(*entity).is_some_and(|curse| curse.known) {
+                            serialized_examine.push_str("\nIt is cursed.");
+                        }
+                    }
+
+                    if let Some(shrine) = shrines.get(*entity) {
+                        serialized_examine.push_str(if shrine.used {
+                            "\nThe shrine has gone cold."
+                        } else {
+                            "\nA faint light flickers here. Praying may lift a curse."
+                        });
                     }
 
                     if let Some(monster) = monster {
@@ -278,11 +409,27 @@ pub fn render_game(ecs: &mut World, frame: &mut Frame, floor_index: u32, _termin
                         serialized_examine.push_str(&monster.description);
                     }
 
+                    match alertness.get(*entity).map(|a| a.state) {
+                        Some(AlertState::Asleep) => serialized_examine.push_str("\nIt appears to be fast asleep."),
+                        Some(AlertState::Unaware) => serialized_examine.push_str("\nIt hasn't noticed you yet."),
+                        _ => {}
+                    }
+
                     if name.is_some() || item.is_some() {
                         break; // only examine the first entity
                     }
                 }
 
+                // Piles only describe their first item, so point at the rest
+                let (x, y) = map.idx_xy(index);
+                let pile_size = pile_sizes.get(&(x, y)).copied().unwrap_or(0);
+                if pile_size > 1 {
+                    serialized_examine.push_str(&format!(
+                        "\nThere is a pile of {} items here. [g] to look through them.",
+                        pile_size
+                    ));
+                }
+
                 // Always include TileType details below entity details
                 if let Some(tile_type) = map.tiles.get(index) {
                     if !serialized_examine.is_empty() {
@@ -329,7 +476,7 @@ pub fn render_game(ecs: &mut World, frame: &mut Frame, floor_index: u32, _termin
 
     let right_vertical_layout = Layout::default()
         .direction(Direction::Vertical)
-        .constraints(vec![Constraint::Length(6), Constraint::Length(6)])
+        .constraints(vec![Constraint::Length(9), Constraint::Length(6)])
         .split(right_inner);
 
     frame.render_widget(Paragraph::new(Text::from(lines)), left_vertical_layout[0]);
@@ -363,6 +510,10 @@ pub fn render_game(ecs: &mut World, frame: &mut Frame, floor_index: u32, _termin
                 Span::styled(pools.exp.2, Style::new().bg(Color::LightMagenta)),
                 Span::styled(pools.exp.3, Style::new().bg(Color::Rgb(60, 60, 60))),
             ]),
+            player_load,
+            player_spell,
+            player_ammo,
+            Line::from(player_statuses),
         ]))
         .block(Block::new().borders(Borders::NONE)),
         right_vertical_layout[0],
@@ -415,16 +566,47 @@ pub struct FormattedPools {
 /*
  * Format the status bar with health, gold, etc.
  */
+/// Highlights the given tiles within the viewport, marking the aimed at tile in red.
+fn highlight_aim_tiles(
+    lines: &mut [Line],
+    map: &Map,
+    map_min: Point,
+    map_max: Point,
+    tiles: &[usize],
+    aim_index: usize,
+    color: Color,
+) {
+    for tile_index in tiles.iter() {
+        let (tile_x, tile_y) = map.idx_xy(*tile_index);
+        if tile_x < map_min.x || map_max.x <= tile_x || tile_y < map_min.y || map_max.y <= tile_y {
+            continue;
+        }
+        let view_pos = Position {
+            x: tile_x - map_min.x,
+            y: tile_y - map_min.y,
+        };
+        let existing_span = lines[view_pos.y as usize].spans[view_pos.x as usize].clone();
+        lines[view_pos.y as usize].spans[view_pos.x as usize] = Span::styled(
+            existing_span.content,
+            Style::default()
+                .fg(existing_span.style.fg.unwrap_or(Color::White))
+                .bg(if aim_index == *tile_index { Color::Red } else { color }),
+        );
+    }
+}
+
 pub fn format_pools(
     player: &Entity,
     stats: ReadStorage<Stats>,
+    derived_stats: &ReadStorage<DerivedStats>,
     inventory: ReadStorage<Inventory>,
 ) -> Option<FormattedPools> {
     return match (stats.get(*player), inventory.get(*player)) {
         (Some(stats), Some(_inventory)) => {
-            let player_hp = format!("HP: {} / {} ", stats.hp.current, stats.hp.max);
+            let max_hp = effective_max_hp(stats, derived_stats.get(*player));
+            let player_hp = format!("HP: {} / {} ", stats.hp.current, max_hp);
             let hp_bar_remaining =
-                ((stats.hp.current as f64 / stats.hp.max as f64) * (25 as f64)).round() as usize;
+                ((stats.hp.current as f64 / max_hp as f64) * (25 as f64)).round() as usize;
             let player_hp_remaining = " ".repeat(hp_bar_remaining);
             let player_hp_total = " ".repeat(25 - hp_bar_remaining);
 
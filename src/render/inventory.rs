@@ -3,11 +3,11 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout},
     style::{Color, Modifier, Style, Stylize, palette::tailwind::SLATE},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, List, ListItem, ListState, Padding, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
+    widgets::{Block, Borders, List, ListItem, ListState, Padding, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Tabs},
 };
 use specs::prelude::*;
 
-use crate::{RunState, component::{Armor, AttackType, EquipmentSlot, Equippable, Equipped, Inventory, Item, MagicWeapon, MeleeWeapon, Name, RangedWeapon, Stats}, render::game::format_pools, system::inventory_system::get_equipped_weapon};
+use crate::{RunState, component::{Ammunition, Armor, AttackType, Cursed, DerivedStats, Durability, Encumbrance, EquipmentSlot, Equippable, Equipped, FeatType, Feats, Inventory, InventoryTab, Item, MagicWeapon, MeleeWeapon, Modifiers, Name, PickingItem, RangedWeapon, Rarity, RarityTier, Stats, Statuses, Viewshed}, generate::{character::meets_prerequisites, config::FeatConfig, identification::{IdentificationTable, UNIDENTIFIED_DESCRIPTION}, spawn::FEATS}, render::{compare::{format_comparison, render_comparison}, game::format_pools}, system::{derived_stats_system::{derive_stats, equipment_totals}, inventory_system::{get_equipped_weapon, get_inventory_view, pick_from_stack}}};
 
 /**
  * This render function fires when the player is ingame and viewing their inventory.
@@ -19,15 +19,15 @@ pub fn render_inventory(ecs: &mut World, runstate: RunState, frame: &mut Frame)
     let entities = ecs.entities();
     let player_entity = ecs.fetch::<Entity>();
     let inventories = ecs.read_storage::<Inventory>();
-    let items = ecs.read_storage::<Item>();
     let equipment = ecs.read_storage::<Equipped>();
     let names = ecs.read_storage::<Name>();
     let stats = ecs.read_storage::<Stats>();
     let melee_weapons = ecs.read_storage::<MeleeWeapon>();
     let ranged_weapons = ecs.read_storage::<RangedWeapon>();
     let magic_weapons = ecs.read_storage::<MagicWeapon>();
-    let armors = ecs.read_storage::<Armor>();
-    let equippables = ecs.read_storage::<Equippable>();
+    let identification = ecs.fetch::<IdentificationTable>();
+    let feats = ecs.read_storage::<Feats>();
+    let derived_stats = ecs.read_storage::<DerivedStats>();
 
     let inventory = inventories
         .get(*player_entity)
@@ -43,38 +43,82 @@ pub fn render_inventory(ecs: &mut World, runstate: RunState, frame: &mut Frame)
 
     let gold = inventory.gold;
 
+    let derived = derived_stats.get(*player_entity).cloned().unwrap_or_default();
+
+    /*
+     * Hovering an equippable item previews the stats the player would end up with
+     * after equipping (or removing) it, shown as `before -> after` in the character panel.
+     */
+    let inventory_view = inventory.view.clone();
+    let view = get_inventory_view(ecs, inventory);
+    let inventory_index = usize::min(inventory.index, view.len().saturating_sub(1));
+    let hovered = view
+        .get(inventory_index)
+        .and_then(|index| inventory.items.get_index(*index))
+        .and_then(|(_, stack)| pick_from_stack(stack, &equipment, true));
+    let preview = match runstate {
+        RunState::LevelUp { index: _ } | RunState::FeatSelection { index: _ } => None,
+        _ => hovered.and_then(|item| preview_equip(ecs, item)),
+    };
+    let after = preview.unwrap_or(derived.clone());
+
     let weapon: Option<(AttackType, Entity)> = get_equipped_weapon(
         *player_entity, &entities, &equipment, &melee_weapons, &ranged_weapons, &magic_weapons
     );
 
     /*
-     * Just render inventory in order acquired for now, with no grouping.
+     * Only the stacks let through by the current tab and search are listed,
+     * in the order picked by the player, see `get_inventory_view`.
      */
-    let mut inventory_list: Vec<ListItem> = inventory.items.iter().enumerate()
-        .map(|item| format_inventory_item(
-            item.1.0.clone(),
-            item.1.1.first().expect("Unable to retrieve inventory item entity (top of stack)").clone(),
-            item.1.1.len(),
-            &items,
-            &equipment,
-            &melee_weapons,
-            &ranged_weapons,
-            &magic_weapons,
-            &armors,
-            &equippables,
+    let item_storages = ItemStorages::fetch(ecs);
+    let mut inventory_list: Vec<ListItem> = view.iter()
+        .filter_map(|index| inventory.items.get_index(*index))
+        .map(|(signature, stack)| format_inventory_item(
+            identification.display_name(&signature.name),
+            pick_from_stack(stack, &equipment, true).expect("Unable to retrieve inventory item entity (top of stack)"),
+            stack.len(),
+            identification.is_identified(&signature.name),
+            inventory.selected.contains(signature),
+            None,
+            &item_storages,
         )).collect();
 
     let mut state = ListState::default();
-    let inventory_index = inventory.index;
-    if inventory_list.is_empty() {
+    let mut inventory_index = inventory_index;
+    let mut inventory_title = "Inventory";
+    let mut inventory_keys = "[enter] Use  [t] Throw  [d/D] Drop  [x/X] Destroy  [v] Mark  [i] Close";
+    if ecs.read_storage::<PickingItem>().contains(*player_entity) {
+        inventory_title = "Use on which item?";
+        inventory_keys = "[enter] Pick  [esc] Cancel";
+    }
+    if inventory.items.is_empty() {
         inventory_list.push(ListItem::from("Your inventory is empty!".to_string()));
+    } else if inventory_list.is_empty() {
+        inventory_list.push(ListItem::from("Nothing here matches.".to_string()));
     } else {
         state.select(Some(inventory_index));
     }
 
-    let pools = format_pools(&player_entity, stats.clone(), inventories).expect("Unable to format player pools!");
+    /*
+     * While choosing a feat, the feat list takes the place of the inventory.
+     */
+    let all_feats = FEATS.lock().unwrap();
+    let known_feats = feats.get(*player_entity);
+    if let RunState::FeatSelection { index } = runstate {
+        let default_feats = Feats::default();
+        let known = known_feats.unwrap_or(&default_feats);
+        inventory_list = all_feats
+            .iter()
+            .map(|feat| format_feat(feat, &all_feats, meets_prerequisites(feat, stat, known), known.has(feat.feat)))
+            .collect();
+        inventory_index = index;
+        inventory_title = "Level Up! Choose a feat.";
+        state.select(Some(index));
+    }
+
+    let pools = format_pools(&player_entity, stats.clone(), &derived_stats, inventories).expect("Unable to format player pools!");
 
-    let fstat = format_stats(stat, runstate);
+    let fstat = format_stats(&derived, &after, runstate);
 
     let attribute_title = match runstate {
         RunState::LevelUp { index: _ } => "Level Up! Select an attribute to increase.",
@@ -90,24 +134,57 @@ pub fn render_inventory(ecs: &mut World, runstate: RunState, frame: &mut Frame)
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
         .split(frame.area());
 
+    let tabs_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(1)])
+        .split(root_layout[0]);
+
     let inventory_layout = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
             Constraint::Min(1),
             Constraint::Length(1),
         ])
-        .split(root_layout[0]);
+        .split(tabs_layout[1]);
 
+    /*
+     * Equippable items get a comparison against the player's gear below the character panel.
+     */
+    let comparison = match runstate {
+        RunState::LevelUp { index: _ } | RunState::FeatSelection { index: _ } => None,
+        _ => hovered.and_then(|item| format_comparison(ecs, item)),
+    };
     let character_layout = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Fill(1)])
+        .constraints([
+            Constraint::Fill(1),
+            Constraint::Length(comparison.as_ref().map(|lines| lines.len() as u16 + 2).unwrap_or(0)),
+        ])
         .split(root_layout[1]);
 
+    let search = match (inventory_view.filtering, inventory_view.filter.is_empty()) {
+        (true, _) => Span::styled(format!(" Search: {}_ ", inventory_view.filter), Style::new().fg(Color::LightYellow)),
+        (false, false) => Span::styled(format!(" Search: {} ", inventory_view.filter), Style::new().fg(Color::Yellow)),
+        (false, true) => Span::styled(" [/] Search ", Style::new().fg(Color::Gray)),
+    };
+    frame.render_widget(
+        Tabs::new(InventoryTab::ALL.iter().map(|tab| tab.label()))
+            .select(InventoryTab::ALL.iter().position(|tab| *tab == inventory_view.tab))
+            .highlight_style(Style::new().fg(Color::LightYellow).add_modifier(Modifier::BOLD))
+            .block(
+                Block::new()
+                    .title(Line::from(format!(" [tab] Category  [o] Sort: {} ", inventory_view.sort.label())).left_aligned())
+                    .title(Line::from(search).right_aligned())
+                    .borders(Borders::ALL),
+            ),
+        tabs_layout[0],
+    );
     frame.render_stateful_widget(
         List::new(inventory_list)
             .block(
                 Block::new()
-                    .title("Inventory")
+                    .title(inventory_title)
+                    .title_bottom(inventory_keys)
                     .borders(Borders::ALL)
                     .title_alignment(Alignment::Center)
                     .padding(Padding::uniform(1)),
@@ -152,6 +229,10 @@ pub fn render_inventory(ecs: &mut World, runstate: RunState, frame: &mut Frame)
                 format!("Gold: {}", gold),
                 Style::default().fg(Color::Yellow),
             )),
+            Line::from(Span::styled(
+                format_load(&derived),
+                Style::default().fg(derived.encumbrance.color()),
+            )),
             Line::from(""),
             Line::from(Span::styled(
                 attribute_title,
@@ -164,6 +245,74 @@ pub fn render_inventory(ecs: &mut World, runstate: RunState, frame: &mut Frame)
             Line::from(Span::styled(fstat.wisdom.0, fstat.wisdom.1)),
             Line::from(Span::styled(fstat.charisma.0, fstat.charisma.1)),
             Line::from(""),
+            Line::from(Span::styled(
+                "Derived",
+                Style::new().fg(Color::White).add_modifier(Modifier::BOLD),
+            )),
+            Line::from(vec![
+                Span::raw("Max HP: "),
+                compare_span(derived.max_hp, after.max_hp, false),
+                Span::raw("  Armor class: "),
+                compare_span(derived.armor_class, after.armor_class, false),
+            ]),
+            Line::from(vec![
+                Span::raw("To hit: "),
+                compare_span(derived.melee_to_hit, after.melee_to_hit, true),
+                Span::raw(" melee, "),
+                compare_span(derived.ranged_to_hit, after.ranged_to_hit, true),
+                Span::raw(" ranged, "),
+                compare_span(derived.spell_to_hit, after.spell_to_hit, true),
+                Span::raw(" spell"),
+            ]),
+            Line::from(vec![
+                Span::raw("Damage: "),
+                compare_span(derived.melee_damage, after.melee_damage, true),
+                Span::raw(" melee, "),
+                compare_span(derived.ranged_damage, after.ranged_damage, true),
+                Span::raw(" ranged, "),
+                compare_span(derived.spell_power, after.spell_power, true),
+                Span::raw(" spell"),
+            ]),
+            Line::from(vec![
+                Span::raw("Perception: "),
+                compare_span(derived.perception, after.perception, true),
+                Span::raw("  Sight: "),
+                compare_span(derived.sight_range, after.sight_range, false),
+            ]),
+            Line::from(vec![
+                Span::raw("Haggling: "),
+                compare_span(derived.price_modifier, after.price_modifier, true),
+                Span::raw("%  Carry capacity: "),
+                compare_span(derived.carry_capacity, after.carry_capacity, false),
+            ]),
+            Line::from(vec![
+                Span::raw("Mana regen: "),
+                compare_span(derived.equipment.mp_regeneration, after.equipment.mp_regeneration, true),
+                Span::raw("  Speed: "),
+                compare_span(derived.speed, after.speed, true),
+            ]),
+            Line::from(vec![
+                Span::raw("Resistances: "),
+                Span::styled(
+                    format_resistances(&after.equipment),
+                    if after.equipment.resistances == derived.equipment.resistances {
+                        Style::default()
+                    } else {
+                        Style::new().fg(Color::Yellow)
+                    },
+                ),
+            ]),
+            Line::from(""),
+            Line::from(Span::styled(
+                format!(
+                    "Feats: {}",
+                    known_feats
+                        .filter(|known| !known.feats.is_empty())
+                        .map(|known| known.feats.iter().map(|feat| feat_name(&all_feats, *feat)).collect::<Vec<String>>().join(", "))
+                        .unwrap_or("__".to_string())
+                ),
+                Style::default(),
+            )),
             Line::from(Span::styled(
                 format!(
                     "Weapon: {}",
@@ -182,6 +331,9 @@ pub fn render_inventory(ecs: &mut World, runstate: RunState, frame: &mut Frame)
         ),
         character_layout[0],
     );
+    if let Some(lines) = comparison {
+        render_comparison(frame, character_layout[1], lines);
+    }
 }
 
 fn format_weapon(
@@ -215,6 +367,42 @@ fn format_weapon(
     }
 }
 
+/// The storages needed to describe any item in a list, fetched together
+/// so that the inventory and trading screens can share `format_inventory_item`.
+pub struct ItemStorages<'a> {
+    pub items: ReadStorage<'a, Item>,
+    pub equipped: ReadStorage<'a, Equipped>,
+    pub melee_weapons: ReadStorage<'a, MeleeWeapon>,
+    pub ranged_weapons: ReadStorage<'a, RangedWeapon>,
+    pub ammunition: ReadStorage<'a, Ammunition>,
+    pub magic_weapons: ReadStorage<'a, MagicWeapon>,
+    pub armors: ReadStorage<'a, Armor>,
+    pub equippables: ReadStorage<'a, Equippable>,
+    pub modifiers: ReadStorage<'a, Modifiers>,
+    pub rarities: ReadStorage<'a, Rarity>,
+    pub curses: ReadStorage<'a, Cursed>,
+    pub durabilities: ReadStorage<'a, Durability>,
+}
+
+impl<'a> ItemStorages<'a> {
+    pub fn fetch(ecs: &'a World) -> Self {
+        ItemStorages {
+            items: ecs.read_storage::<Item>(),
+            equipped: ecs.read_storage::<Equipped>(),
+            melee_weapons: ecs.read_storage::<MeleeWeapon>(),
+            ranged_weapons: ecs.read_storage::<RangedWeapon>(),
+            ammunition: ecs.read_storage::<Ammunition>(),
+            magic_weapons: ecs.read_storage::<MagicWeapon>(),
+            armors: ecs.read_storage::<Armor>(),
+            equippables: ecs.read_storage::<Equippable>(),
+            modifiers: ecs.read_storage::<Modifiers>(),
+            rarities: ecs.read_storage::<Rarity>(),
+            curses: ecs.read_storage::<Cursed>(),
+            durabilities: ecs.read_storage::<Durability>(),
+        }
+    }
+}
+
 /// Render each inventory item using the given ecs datasets.
 ///
 /// We mainly want to show what items the user has in their inventory,
@@ -228,27 +416,36 @@ pub fn format_inventory_item<'a>(
     name: String,
     item_entity: Entity,
     count: usize,
-    items: &ReadStorage<Item>,
-    equipped: &ReadStorage<Equipped>,
-    melee_weapons: &ReadStorage<MeleeWeapon>,
-    ranged_weapons: &ReadStorage<RangedWeapon>,
-    magic_weapons: &ReadStorage<MagicWeapon>,
-    armors: &ReadStorage<Armor>,
-    equippables: &ReadStorage<Equippable>,
+    identified: bool,
+    selected: bool,
+    price: Option<i32>,
+    storages: &ItemStorages,
 ) -> ListItem<'a> {
-    let (base_value, description) = items.get(item_entity)
-        .map(|item| (item.base_value, item.description.clone()))
+    let (base_value, description) = storages.items.get(item_entity)
+        .map(|item| (price.unwrap_or(item.base_value), item.description.clone()))
         .unwrap_or((0, "???".to_string()));
+    let description = if identified { description } else { UNIDENTIFIED_DESCRIPTION.to_string() };
 
-    let slot = equippables.get(item_entity).map(|e| e.slot);
+    let slot = storages.equippables.get(item_entity).map(|e| e.slot);
+    let hands = match storages.equippables.get(item_entity) {
+        Some(equippable) if equippable.is_two_handed() => " | Two-handed",
+        Some(equippable) if equippable.fits_off_hand() => " | Light",
+        _ => "",
+    };
+    let name_color = storages.rarities.get(item_entity).map(|r| r.tier.color()).unwrap_or(RarityTier::Common.color());
 
     let top_line = Line::from(vec![
+        Span::styled(if selected { " [*]" } else { "" }, Style::new().fg(Color::LightYellow)),
         Span::styled(
-            if equipped.contains(item_entity) { " [equipped] " } else { " " },
+            if storages.equipped.contains(item_entity) { " [equipped] " } else { " " },
             Style::new().fg(Color::Green)
         ),
+        Span::styled(
+            if storages.curses.get(item_entity).is_some_and(|curse| curse.known) { "[cursed] " } else { "" },
+            Style::new().fg(Color::Red)
+        ),
         Span::styled(format!("[{} gold] ", base_value), Style::default().fg(Color::Yellow)),
-        Span::styled(name, Style::default().fg(Color::Cyan).bg(Color::Black).add_modifier(Modifier::BOLD)),
+        Span::styled(name, Style::default().fg(name_color).bg(Color::Black).add_modifier(Modifier::BOLD)),
         Span::styled(format!(" x {}", count), Style::default()),
     ]);
 
@@ -262,25 +459,52 @@ pub fn format_inventory_item<'a>(
         "".into(),
     ];
 
-    if let Some(melee) = melee_weapons.get(item_entity) {
+    if let Some(melee) = storages.melee_weapons.get(item_entity) {
         lines.push(Line::from(vec![
             Span::styled(" [weapon] Type: Melee | Damage: ", Style::default()),
             Span::styled(format!("{}", melee.damage.to_expression()), Style::default().fg(Color::Yellow)),
+            Span::styled(hands, Style::default()),
         ]));
         lines.push("".into());
     }
 
-    if let Some(ranged) = ranged_weapons.get(item_entity) {
+    if let Some(ranged) = storages.ranged_weapons.get(item_entity) {
         lines.push(Line::from(vec![
             Span::styled(" [weapon] Type: Ranged | Damage: ", Style::default()),
             Span::styled(format!("{}", ranged.damage.to_expression()), Style::default().fg(Color::Yellow)),
             Span::styled(" | Range: ", Style::default()),
             Span::styled(format!("{}", ranged.range), Style::default().fg(Color::Yellow)),
+            Span::styled(
+                ranged.ammo.map(|ammo| format!(" | Ammo: {:?}", ammo)).unwrap_or_default(),
+                Style::default(),
+            ),
+            Span::styled(hands, Style::default()),
         ]));
         lines.push("".into());
     }
 
-    if let Some(magic) = magic_weapons.get(item_entity) {
+    if let Some(ammo) = storages.ammunition.get(item_entity) {
+        let mut spans = vec![
+            Span::styled(format!(" [ammo] Type: {:?} | Damage: ", ammo.ammo_type), Style::default()),
+            Span::styled(format!("{:+}", ammo.damage_bonus), Style::default().fg(Color::Yellow)),
+        ];
+        if let Some(damage_type) = ammo.damage_type {
+            spans.push(Span::styled(format!(" {:?}", damage_type), Style::default().fg(Color::Yellow)));
+        }
+        if let Some(status) = ammo.status {
+            spans.push(Span::styled(" | Inflicts: ", Style::default()));
+            spans.push(Span::styled(
+                format!("{:?} ({} turns)", status, ammo.status_duration),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        spans.push(Span::styled(" | Recovery: ", Style::default()));
+        spans.push(Span::styled(format!("{}%", ammo.recovery_chance), Style::default().fg(Color::Yellow)));
+        lines.push(Line::from(spans));
+        lines.push("".into());
+    }
+
+    if let Some(magic) = storages.magic_weapons.get(item_entity) {
         lines.push(Line::from(vec![
             Span::styled(" [magic] Type: Magic | Range: ", Style::default()),
             Span::styled(format!("{}", magic.range), Style::default().fg(Color::Blue)),
@@ -288,7 +512,7 @@ pub fn format_inventory_item<'a>(
         lines.push("".into());
     }
 
-    if let Some(armor) = armors.get(item_entity) {
+    if let Some(armor) = storages.armors.get(item_entity) {
         lines.push(Line::from(vec![
             Span::styled(format!(" [armor] Type: {:?} | Defense: ", slot.unwrap_or(EquipmentSlot::Head)), Style::default()),
             Span::styled(format!("{}", armor.defense), Style::default().fg(Color::Yellow)),
@@ -296,11 +520,169 @@ pub fn format_inventory_item<'a>(
         lines.push("".into());
     }
 
+    if let Some(durability) = storages.durabilities.get(item_entity) {
+        lines.push(Line::from(vec![
+            Span::styled(" [durability] ", Style::default()),
+            if durability.is_broken() {
+                Span::styled("Broken, a merchant can repair it", Style::default().fg(Color::Red))
+            } else {
+                Span::styled(format!("{} / {}", durability.current, durability.max), Style::default().fg(Color::Yellow))
+            },
+        ]));
+        lines.push("".into());
+    }
+
+    if let Some(item_modifiers) = storages.modifiers.get(item_entity).filter(|_| identified) {
+        lines.push(Line::from(vec![
+            Span::styled(" [modifiers] ", Style::default()),
+            Span::styled(format_modifiers(item_modifiers), Style::default().fg(Color::LightGreen)),
+        ]));
+        lines.push("".into());
+    }
+
     let text = Text::from(lines);
     // TODO: This doesn't wrap descriptions or anything else :(
     return ListItem::new(text);
 }
 
+/// Render a single feat, along with its prerequisites when they haven't been met.
+fn format_feat<'a>(feat: &FeatConfig, all_feats: &[FeatConfig], available: bool, known: bool) -> ListItem<'a> {
+    let prerequisites = &feat.prerequisites;
+    let mut requirements: Vec<String> = Vec::new();
+    if prerequisites.level > 0 {
+        requirements.push(format!("Level {}", prerequisites.level));
+    }
+    let attributes = [
+        ("Str", prerequisites.attributes.strength),
+        ("Dex", prerequisites.attributes.dexterity),
+        ("Con", prerequisites.attributes.constitution),
+        ("Int", prerequisites.attributes.intelligence),
+        ("Wis", prerequisites.attributes.wisdom),
+        ("Cha", prerequisites.attributes.charisma),
+    ];
+    for (name, value) in attributes.iter() {
+        if *value > 0 {
+            requirements.push(format!("{} {}", name, value));
+        }
+    }
+    for required in prerequisites.feats.iter() {
+        requirements.push(feat_name(all_feats, *required));
+    }
+
+    let top_line = Line::from(vec![
+        Span::styled(if known { " [known] " } else { " " }, Style::new().fg(Color::Green)),
+        Span::styled(
+            feat.name.clone(),
+            Style::default()
+                .fg(if available { Color::Cyan } else { Color::DarkGray })
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            if requirements.is_empty() { "".to_string() } else { format!(" (requires {})", requirements.join(", ")) },
+            Style::default().fg(if available { Color::Gray } else { Color::Red }),
+        ),
+    ]);
+    let desc_line = Line::from(Span::styled(format!(" {}", feat.description.trim()), Style::default().italic()));
+    return ListItem::new(Text::from(vec![top_line, desc_line, "".into()]));
+}
+
+/// Looks up the display name of a feat from config, falling back to its type.
+fn feat_name(all_feats: &[FeatConfig], feat: FeatType) -> String {
+    all_feats
+        .iter()
+        .find(|config| config.feat == feat)
+        .map(|config| config.name.clone())
+        .unwrap_or(format!("{:?}", feat))
+}
+
+/// Returns the derived stats the player would have after toggling the given item,
+/// i.e. equipping it in place of whatever is in its slot, or removing it.
+pub fn preview_equip(ecs: &World, item: Entity) -> Option<DerivedStats> {
+    let entities = ecs.entities();
+    let player_entity = *ecs.fetch::<Entity>();
+    let equipment = ecs.read_storage::<Equipped>();
+    let equippables = ecs.read_storage::<Equippable>();
+    let armors = ecs.read_storage::<Armor>();
+    let modifiers = ecs.read_storage::<Modifiers>();
+    let durabilities = ecs.read_storage::<Durability>();
+    let stats = ecs.read_storage::<Stats>();
+    let derived_stats = ecs.read_storage::<DerivedStats>();
+    let statuses = ecs.read_storage::<Statuses>();
+    let feats = ecs.read_storage::<Feats>();
+    let viewsheds = ecs.read_storage::<Viewshed>();
+
+    let stat = stats.get(player_entity)?;
+    let derived = derived_stats.get(player_entity).cloned().unwrap_or_default();
+    let slot = equippables.get(item)?.slot;
+    let removing = equipment.get(item).is_some_and(|equipped| equipped.owner == player_entity);
+    let mut items: Vec<Entity> = (&entities, &equipment)
+        .join()
+        .filter(|(_, equipped)| equipped.owner == player_entity)
+        .filter(|(equipped_item, equipped)| if removing { *equipped_item != item } else { equipped.slot != slot })
+        .map(|(equipped_item, _)| equipped_item)
+        .collect();
+    if !removing {
+        items.push(item);
+    }
+
+    let (armor_defense, totals) = equipment_totals(&items, &armors, &modifiers, &durabilities);
+    let sight = viewsheds.get(player_entity).map(|viewshed| viewshed.range).unwrap_or(0);
+    return Some(derive_stats(
+        stat, sight, armor_defense, totals, statuses.get(player_entity), feats.get(player_entity), derived.load,
+    ));
+}
+
+/// Shows the weight carried against carry capacity, e.g. "Load: 42.5 / 150 (Burdened)".
+pub fn format_load(derived: &DerivedStats) -> String {
+    return match derived.encumbrance {
+        Encumbrance::Unburdened => format!("Load: {:.1} / {}", derived.load, derived.carry_capacity),
+        encumbrance => format!("Load: {:.1} / {} ({:?})", derived.load, derived.carry_capacity, encumbrance),
+    };
+}
+
+/// Shows a value, or `before -> after` in green or red if it would change.
+pub fn compare_span<'a>(before: i32, after: i32, signed: bool) -> Span<'a> {
+    let format = |value: i32| if signed { format!("{:+}", value) } else { format!("{}", value) };
+    if before == after {
+        Span::raw(format(before))
+    } else {
+        Span::styled(
+            format!("{} -> {}", format(before), format(after)),
+            Style::new().fg(if after > before { Color::Green } else { Color::Red }),
+        )
+    }
+}
+
+/// Summarizes the non-zero bonuses of a set of modifiers, e.g. "+2 STR, +10 max HP".
+pub fn format_modifiers(modifiers: &Modifiers) -> String {
+    let mut parts: Vec<String> = [
+        (modifiers.strength, "STR"),
+        (modifiers.dexterity, "DEX"),
+        (modifiers.constitution, "CON"),
+        (modifiers.intelligence, "INT"),
+        (modifiers.wisdom, "WIS"),
+        (modifiers.charisma, "CHA"),
+        (modifiers.max_hp, "max HP"),
+        (modifiers.viewshed_range, "sight"),
+        (modifiers.mp_regeneration, "mana regen"),
+        (modifiers.speed, "speed"),
+        (modifiers.to_hit, "to hit"),
+    ]
+    .iter()
+    .filter(|(value, _)| *value != 0)
+    .map(|(value, label)| format!("{:+} {}", value, label))
+    .collect();
+    parts.extend(modifiers.resistances.iter().map(|r| format!("resist {:?}", r).to_lowercase()));
+    return parts.join(", ");
+}
+
+fn format_resistances(modifiers: &Modifiers) -> String {
+    if modifiers.resistances.is_empty() {
+        return "__".to_string();
+    }
+    return modifiers.resistances.iter().map(|r| format!("{:?}", r)).collect::<Vec<String>>().join(", ");
+}
+
 pub struct FormattedStats {
     strength: (String, Style),
     dexterity: (String, Style),
@@ -310,14 +692,14 @@ pub struct FormattedStats {
     charisma: (String, Style),
 }
 
-fn format_stats(stat: &Stats, runstate: RunState) -> FormattedStats {
+fn format_stats(stat: &DerivedStats, preview: &DerivedStats, runstate: RunState) -> FormattedStats {
     let mut formatted = FormattedStats {
-        strength: (format!("Strength: {}", stat.strength), Style::default()),
-        dexterity: (format!("Dexterity: {}", stat.dexterity), Style::default()),
-        constitution: (format!("Constitution: {}", stat.constitution), Style::default()),
-        intelligence: (format!("Intelligence: {}", stat.intelligence), Style::default()),
-        wisdom: (format!("Wisdom: {}", stat.wisdom), Style::default()),
-        charisma: (format!("Charisma: {}", stat.charisma), Style::default()),
+        strength: format_attribute("Strength", stat.strength, preview.strength),
+        dexterity: format_attribute("Dexterity", stat.dexterity, preview.dexterity),
+        constitution: format_attribute("Constitution", stat.constitution, preview.constitution),
+        intelligence: format_attribute("Intelligence", stat.intelligence, preview.intelligence),
+        wisdom: format_attribute("Wisdom", stat.wisdom, preview.wisdom),
+        charisma: format_attribute("Charisma", stat.charisma, preview.charisma),
     };
 
     if let RunState::LevelUp { index } = runstate {
@@ -351,3 +733,15 @@ fn format_stats(stat: &Stats, runstate: RunState) -> FormattedStats {
     }
     return formatted;
 }
+
+/// Formats an attribute, along with what it would become if it changes (e.g. equipping a ring).
+fn format_attribute(label: &str, before: i32, after: i32) -> (String, Style) {
+    if before == after {
+        (format!("{}: {}", label, before), Style::default())
+    } else {
+        (
+            format!("{}: {} -> {}", label, before, after),
+            Style::new().fg(if after > before { Color::Green } else { Color::Red }),
+        )
+    }
+}
@@ -1,8 +1,13 @@
 pub mod base;
+pub mod character_creation;
+pub mod compare;
+pub mod equipment;
 pub mod game;
 pub mod game_over;
 pub mod inventory;
 pub mod log;
 pub mod menu;
+pub mod pickup;
 pub mod quit;
+pub mod spellbook;
 pub mod trading;
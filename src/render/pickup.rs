@@ -0,0 +1,91 @@
+use std::collections::HashSet;
+
+use ratatui::{
+    Frame,
+    layout::Alignment,
+    style::{Color, Modifier, Style, palette::tailwind::SLATE},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Padding},
+};
+use rltk::Point;
+use specs::prelude::*;
+
+use crate::{
+    component::ItemSignature,
+    generate::identification::IdentificationTable,
+    render::base::centered_rect,
+    system::inventory_system::get_tile_items,
+};
+
+/**
+ * This render function fires on top of the explore view when the player
+ * looks through a tile holding one or more items.
+ *
+ * Lists every item on the tile, grouped the way the inventory stacks them,
+ * marking the entries the player has selected for pick up.
+ */
+pub fn render_pickup(ecs: &mut World, frame: &mut Frame, x: i32, y: i32, index: usize, selected: &HashSet<ItemSignature>) {
+    let player_pos = *ecs.fetch::<Point>();
+    let in_reach = player_pos.x == x && player_pos.y == y;
+    let tile_items = get_tile_items(ecs, x, y);
+    let identification = ecs.fetch::<IdentificationTable>();
+
+    let mut item_list: Vec<ListItem> = tile_items
+        .iter()
+        .map(|(signature, stack)| {
+            let display_name = identification.display_name(&signature.name);
+            let name_color = signature.rarity.map(|tier| tier.color()).unwrap_or(Color::LightBlue);
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    if selected.contains(signature) { " [*] " } else { " [ ] " },
+                    Style::new().fg(Color::Yellow),
+                ),
+                Span::styled(
+                    if signature.known_curse { "[cursed] " } else { "" },
+                    Style::new().fg(Color::Red),
+                ),
+                Span::styled(
+                    if stack.len() > 1 {
+                        format!("{} x {}", display_name, stack.len())
+                    } else {
+                        display_name
+                    },
+                    Style::new().fg(name_color).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    match signature.durability {
+                        Some(durability) => format!(" ({} durability)", durability),
+                        None => String::new(),
+                    },
+                    Style::new().fg(Color::Gray),
+                ),
+            ]))
+        })
+        .collect();
+
+    let mut state = ListState::default();
+    if item_list.is_empty() {
+        item_list.push(ListItem::from("There is nothing left here.".to_string()));
+    } else {
+        state.select(Some(index));
+    }
+
+    let modal_area = centered_rect(50, 50, frame.area());
+    frame.render_widget(Clear, modal_area);
+    frame.render_stateful_widget(
+        List::new(item_list)
+            .style(Style::default().fg(Color::White).bg(Color::Black))
+            .block(
+                Block::new()
+                    .title(if in_reach { "Pick up" } else { "Items (out of reach)" })
+                    .title_bottom("[enter] Take  [v] Mark  [a] Take all  [g] Close")
+                    .borders(Borders::ALL)
+                    .title_alignment(Alignment::Center)
+                    .padding(Padding::uniform(1)),
+            )
+            .highlight_style(Style::new().bg(SLATE.c800).add_modifier(Modifier::BOLD))
+            .highlight_spacing(ratatui::widgets::HighlightSpacing::Never),
+        modal_area,
+        &mut state,
+    );
+}
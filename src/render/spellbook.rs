@@ -0,0 +1,133 @@
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style, Stylize, palette::tailwind::SLATE},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, List, ListItem, ListState, Padding, Paragraph},
+};
+use specs::prelude::*;
+
+use crate::component::{AreaShape, Spell, SpellKnowledge, Stats};
+
+/**
+ * This render function fires when the player is ingame and viewing their spellbook.
+ *
+ * Lists every spell in the player's `SpellKnowledge`, marking the readied spell
+ * and any bound hotkeys, alongside the details of the currently selected spell.
+ */
+pub fn render_spellbook(ecs: &mut World, index: usize, frame: &mut Frame) {
+    let player_entity = ecs.fetch::<Entity>();
+    let spell_knowledge = ecs.read_storage::<SpellKnowledge>();
+    let stats = ecs.read_storage::<Stats>();
+
+    let known = spell_knowledge
+        .get(*player_entity)
+        .expect("Unable to retrieve the player's spell knowledge!");
+
+    let stat = stats
+        .get(*player_entity)
+        .expect("Unable to retrieve the player's stats!");
+
+    let mut spell_list: Vec<ListItem> = known
+        .spells
+        .iter()
+        .enumerate()
+        .map(|(spell_index, spell)| {
+            format_spell(spell, spell_index == known.active, known.hotkey_for(spell_index), stat.mp.current)
+        })
+        .collect();
+
+    let mut state = ListState::default();
+    if spell_list.is_empty() {
+        spell_list.push(ListItem::from("You don't know any spells yet!".to_string()));
+    } else {
+        state.select(Some(index));
+    }
+
+    let root_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(frame.area());
+
+    frame.render_stateful_widget(
+        List::new(spell_list)
+            .block(
+                Block::new()
+                    .title("Spellbook")
+                    .title_bottom("[enter] Ready  [3-9] Bind hotkey  [m] Close")
+                    .borders(Borders::ALL)
+                    .title_alignment(Alignment::Center)
+                    .padding(Padding::uniform(1)),
+            )
+            .highlight_style(Style::new().bg(SLATE.c800).add_modifier(Modifier::BOLD))
+            .highlight_spacing(ratatui::widgets::HighlightSpacing::Never),
+        root_layout[0],
+        &mut state,
+    );
+
+    let details = match known.spells.get(index) {
+        Some(spell) => Text::from(vec![
+            Line::from(Span::styled(
+                spell.name.clone(),
+                Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                format!("Mana cost: {} (you have {})", spell.mp_cost, stat.mp.current),
+                Style::new().fg(if stat.mp.current >= spell.mp_cost { Color::Blue } else { Color::Red }),
+            )),
+            Line::from(format!("Damage: {} {:?}", spell.damage.to_expression(), spell.damage_type)),
+            Line::from(format!(
+                "Area: {}",
+                spell
+                    .area
+                    .map(|area| match area.shape {
+                        AreaShape::Ball => format!("ball, radius {}", area.size),
+                        AreaShape::Line => format!("line, length {}", area.size),
+                        AreaShape::Cone => format!("cone, length {}", area.size),
+                    })
+                    .unwrap_or("single target".to_string())
+            )),
+            Line::from(format!(
+                "Hotkey: {}",
+                known.hotkey_for(index).map(|key| key.to_string()).unwrap_or("__".to_string())
+            )),
+        ]),
+        None => Text::from(""),
+    };
+
+    frame.render_widget(
+        Paragraph::new(details).block(
+            Block::new()
+                .title("Spell")
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center)
+                .padding(Padding::uniform(1)),
+        ),
+        root_layout[1],
+    );
+}
+
+/// Render a single spell entry, greying out spells the player can't currently afford.
+fn format_spell<'a>(spell: &Spell, is_active: bool, hotkey: Option<char>, mana: i32) -> ListItem<'a> {
+    let affordable = mana >= spell.mp_cost;
+    ListItem::new(Line::from(vec![
+        Span::styled(
+            if is_active { " [ready] " } else { " " },
+            Style::new().fg(Color::Green),
+        ),
+        Span::styled(
+            hotkey.map(|key| format!("[{}] ", key)).unwrap_or_default(),
+            Style::new().fg(Color::Yellow),
+        ),
+        Span::styled(
+            spell.name.clone(),
+            Style::new().fg(if affordable { Color::Cyan } else { Color::DarkGray }).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(format!(" | {} mp", spell.mp_cost), Style::new().fg(Color::Blue)),
+        Span::styled(
+            format!(" | {} {:?}", spell.damage.to_expression(), spell.damage_type),
+            Style::default().italic(),
+        ),
+    ]))
+}
@@ -8,10 +8,19 @@ use specs::prelude::*;
 
 use crate::{
     component::{
-        Armor, Equippable, Equipped, Inventory, Item, MagicWeapon, MeleeWeapon, Name, RangedWeapon, Vendor
+        DerivedStats, Equipped, Inventory, Item, Name, Vendor,
+    },
+    generate::identification::IdentificationTable,
+    input::main_trading::{
+        IDENTIFY_FEE, REPAIR_FEE_PER_POINT, UNCURSE_FEE, buy_percent, buy_price, sell_percent,
+        sell_price,
     },
     logbook::logbook::format_latest_text,
-    render::inventory::format_inventory_item,
+    render::{
+        compare::{format_comparison, render_comparison},
+        inventory::{ItemStorages, format_inventory_item},
+    },
+    system::inventory_system::pick_from_stack,
 };
 
 pub fn render_trading(
@@ -21,18 +30,17 @@ pub fn render_trading(
     vendor_index: usize,
     player_index: usize,
     is_buying: bool,
+    is_buyback: bool,
 ) {
     let player_entity = ecs.fetch::<Entity>();
     let names = ecs.read_storage::<Name>();
     let items = ecs.read_storage::<Item>();
     let equipment = ecs.read_storage::<Equipped>();
     let inventories = ecs.read_storage::<Inventory>();
-    let melee_weapons = ecs.read_storage::<MeleeWeapon>();
-    let ranged_weapons = ecs.read_storage::<RangedWeapon>();
-    let magic_weapons = ecs.read_storage::<MagicWeapon>();
-    let armors = ecs.read_storage::<Armor>();
-    let equippables = ecs.read_storage::<Equippable>();
     let vendors = ecs.read_storage::<Vendor>();
+    let identification = ecs.fetch::<IdentificationTable>();
+    let derived_stats = ecs.read_storage::<DerivedStats>();
+    let item_storages = ItemStorages::fetch(ecs);
 
     let inventory = inventories
         .get(*player_entity)
@@ -42,25 +50,31 @@ pub fn render_trading(
         .get(vendor_entity)
         .expect("Unable to access given vendor component");
 
-    let vendor_inventory_list: Vec<ListItem> = vendor
-        .items
+    /*
+     * The buyback list is priced at what the vendor paid for each item.
+     */
+    let vendor_items = if is_buyback { &vendor.buyback } else { &vendor.items };
+    let vendor_gold = inventories.get(vendor_entity).map(|inventory| inventory.gold).unwrap_or(0);
+    let vendor_price = |base_value: i32| match is_buyback {
+        true => sell_price(base_value, derived_stats.get(*player_entity)),
+        false => buy_price(base_value, derived_stats.get(*player_entity)),
+    };
+
+    let vendor_inventory_list: Vec<ListItem> = vendor_items
         .iter()
         .map(|item_entity| {
             let name = names
                 .get(*item_entity)
-                .map(|name| name.name.clone())
+                .map(|name| identification.display_name(&name.name))
                 .unwrap_or("???".to_string());
             format_inventory_item(
                 name,
                 *item_entity,
                 1,
-                &items,
-                &equipment,
-                &melee_weapons,
-                &ranged_weapons,
-                &magic_weapons,
-                &armors,
-                &equippables,
+                identification.is_item_identified(*item_entity, &names),
+                false,
+                items.get(*item_entity).map(|item| vendor_price(item.base_value)),
+                &item_storages,
             )
         })
         .collect();
@@ -68,23 +82,18 @@ pub fn render_trading(
     let player_inventory_list: Vec<ListItem> = inventory
         .items
         .iter()
-        .enumerate()
-        .map(|item| {
+        .map(|(signature, stack)| {
+            /* Show the item that would actually be sold */
+            let item_entity = pick_from_stack(stack, &equipment, false)
+                .expect("Unable to retrieve inventory item entity (top of stack)");
             format_inventory_item(
-                item.1.0.clone(),
-                item.1
-                    .1
-                    .first()
-                    .expect("Unable to retrieve inventory item entity (top of stack)")
-                    .clone(),
-                item.1.1.len(),
-                &items,
-                &equipment,
-                &melee_weapons,
-                &ranged_weapons,
-                &magic_weapons,
-                &armors,
-                &equippables,
+                identification.display_name(&signature.name),
+                item_entity,
+                stack.len(),
+                identification.is_identified(&signature.name),
+                false,
+                items.get(item_entity).map(|item| sell_price(item.base_value, derived_stats.get(*player_entity))),
+                &item_storages,
             )
         })
         .collect();
@@ -95,17 +104,43 @@ pub fn render_trading(
     )
     .areas(frame.area());
 
+    /*
+     * Equippable items on either side get a comparison against the player's gear.
+     */
+    let highlighted = match is_buying {
+        true => vendor_items.get(vendor_index).copied(),
+        false => inventory
+            .items
+            .get_index(player_index)
+            .and_then(|(_, stack)| pick_from_stack(stack, &equipment, false)),
+    };
+    let comparison = highlighted.and_then(|item| format_comparison(ecs, item));
+    let [lists_area, comparison_area] = Layout::new(
+        Direction::Vertical,
+        vec![
+            Constraint::Fill(1),
+            Constraint::Length(comparison.as_ref().map(|lines| lines.len() as u16 + 2).unwrap_or(0)),
+        ],
+    )
+    .areas(trading_area);
+
     let [vendor_inventory_area, player_inventory_area] = Layout::new(
         Direction::Horizontal,
         vec![Constraint::Percentage(50), Constraint::Percentage(50)],
     )
-    .areas(trading_area);
+    .areas(lists_area);
 
     frame.render_stateful_widget(
         List::new(vendor_inventory_list)
             .block(
                 Block::new()
-                    .title("For sale")
+                    .title(format!("{} ({} gold)", if is_buyback { "Buyback" } else { "For sale" }, vendor_gold))
+                    .title_bottom(format!(
+                        "[b] {}  You buy at {}% and sell at {}% of value",
+                        if is_buyback { "For sale" } else { "Buyback" },
+                        buy_percent(derived_stats.get(*player_entity)),
+                        sell_percent(derived_stats.get(*player_entity)),
+                    ))
                     .borders(Borders::ALL)
                     .title_alignment(Alignment::Center)
                     .padding(Padding::uniform(1)),
@@ -125,6 +160,10 @@ pub fn render_trading(
             .block(
                 Block::new()
                     .title(format!("My inventory ({} gold)", inventory.gold))
+                    .title_bottom(format!(
+                        "[i] Identify ({} gold)  [u] Remove curse ({} gold)  [r] Repair ({} gold/pt)",
+                        IDENTIFY_FEE, UNCURSE_FEE, REPAIR_FEE_PER_POINT
+                    ))
                     .borders(Borders::ALL)
                     .title_alignment(Alignment::Center)
                     .padding(Padding::uniform(1)),
@@ -139,5 +178,8 @@ pub fn render_trading(
         &mut ListState::default().with_selected(Some(player_index)),
     );
 
+    if let Some(lines) = comparison {
+        render_comparison(frame, comparison_area, lines);
+    }
     frame.render_widget(format_latest_text(log_area.height as usize), log_area);
 }
@@ -0,0 +1,136 @@
+use ratatui::style::Color;
+use rltk::{Point, RandomNumberGenerator};
+use specs::prelude::*;
+
+use crate::{
+    RunState,
+    component::{AlertState, Alertness, DerivedStats, Name, Noise, Position, Viewshed},
+    logbook::logbook::Logger,
+    system::derived_stats_system::stat_to_modifier,
+};
+
+/// The perception roll a monster must meet to hear a noise at the edge of its range.
+pub const NOISE_PERCEPTION_DC: i32 = 15;
+
+/// Base volume of a single step, before any noisy armor is taken into account.
+pub const FOOTSTEP_NOISE: i32 = 2;
+pub const MELEE_NOISE: i32 = 8;
+pub const RANGED_NOISE: i32 = 4;
+pub const MAGIC_NOISE: i32 = 10;
+pub const TRAP_NOISE: i32 = 10;
+
+pub struct AlertnessSystem {}
+
+/**
+ * Drives the monster alertness state machine once per monster turn.
+ *
+ * Asleep -> Unaware: the monster hears a noise within earshot.
+ * Unaware -> Alert: the monster hears another noise, or spots the player
+ * and beats their stealth with a perception (wisdom) roll.
+ *
+ * Alert monsters are left alone here, the `MonsterSystem` handles them.
+ */
+impl<'a> System<'a> for AlertnessSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, RunState>,
+        ReadExpect<'a, Point>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, RandomNumberGenerator>,
+        ReadStorage<'a, Viewshed>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, DerivedStats>,
+        WriteStorage<'a, Alertness>,
+        WriteStorage<'a, Noise>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            runstate,
+            player_position,
+            player_entity,
+            mut rng,
+            viewsheds,
+            positions,
+            names,
+            derived_stats,
+            mut alertness,
+            mut noises,
+        ) = data;
+
+        match *runstate {
+            RunState::MonsterTurn => {}
+            _ => return,
+        }
+
+        let player_stealth = derived_stats
+            .get(*player_entity)
+            .map(|s| 10 + stat_to_modifier(s.dexterity))
+            .unwrap_or(10);
+
+        let player_visible_tiles = viewsheds
+            .get(*player_entity)
+            .map(|v| v.visible_tiles.clone())
+            .unwrap_or_default();
+
+        let mut heard_noises: Vec<(Point, i32)> = Vec::new();
+        for (noise, position) in (&noises, &positions).join() {
+            for volume in noise.volume.iter() {
+                heard_noises.push((Point::new(position.x, position.y), *volume));
+            }
+        }
+
+        for (entity, alert, derived, position, viewshed, name) in
+            (&entities, &mut alertness, &derived_stats, &positions, &viewsheds, &names).join()
+        {
+            if alert.state == AlertState::Alert {
+                continue;
+            }
+
+            let monster_position = Point::new(position.x, position.y);
+            let perception = stat_to_modifier(derived.wisdom);
+
+            let heard_noise = heard_noises.iter().any(|(source, volume)| {
+                let distance = rltk::DistanceAlg::Pythagoras.distance2d(monster_position, *source);
+                if distance > *volume as f32 {
+                    return false;
+                }
+                let proximity = *volume - distance as i32;
+                rng.roll_dice(1, 20) + perception + proximity >= NOISE_PERCEPTION_DC
+            });
+
+            let spotted_player = alert.state == AlertState::Unaware
+                && viewshed.visible_tiles.contains(&*player_position)
+                && rng.roll_dice(1, 20) + perception >= player_stealth;
+
+            let next_state = match (alert.state, heard_noise, spotted_player) {
+                (AlertState::Asleep, true, _) => AlertState::Unaware,
+                (AlertState::Unaware, true, _) | (AlertState::Unaware, _, true) => AlertState::Alert,
+                (state, _, _) => state,
+            };
+
+            if next_state == alert.state {
+                continue;
+            }
+            alert.state = next_state;
+
+            // Only tell the player about monsters they can actually see
+            if entity != *player_entity && player_visible_tiles.contains(&monster_position) {
+                match next_state {
+                    AlertState::Unaware => Logger::new()
+                        .append_with_color(Color::Red, format!("{} ", name.name))
+                        .append("stirs from its sleep.")
+                        .log(),
+                    AlertState::Alert => Logger::new()
+                        .append_with_color(Color::Red, format!("{} ", name.name))
+                        .append("notices you!")
+                        .log(),
+                    AlertState::Asleep => {}
+                }
+            }
+        }
+        noises.clear();
+    }
+}
@@ -0,0 +1,301 @@
+use ratatui::style::Color;
+use specs::prelude::*;
+
+use crate::{
+    component::{
+        Armor, DamageType, DerivedStats, Durability, Encumbrance, Equipped, FeatType, Feats,
+        InBackpack, Item, Modifiers, Stats, StatusType, Statuses, Viewshed,
+    },
+    logbook::logbook::Logger,
+};
+
+pub const BASE_ARMOR_CLASS: i32 = 10;
+pub const POWER_ATTACK_BONUS: i32 = 2;
+pub const TRAP_SENSE_BONUS: i32 = 5;
+pub const HAGGLER_BONUS: i32 = 20;
+pub const POISON_PENALTY: i32 = 2;
+
+/// Each point of charisma modifier shifts vendor prices by this percentage.
+pub const CHARISMA_PRICE_PERCENT: i32 = 5;
+
+/// Carrying capacity per point of strength.
+pub const CARRY_CAPACITY_PER_STRENGTH: i32 = 15;
+
+/// Carrying more than this percentage of capacity leaves an entity overloaded rather than burdened.
+pub const OVERLOADED_LOAD_PERCENT: i32 = 150;
+/// Penalties to hit, armor class and speed while burdened or overloaded.
+pub const BURDENED_PENALTY: i32 = 1;
+pub const BURDENED_SPEED_PENALTY: i32 = 25;
+pub const OVERLOADED_PENALTY: i32 = 3;
+pub const OVERLOADED_SPEED_PENALTY: i32 = 50;
+
+pub struct DerivedStatsSystem {}
+
+/**
+ * Recalculates the `DerivedStats` of every entity with `Stats`.
+ *
+ * This runs every tick ahead of anything that consumes derived stats, so that
+ * equipping gear or picking up a status is reflected immediately.
+ *
+ * Equipment modifiers are added on top of the base `Stats` here as well, without ever
+ * writing them back, so unequipping an item simply takes its bonuses away again.
+ * Current hp is the only thing touched, being clamped whenever max hp shrinks so
+ * that taking off an amulet of health can't leave the wearer overhealed.
+ *
+ * The weight of everything in an entity's backpack is totalled up as its load, and the
+ * player is told whenever their load crosses into (or out of) being encumbered.
+ */
+impl<'a> System<'a> for DerivedStatsSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Stats>,
+        ReadStorage<'a, Viewshed>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, Armor>,
+        ReadStorage<'a, Modifiers>,
+        ReadStorage<'a, Durability>,
+        ReadStorage<'a, Statuses>,
+        ReadStorage<'a, Feats>,
+        ReadStorage<'a, Item>,
+        ReadStorage<'a, InBackpack>,
+        ReadExpect<'a, Entity>,
+        WriteStorage<'a, DerivedStats>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut stats,
+            viewsheds,
+            equipment,
+            armor,
+            modifiers,
+            durabilities,
+            statuses,
+            feats,
+            items,
+            backpacks,
+            player_entity,
+            mut derived_stats,
+        ) = data;
+
+        for (entity, stat) in (&entities, &mut stats).join() {
+            let equipped_items: Vec<Entity> = (&entities, &equipment)
+                .join()
+                .filter(|(_, equipped)| equipped.owner == entity)
+                .map(|(item, _)| item)
+                .collect();
+            let (armor_defense, equipment_modifiers) = equipment_totals(&equipped_items, &armor, &modifiers, &durabilities);
+
+            let load: f32 = (&items, &backpacks)
+                .join()
+                .filter(|(_, backpack)| backpack.owner == entity)
+                .fold(0.0, |load, (item, _)| load + item.weight);
+
+            let derived = derive_stats(
+                stat,
+                viewsheds.get(entity).map(|viewshed| viewshed.range).unwrap_or(0),
+                armor_defense,
+                equipment_modifiers,
+                statuses.get(entity),
+                feats.get(entity),
+                load,
+            );
+            if derived_stats.get(entity).is_some_and(|previous| derived.max_hp < previous.max_hp) {
+                stat.hp.current = i32::min(stat.hp.current, derived.max_hp);
+            }
+            let previous = derived_stats.get(entity).map(|d| d.encumbrance).unwrap_or_default();
+            if entity == *player_entity && previous != derived.encumbrance {
+                log_encumbrance(previous, derived.encumbrance);
+            }
+            if derived_stats.get(entity) != Some(&derived) {
+                derived_stats
+                    .insert(entity, derived)
+                    .expect("Unable to insert derived stats");
+            }
+        }
+    }
+}
+
+/// Combines base attributes and sight with everything else that modifies them.
+///
+/// Exposed separately from the system so that screens can preview how
+/// a change (e.g. a new piece of armor) would affect the result.
+pub fn derive_stats(
+    stat: &Stats,
+    sight_range: i32,
+    armor_defense: i32,
+    equipment: Modifiers,
+    statuses: Option<&Statuses>,
+    feats: Option<&Feats>,
+    load: f32,
+) -> DerivedStats {
+    let has_feat = |feat: FeatType| feats.is_some_and(|f| f.has(feat));
+    let poisoned = statuses.is_some_and(|s| s.statuses.iter().any(|s| s.status_type == StatusType::Poisoned));
+    let strength = stat.strength + equipment.strength;
+    let dexterity = stat.dexterity + equipment.dexterity;
+    let constitution = stat.constitution + equipment.constitution;
+    let intelligence = stat.intelligence + equipment.intelligence;
+    let wisdom = stat.wisdom + equipment.wisdom;
+    let charisma = stat.charisma + equipment.charisma;
+    let carry_capacity = i32::max(0, strength * CARRY_CAPACITY_PER_STRENGTH);
+    let encumbrance = encumbrance(load, carry_capacity);
+    let (encumbrance_penalty, speed_penalty) = match encumbrance {
+        Encumbrance::Unburdened => (0, 0),
+        Encumbrance::Burdened => (BURDENED_PENALTY, BURDENED_SPEED_PENALTY),
+        Encumbrance::Overloaded => (OVERLOADED_PENALTY, OVERLOADED_SPEED_PENALTY),
+    };
+    let status_penalty = if poisoned { POISON_PENALTY } else { 0 };
+    let to_hit_penalty = status_penalty + encumbrance_penalty;
+    let proficiency = proficiency_bonus(stat.level);
+
+    DerivedStats {
+        strength,
+        dexterity,
+        constitution,
+        intelligence,
+        wisdom,
+        charisma,
+        max_hp: stat.hp.max + equipment.max_hp,
+        sight_range: sight_range + equipment.viewshed_range,
+        armor_class: BASE_ARMOR_CLASS + stat_to_modifier(dexterity) + armor_defense - encumbrance_penalty,
+        melee_to_hit: proficiency + stat_to_modifier(strength) + equipment.to_hit - to_hit_penalty,
+        ranged_to_hit: proficiency + stat_to_modifier(dexterity) + equipment.to_hit - to_hit_penalty,
+        spell_to_hit: proficiency + stat_to_modifier(intelligence) + equipment.to_hit - to_hit_penalty,
+        melee_damage: stat_to_modifier(strength)
+            + if has_feat(FeatType::PowerAttack) { POWER_ATTACK_BONUS } else { 0 },
+        ranged_damage: stat_to_modifier(dexterity),
+        spell_power: stat_to_modifier(intelligence),
+        perception: stat_to_modifier(wisdom)
+            + if has_feat(FeatType::TrapSense) { TRAP_SENSE_BONUS } else { 0 }
+            - status_penalty,
+        price_modifier: stat_to_modifier(charisma) * CHARISMA_PRICE_PERCENT
+            + if has_feat(FeatType::Haggler) { HAGGLER_BONUS } else { 0 },
+        carry_capacity,
+        load,
+        encumbrance,
+        speed: equipment.speed - speed_penalty,
+        equipment,
+    }
+}
+
+/// Anything over capacity is burdened, and well over capacity is overloaded.
+pub fn encumbrance(load: f32, carry_capacity: i32) -> Encumbrance {
+    if load > (carry_capacity * OVERLOADED_LOAD_PERCENT) as f32 / 100.0 {
+        return Encumbrance::Overloaded;
+    }
+    if load > carry_capacity as f32 {
+        return Encumbrance::Burdened;
+    }
+    return Encumbrance::Unburdened;
+}
+
+fn log_encumbrance(previous: Encumbrance, current: Encumbrance) {
+    let (message, color) = match current {
+        Encumbrance::Overloaded => ("You are overloaded and can barely move!", Color::Red),
+        Encumbrance::Burdened if previous == Encumbrance::Overloaded => ("You are no longer overloaded, but still burdened.", Color::Yellow),
+        Encumbrance::Burdened => ("You are burdened by your load.", Color::Yellow),
+        Encumbrance::Unburdened => ("You are no longer encumbered.", Color::Green),
+    };
+    Logger::new().append_with_color(color, message).log();
+}
+
+/// Totals up the armor defense and modifiers of the given (equipped) items.
+/// Broken items are skipped entirely until they are repaired.
+pub fn equipment_totals(
+    items: &[Entity],
+    armor: &ReadStorage<Armor>,
+    modifiers: &ReadStorage<Modifiers>,
+    durabilities: &ReadStorage<Durability>,
+) -> (i32, Modifiers) {
+    let mut armor_defense = 0;
+    let mut totals = Modifiers::default();
+    for item in items.iter() {
+        if durabilities.get(*item).is_some_and(|durability| durability.is_broken()) {
+            continue;
+        }
+        if let Some(armor) = armor.get(*item) {
+            armor_defense += armor.defense;
+        }
+        if let Some(item_modifiers) = modifiers.get(*item) {
+            totals.combine(item_modifiers);
+        }
+    }
+    return (armor_defense, totals);
+}
+
+/// The max hp of an entity including its equipment, falling back to
+/// its base max hp until its derived stats have been calculated.
+pub fn effective_max_hp(stat: &Stats, derived: Option<&DerivedStats>) -> i32 {
+    match derived {
+        Some(derived) => derived.max_hp,
+        None => stat.hp.max,
+    }
+}
+
+/// Halves damage of any type the entity's equipment resists.
+pub fn resist_damage(amount: i32, damage_type: DamageType, derived: Option<&DerivedStats>) -> i32 {
+    if derived.is_some_and(|d| d.equipment.resists(damage_type)) {
+        amount / 2
+    } else {
+        amount
+    }
+}
+
+/// The usual dnd proficiency bonus, +2 at level 1 and growing every four levels.
+pub fn proficiency_bonus(level: i32) -> i32 {
+    return 2 + (level - 1).max(0) / 4;
+}
+
+pub fn stat_to_modifier(stat: i32) -> i32 {
+    return (stat - 10).div_euclid(2);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::Pool;
+
+    #[test]
+    fn encumbrance_at_capacity_is_unburdened() {
+        assert_eq!(encumbrance(0.0, 60), Encumbrance::Unburdened);
+        assert_eq!(encumbrance(60.0, 60), Encumbrance::Unburdened);
+        assert_eq!(encumbrance(60.1, 60), Encumbrance::Burdened);
+    }
+
+    #[test]
+    fn encumbrance_at_overload_threshold_is_burdened() {
+        assert_eq!(encumbrance(90.0, 60), Encumbrance::Burdened);
+        assert_eq!(encumbrance(90.1, 60), Encumbrance::Overloaded);
+    }
+
+    #[test]
+    fn encumbrance_threshold_is_not_truncated_for_odd_capacities() {
+        assert_eq!(encumbrance(22.5, 15), Encumbrance::Burdened);
+        assert_eq!(encumbrance(22.6, 15), Encumbrance::Overloaded);
+    }
+
+    #[test]
+    fn equipment_is_added_on_top_of_base_stats() {
+        let pool = |value: i32| Pool { current: value, max: value };
+        let stat = Stats {
+            hp: pool(50),
+            mp: pool(10),
+            exp: Pool { current: 0, max: 1_000 },
+            level: 1,
+            strength: 12,
+            dexterity: 10,
+            constitution: 10,
+            intelligence: 10,
+            wisdom: 10,
+            charisma: 10,
+        };
+        let equipment = Modifiers { strength: 2, max_hp: 10, viewshed_range: 3, ..Default::default() };
+        let derived = derive_stats(&stat, 8, 0, equipment, None, None, 0.0);
+        assert_eq!(stat.strength, 12);
+        assert_eq!(derived.strength, 14);
+        assert_eq!(derived.melee_damage, 2);
+        assert_eq!(derived.max_hp, 60);
+        assert_eq!(derived.sight_range, 11);
+    }
+}
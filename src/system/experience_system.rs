@@ -2,7 +2,7 @@ use ratatui::style::Color;
 use specs::prelude::*;
 
 use crate::{
-    component::{Experience, Name, Pool, Stats}, effect::effect::{Effect, EffectType, create_effect}, logbook::logbook::Logger
+    component::{DerivedStats, Experience, Name, Pool, Stats}, effect::effect::{Effect, EffectType, create_effect}, logbook::logbook::Logger
 };
 
 pub struct ExperienceSystem {}
@@ -12,11 +12,12 @@ impl<'a> System<'a> for ExperienceSystem {
         Entities<'a>,
         ReadStorage<'a, Name>,
         WriteStorage<'a, Stats>,
+        ReadStorage<'a, DerivedStats>,
         WriteStorage<'a, Experience>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, names, mut stats, mut experience) = data;
+        let (entities, names, mut stats, derived_stats, mut experience) = data;
 
         for (entity, name, stat, experience) in (&entities, &names, &mut stats, &experience).join()
         {
@@ -32,10 +33,11 @@ impl<'a> System<'a> for ExperienceSystem {
                     effect_type: EffectType::LevelUp { _level: stat.level },
                 });
 
-                // Upgrade max hp and mp based on attributes
+                // Upgrade max hp and mp based on base attributes, then heal up to max including equipment
                 let hp_multiplier = std::cmp::max(1, (stat.constitution - 10) / 2);
                 stat.hp.max = stat.hp.max + (2 * hp_multiplier);
-                stat.hp.current = stat.hp.max;
+                let equipment_hp = derived_stats.get(entity).map(|d| d.equipment.max_hp).unwrap_or(0);
+                stat.hp.current = stat.hp.max + equipment_hp;
                 let mp_multiplier = std::cmp::max(1, (stat.intelligence - 10) / 2);
                 stat.mp.max = stat.mp.max + (2 * mp_multiplier);
                 stat.mp.current = stat.mp.max;
@@ -1,11 +1,22 @@
+use std::{cmp::Ordering, ops::Deref};
+
+use indexmap::IndexMap;
 use ratatui::style::Color;
-use specs::{BitSet, Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
+use rltk::RandomNumberGenerator;
+use specs::{
+    BitSet, Entities, Entity, Join, ReadExpect, ReadStorage, Storage, System, World, WorldExt,
+    WriteExpect, WriteStorage, storage::MaskedStorage,
+};
 
 use crate::{
     component::{
-        AttackType, EquipmentSlot, Equippable, Equipped, InBackpack, Inventory, MagicMapper, MagicWeapon, MeleeWeapon, Name, Position, Potion, PotionType, RangedWeapon, Spell, SpellKnowledge, Stats, WantsToConsumeItem, WantsToPickupItem
+        AmmoType, Ammunition, AttackType, Consumable, Cursed, Durability, EquipmentSlot, Equippable, Equipped,
+        FeatType, Feats, Gold, Hidden, InBackpack, Inventory, InventorySort, InventoryTab, Item, ItemEffect,
+        ItemSignature, MagicWeapon, MeleeWeapon, Name, Position, RangedWeapon, Rarity,
+        WantsToConsumeItem, WantsToDestroyItem, WantsToDropItem, WantsToPickupItem,
     },
-    generate::map::Map,
+    effect::effect::{Effect, EffectType, create_effect},
+    generate::identification::IdentificationTable,
     logbook::logbook::Logger,
 };
 
@@ -19,15 +30,20 @@ impl<'a> System<'a> for InventorySystem {
         WriteStorage<'a, WantsToConsumeItem>,
         WriteStorage<'a, Position>,
         ReadStorage<'a, Name>,
-        WriteStorage<'a, Stats>,
         WriteStorage<'a, InBackpack>,
         WriteStorage<'a, Inventory>,
-        WriteExpect<'a, Map>,
-        ReadStorage<'a, Potion>,
-        WriteStorage<'a, SpellKnowledge>,
-        ReadStorage<'a, MagicMapper>,
+        ReadStorage<'a, Consumable>,
         ReadStorage<'a, Equippable>,
         WriteStorage<'a, Equipped>,
+        ReadExpect<'a, IdentificationTable>,
+        WriteStorage<'a, Cursed>,
+        WriteStorage<'a, WantsToDropItem>,
+        WriteStorage<'a, WantsToDestroyItem>,
+        ReadStorage<'a, Gold>,
+        WriteExpect<'a, RandomNumberGenerator>,
+        ReadStorage<'a, Rarity>,
+        ReadStorage<'a, Durability>,
+        ReadStorage<'a, Feats>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -38,23 +54,47 @@ impl<'a> System<'a> for InventorySystem {
             mut wants_consume,
             mut positions,
             names,
-            mut stats,
             mut backpack,
             mut inventories,
-            mut map,
-            potions,
-            mut spell_knowledge,
-            magic_mappers,
+            consumables,
             equippables,
             mut equipment,
+            identification,
+            mut curses,
+            mut wants_drop,
+            mut wants_destroy,
+            golds,
+            mut rng,
+            rarities,
+            durabilities,
+            feats,
         ) = data;
 
         /*
          * Item collection subsystem
+         *
+         * Coins are emptied straight into the collector's purse,
+         * everything else is stacked in their backpack.
          */
+        let mut collected_counts: IndexMap<(String, bool), i32> = IndexMap::new();
+        let mut collected_gold: IndexMap<bool, i32> = IndexMap::new();
         for (pickup, _name) in (&wants_pickup, &names).join() {
             for item in &pickup.items {
                 positions.remove(*item);
+                if let Some(gold) = golds.get(*item) {
+                    let amount = i32::max(
+                        1,
+                        rng.roll_dice(gold.amount.dice_count, gold.amount.dice_sides) + gold.amount.modifier,
+                    );
+                    if let Some(inventory) = inventories.get_mut(pickup.collected_by) {
+                        inventory.gold += amount;
+                    }
+                    if pickup.collected_by == *player_entity {
+                        *collected_gold.entry(pickup.auto).or_insert(0) += amount;
+                    }
+                    entities.delete(*item).expect("Unable to delete collected gold");
+                    continue;
+                }
                 backpack
                     .insert(
                         *item,
@@ -67,24 +107,32 @@ impl<'a> System<'a> for InventorySystem {
                 let item_name = names
                     .get(*item)
                     .expect("Unable to access name for picked up item");
+                let display_name = identification.display_name(&item_name.name);
 
                 if let Some(inventory) = inventories.get_mut(pickup.collected_by) {
-                    let item_stack = inventory
-                        .items
-                        .entry(item_name.name.clone())
-                        .or_insert(vec![]);
-                    item_stack.push(*item);
+                    inventory.add_item(*item, item_signature(*item, &names, &rarities, &curses, &durabilities));
                 }
 
                 if pickup.collected_by == *player_entity {
-                    Logger::new()
-                        .append("You pick up the ")
-                        .with_color(Color::LightBlue)
-                        .append(format!("{}.", item_name.name))
-                        .log();
+                    *collected_counts.entry((display_name, pickup.auto)).or_insert(0) += 1;
                 }
             }
         }
+        for (auto, amount) in collected_gold.into_iter() {
+            Logger::new()
+                .append(if auto { "You automatically pick up " } else { "You pick up " })
+                .append_with_color(Color::Yellow, format!("{} gold.", amount))
+                .log();
+        }
+        for ((display_name, auto), count) in collected_counts.into_iter() {
+            Logger::new()
+                .append(if auto { "You automatically pick up " } else { "You pick up " })
+                .append_with_color(
+                    Color::LightBlue,
+                    if count > 1 { format!("{} x {}.", display_name, count) } else { format!("the {}.", display_name) },
+                )
+                .log();
+        }
         wants_pickup.clear();
 
         /*
@@ -93,110 +141,152 @@ impl<'a> System<'a> for InventorySystem {
          * Iterates over the list of consumable components and then clears them.
          * Each consumable entity may or may not have an effect, if so it should
          * be explicitly mentioned and handled here, e.g. potion drinking.
+         *
+         * Consumable effects are queued rather than applied here, see `process_effects`.
+         * Potions and scrolls used by the player are identified once those effects resolve.
          */
-        for (entity, consume, stat) in (&entities, &wants_consume, &mut stats).join() {
+        for (entity, consume) in (&entities, &wants_consume).join() {
             let item_name = names
                 .get(consume.item)
                 .expect("Unable to access name for consumed item");
+            let display_name = identification.display_name(&item_name.name);
             let mut has_effect = false;
             let mut should_consume = false;
 
-            // Someone wants to drink a potion...
-            if let Some(potion) = potions.get(consume.item) {
+            // Someone wants to use a consumable, e.g. drink a potion or read a scroll...
+            if let Some(consumable) = consumables.get(consume.item) {
                 has_effect = true;
                 should_consume = true;
-                let fstat: String;
-                match potion.potion_type {
-                    PotionType::Health => {
-                        stat.hp.current =
-                            i32::min(stat.hp.max, stat.hp.current + potion.restore_amount);
-                        fstat = "hp".to_string();
-                    }
-                    PotionType::Mana => {
-                        stat.mp.current =
-                            i32::min(stat.mp.max, stat.mp.current + potion.restore_amount);
-                        fstat = "mp".to_string();
-                    }
-                }
                 if entity == *player_entity {
                     Logger::new()
                         .append("You consume the ")
-                        .append_with_color(Color::Blue, format!("{}", item_name.name))
-                        .append(", restoring ")
-                        .append_with_color(
-                            Color::Green,
-                            format!("{} {}.", potion.restore_amount, fstat),
-                        )
+                        .append_with_color(Color::Blue, format!("{}.", display_name))
                         .log();
                 }
-            }
-
-            // Someone wants to read a spellbook...
-            let mut new_spells: Vec<Spell> = Vec::new();
-            if let Some(spellbook) = spell_knowledge.get(consume.item) {
-                has_effect = true;
-                should_consume = true;
-                if entity == *player_entity {
-                    new_spells.extend_from_slice(&spellbook.spells);
-                    let spell_names: Vec<String> =
-                        spellbook.spells.iter().map(|s| s.name.clone()).collect();
-                    Logger::new()
-                        .append("You read the ")
-                        .append_with_color(Color::Blue, format!("{}", item_name.name))
-                        .append(", and learn the following spell(s) -> ")
-                        .append_with_color(Color::Green, format!("{}", spell_names.join(", ")))
-                        .log();
+                for effect in consumable.effects.iter() {
+                    create_effect(Effect {
+                        effect_type: EffectType::ItemUse {
+                            effect: effect.clone(),
+                            user: entity,
+                            target: consume.target,
+                        },
+                        _creator: Some(entity),
+                    });
+                }
+                if entity == *player_entity && !identification.is_identified(&item_name.name) {
+                    create_effect(Effect {
+                        effect_type: EffectType::IdentifyByUse {
+                            item_name: item_name.name.clone(),
+                        },
+                        _creator: Some(entity),
+                    });
                 }
-            }
-
-            // If the player read a spellbook above, teach any new spells to the player
-            if let Some(player_spells) = spell_knowledge.get_mut(*player_entity) {
-                let net_new_spells: Vec<Spell> = new_spells
-                    .iter()
-                    .filter(|ns| {
-                        player_spells
-                            .spells
-                            .iter()
-                            .find(|os| ns.name == os.name)
-                            .is_none()
-                    })
-                    .cloned()
-                    .collect();
-                player_spells.spells.extend_from_slice(&net_new_spells);
             }
 
             // Someone wants to equip an item...
             if let Some(equippable) = equippables.get(consume.item) {
                 has_effect = true;
 
-                let mut unequip: Vec<Entity> = Vec::new();
-                for (item_entity, equipment, name) in (&entities, &equipment, &names).join() {
-                    if equipment.owner == entity && equipment.slot == equippable.slot {
-                        unequip.push(item_entity);
+                /*
+                 * Using an equipped item takes it off again, so it is looked for in whichever
+                 * slot it is in. Otherwise it goes in its own slot, unless the off hand was asked for.
+                 */
+                let slot = match consume.slot {
+                    Some(slot) => slot,
+                    None => equipment
+                        .get(consume.item)
+                        .filter(|equipped| equipped.owner == entity)
+                        .map(|equipped| equipped.slot)
+                        .unwrap_or(equippable.slot),
+                };
+                if slot != equippable.slot && !(slot == EquipmentSlot::Shield && equippable.fits_off_hand()) {
+                    if entity == *player_entity {
+                        Logger::new()
+                            .append(format!("The {} is too unwieldy to hold in your off hand.", display_name))
+                            .log();
+                    }
+                    continue;
+                }
+                let is_equipped = equipment.get(consume.item).is_some_and(|equipped| equipped.owner == entity);
+                if slot == EquipmentSlot::Shield
+                    && equippable.slot == EquipmentSlot::Weapon
+                    && !is_equipped
+                    && !can_dual_wield(entity, &feats)
+                {
+                    if entity == *player_entity {
+                        Logger::new()
+                            .append("You lack the training to fight with a weapon in your off hand.")
+                            .log();
+                    }
+                    continue;
+                }
+                let conflicts = get_equip_conflicts(entity, equippable, slot, &entities, &equipment, &equippables);
+
+                /*
+                 * Cursed equipment refuses to leave its slot, which also blocks
+                 * swapping anything else into that slot until the curse is lifted.
+                 */
+                let cursed_item = conflicts
+                    .iter()
+                    .filter(|item_entity| curses.contains(**item_entity))
+                    .map(|item_entity| (*item_entity, names.get(*item_entity).map(|n| n.name.clone()).unwrap_or_default()))
+                    .next();
+                if let Some((cursed_entity, cursed_name)) = cursed_item {
+                    if let Some(curse) = curses.get_mut(cursed_entity) {
+                        curse.known = true;
+                    }
+                    if entity == *player_entity {
+                        Logger::new()
+                            .append("You can't remove the ")
+                            .append_with_color(Color::Red, cursed_name)
+                            .append(", it's cursed!")
+                            .log();
+                    }
+                    continue;
+                }
+
+                /*
+                 * Anything in the way comes off. Items in other slots are only ever in the way
+                 * because of a two-handed weapon, which is worth spelling out.
+                 */
+                for item_entity in conflicts.iter() {
+                    let Some(equipped) = equipment.remove(*item_entity) else {
+                        continue;
+                    };
+                    let name = names.get(*item_entity).map(|n| n.name.clone()).unwrap_or_default();
+                    if entity != *player_entity {
+                        continue;
+                    }
+                    if equipped.slot == slot {
+                        Logger::new()
+                            .append(format!("You unequp the {} from the {:?} slot.", name, equipped.slot))
+                            .log();
+                    } else if equippable.is_two_handed() {
                         Logger::new()
                             .append(format!(
-                                "You unequp the {} from the {:?} slot.",
-                                name.name, equipment.slot,
+                                "You unequip the {} from the {:?} slot, you need both hands for the {}.",
+                                name, equipped.slot, display_name,
                             ))
                             .log();
+                    } else {
+                        Logger::new()
+                            .append(format!("You put away the {}, it needs both hands.", name))
+                            .log();
                     }
                 }
-                unequip.iter().for_each(|item| {
-                    equipment.remove(*item).expect("Unable to unequip item");
-                });
 
                 /*
                  * If the same item is being used and is already equipped...
                  * That means the player wants to unequip it, so we should not reequip it
                  */
-                let unequip_only = unequip.contains(&consume.item);
+                let unequip_only = conflicts.contains(&consume.item);
 
                 if !unequip_only {
                     equipment
                         .insert(
                             consume.item,
                             Equipped {
-                                slot: equippable.slot,
+                                slot,
                                 owner: entity,
                             },
                         )
@@ -205,62 +295,157 @@ impl<'a> System<'a> for InventorySystem {
                         Logger::new()
                             .append(format!(
                                 "You equip the {} to the {:?} slot.",
-                                item_name.name, equippable.slot
+                                display_name, slot
                             ))
                             .log();
                     }
+                    if let Some(curse) = curses.get_mut(consume.item).filter(|curse| !curse.known) {
+                        curse.known = true;
+                        if entity == *player_entity {
+                            Logger::new()
+                                .append_with_color(Color::Red, format!("The {} clamps onto you, it's cursed!", display_name))
+                                .log();
+                        }
+                    }
                 }
             }
 
-            // Someone wants to use a magic mapper scroll...
-            if magic_mappers.contains(consume.item) {
-                has_effect = true;
-                should_consume = true;
-                for tile in map.revealed_tiles.iter_mut() {
-                    *tile = true;
-                }
-                Logger::new()
-                    .append(
-                        "The darkness lifts, and you become more aware of everything around you.",
-                    )
-                    .log();
-            }
-
             if !has_effect {
                 Logger::new()
                     .append(
                         if should_consume {
-                            format!("You consume the {}, but nothing happens.", item_name.name)
+                            format!("You consume the {}, but nothing happens.", display_name)
                         } else {
-                            format!("You attempt to consume the {}, but nothing happens.", item_name.name)
+                            format!("You attempt to consume the {}, but nothing happens.", display_name)
                         })
                     .log();
             }
 
             /*
-             * Decrement the item stack since it was used.
-             * If this was the final element in the stack, remove the item entirely.
+             * Take the used item out of its stack and get rid of it,
+             * its effects have already been queued up above.
              */
             if should_consume {
                 if let Some(inventory) = inventories.get_mut(entity) {
-                    let item_stack = inventory
-                        .items
-                        .entry(item_name.name.clone())
-                        .or_insert(vec![]);
-                    item_stack.pop();
-                    if item_stack.is_empty() {
-                        inventory.items.shift_remove(&item_name.name);
-                        if inventory.index > 0 {
-                            inventory.index -= 1;
-                        }
-                    }
+                    inventory.remove_item(consume.item);
                 }
+                entities.delete(consume.item).expect("Unable to delete consumed item");
             }
         }
         wants_consume.clear();
+
+        /*
+         * Item drop and destroy subsystem
+         *
+         * Dropped items land on the owner's tile, destroyed items are gone for good.
+         * Either way, anything cursed and equipped refuses to leave its owner.
+         */
+        let mut discarded: Vec<(Entity, Entity, bool)> = Vec::new();
+        for (entity, drop) in (&entities, &wants_drop).join() {
+            discarded.extend(drop.items.iter().map(|item| (entity, *item, false)));
+        }
+        for (entity, destroy) in (&entities, &wants_destroy).join() {
+            discarded.extend(destroy.items.iter().map(|item| (entity, *item, true)));
+        }
+        let mut discarded_counts: IndexMap<(String, bool), i32> = IndexMap::new();
+        for (entity, item, destroy) in discarded.into_iter() {
+            let display_name = names
+                .get(item)
+                .map(|name| identification.display_name(&name.name))
+                .unwrap_or("???".to_string());
+            if equipment.contains(item) {
+                if let Some(curse) = curses.get_mut(item) {
+                    curse.known = true;
+                    if entity == *player_entity {
+                        Logger::new()
+                            .append_with_color(Color::Red, format!("You can't let go of the {}, it's cursed!", display_name))
+                            .log();
+                    }
+                    continue;
+                }
+            }
+
+            equipment.remove(item);
+            backpack.remove(item);
+            if let Some(inventory) = inventories.get_mut(entity) {
+                inventory.remove_item(item);
+            }
+            if destroy {
+                entities.delete(item).expect("Unable to destroy item");
+            } else if let Some(pos) = positions.get(entity).copied() {
+                positions.insert(item, pos).expect("Unable to drop item");
+            }
+            if entity == *player_entity {
+                *discarded_counts.entry((display_name, destroy)).or_insert(0) += 1;
+            }
+        }
+        for ((display_name, destroy), count) in discarded_counts.into_iter() {
+            Logger::new()
+                .append(if destroy { "You destroy " } else { "You drop " })
+                .append_with_color(
+                    Color::LightBlue,
+                    if count > 1 { format!("{} x {}.", display_name, count) } else { format!("the {}.", display_name) },
+                )
+                .log();
+        }
+        wants_drop.clear();
+        wants_destroy.clear();
+
+        /*
+         * Anything whose signature changed this turn (e.g. a curse was discovered)
+         * moves into the stack it now belongs to.
+         */
+        for inventory in (&mut inventories).join() {
+            inventory.restack(|item| item_signature(item, &names, &rarities, &curses, &durabilities));
+        }
     }
 }
 
+/// Builds the signature that decides which stack the given item belongs to.
+/// Curses only set items apart once they are known, so stacks never give a curse away.
+/// Worn items are set apart from fresh ones, since they can no longer be swapped freely.
+pub fn item_signature<D: Deref<Target = MaskedStorage<Cursed>>>(
+    item: Entity,
+    names: &ReadStorage<Name>,
+    rarities: &ReadStorage<Rarity>,
+    curses: &Storage<Cursed, D>,
+    durabilities: &ReadStorage<Durability>,
+) -> ItemSignature {
+    return ItemSignature {
+        name: names.get(item).map(|n| n.name.clone()).unwrap_or_default(),
+        rarity: rarities.get(item).map(|r| r.tier),
+        known_curse: curses.get(item).is_some_and(|curse| curse.known),
+        durability: durabilities.get(item).map(|durability| durability.current),
+    };
+}
+
+/// Same as `item_signature`, for callers holding the world rather than storages.
+pub fn get_item_signature(ecs: &World, item: Entity) -> ItemSignature {
+    return item_signature(
+        item,
+        &ecs.read_storage::<Name>(),
+        &ecs.read_storage::<Rarity>(),
+        &ecs.read_storage::<Cursed>(),
+        &ecs.read_storage::<Durability>(),
+    );
+}
+
+/// Picks the item in a stack that an action should apply to.
+///
+/// Stacked items are interchangeable apart from being equipped, so this only decides
+/// between the equipped item (e.g. to unequip it) and a spare (e.g. to sell or throw).
+pub fn pick_from_stack<D: Deref<Target = MaskedStorage<Equipped>>>(
+    stack: &[Entity],
+    equipment: &Storage<Equipped, D>,
+    prefer_equipped: bool,
+) -> Option<Entity> {
+    return stack
+        .iter()
+        .find(|item| equipment.contains(**item) == prefer_equipped)
+        .or(stack.first())
+        .copied();
+}
+
 pub fn get_equipped_weapon(
     player_entity: Entity,
     entities: &Entities,
@@ -288,3 +473,328 @@ pub fn get_equipped_weapon(
     }
     None
 }
+
+/// Lists the equipped items that would have to come off for the owner to put an item in the given slot.
+///
+/// Besides whatever is already in the slot, a two-handed weapon needs the shield slot free,
+/// and anything going into the shield slot needs any two-handed weapon put away.
+pub fn get_equip_conflicts<D: Deref<Target = MaskedStorage<Equipped>>>(
+    owner: Entity,
+    equippable: &Equippable,
+    slot: EquipmentSlot,
+    entities: &Entities,
+    equipment: &Storage<Equipped, D>,
+    equippables: &ReadStorage<Equippable>,
+) -> Vec<Entity> {
+    return (entities, equipment)
+        .join()
+        .filter(|(_, equipped)| equipped.owner == owner)
+        .filter(|(item, equipped)| {
+            equipped.slot == slot
+                || (equippable.is_two_handed() && equipped.slot == EquipmentSlot::Shield)
+                || (slot == EquipmentSlot::Shield && equippables.get(*item).is_some_and(|e| e.is_two_handed()))
+        })
+        .map(|(item, _)| item)
+        .collect();
+}
+
+/// Returns whatever the owner has equipped in the given slot.
+pub fn get_equipped_in_slot(ecs: &World, owner: Entity, slot: EquipmentSlot) -> Option<Entity> {
+    let entities = ecs.entities();
+    let equipment = ecs.read_storage::<Equipped>();
+    return (&entities, &equipment)
+        .join()
+        .find(|(_, equipped)| equipped.owner == owner && equipped.slot == slot)
+        .map(|(item, _)| item);
+}
+
+/// Lists the backpack items the owner could put into the given slot, including
+/// off hand weapons for the shield slot once the owner can dual wield. One per
+/// inventory stack, leaving out anything already equipped.
+pub fn get_slot_candidates(ecs: &World, owner: Entity, slot: EquipmentSlot) -> Vec<Entity> {
+    let inventories = ecs.read_storage::<Inventory>();
+    let equipment = ecs.read_storage::<Equipped>();
+    let equippables = ecs.read_storage::<Equippable>();
+    let dual_wield = can_dual_wield(owner, &ecs.read_storage::<Feats>());
+    let Some(inventory) = inventories.get(owner) else {
+        return Vec::new();
+    };
+    return inventory
+        .items
+        .values()
+        .filter_map(|stack| pick_from_stack(stack, &equipment, false))
+        .filter(|item| !equipment.contains(*item))
+        .filter(|item| {
+            equippables.get(*item).is_some_and(|equippable| {
+                equippable.slot == slot || (slot == EquipmentSlot::Shield && dual_wield && equippable.fits_off_hand())
+            })
+        })
+        .collect();
+}
+
+/// Whether the entity has trained to hold a weapon in its off hand, see `FeatType::TwoWeaponFighting`.
+pub fn can_dual_wield(entity: Entity, feats: &ReadStorage<Feats>) -> bool {
+    return feats.get(entity).is_some_and(|feats| feats.has(FeatType::TwoWeaponFighting));
+}
+
+/// Returns the ammunition sitting in the owner's quiver, as long as it
+/// is the type of ammunition the weapon fires.
+pub fn get_quivered_ammo<D: Deref<Target = MaskedStorage<Equipped>>>(
+    owner: Entity,
+    ammo_type: AmmoType,
+    entities: &Entities,
+    equipment: &Storage<Equipped, D>,
+    ammunition: &ReadStorage<Ammunition>,
+) -> Option<Entity> {
+    (entities, equipment, ammunition)
+        .join()
+        .find(|(_, equipped, ammo)| {
+            equipped.owner == owner
+                && equipped.slot == EquipmentSlot::Quiver
+                && ammo.ammo_type == ammo_type
+        })
+        .map(|(entity, _, _)| entity)
+}
+
+/// Lifts the curse from everything the owner has equipped.
+/// Returns the names of the items that were uncursed.
+pub fn lift_curses(ecs: &World, owner: Entity) -> Vec<String> {
+    let entities = ecs.entities();
+    let equipment = ecs.read_storage::<Equipped>();
+    let names = ecs.read_storage::<Name>();
+    let mut curses = ecs.write_storage::<Cursed>();
+
+    let cursed: Vec<(Entity, String)> = (&entities, &equipment, &names, &curses)
+        .join()
+        .filter(|(_, equipped, _, _)| equipped.owner == owner)
+        .map(|(item, _, name, _)| (item, name.name.clone()))
+        .collect();
+    for (item, _) in cursed.iter() {
+        curses.remove(*item);
+    }
+    return cursed.into_iter().map(|(_, name)| name).collect();
+}
+
+/// Groups every visible item lying on the given map tile by `ItemSignature`,
+/// the same way the inventory stacks items in a backpack.
+pub fn get_tile_items(ecs: &World, x: i32, y: i32) -> IndexMap<ItemSignature, Vec<Entity>> {
+    let entities = ecs.entities();
+    let items = ecs.read_storage::<Item>();
+    let positions = ecs.read_storage::<Position>();
+    let names = ecs.read_storage::<Name>();
+    let rarities = ecs.read_storage::<Rarity>();
+    let curses = ecs.read_storage::<Cursed>();
+    let durabilities = ecs.read_storage::<Durability>();
+    let hidden = ecs.read_storage::<Hidden>();
+
+    let mut tile_items: IndexMap<ItemSignature, Vec<Entity>> = IndexMap::new();
+    for (entity, _item, position, _name, _hidden) in (&entities, &items, &positions, &names, !&hidden).join() {
+        if position.x == x && position.y == y {
+            tile_items
+                .entry(item_signature(entity, &names, &rarities, &curses, &durabilities))
+                .or_insert(vec![])
+                .push(entity);
+        }
+    }
+    return tile_items;
+}
+
+///
+/// I N V E N T O R Y  V I E W
+/// 
+/// Lists the stacks of an inventory that the inventory screen shows, as indices
+/// into `Inventory.items`, following the view's tab, search text and sort order.
+///
+/// Equipped stacks are pinned to the top. Ties keep the order items were picked up in,
+/// so the list stays put while the player moves through it.
+pub fn get_inventory_view(ecs: &World, inventory: &Inventory) -> Vec<usize> {
+    let items = ecs.read_storage::<Item>();
+    let equipment = ecs.read_storage::<Equipped>();
+    let equippables = ecs.read_storage::<Equippable>();
+    let consumables = ecs.read_storage::<Consumable>();
+    let identification = ecs.fetch::<IdentificationTable>();
+    let filter = inventory.view.filter.to_lowercase();
+
+    let mut view: Vec<(usize, String, &Vec<Entity>)> = inventory
+        .items
+        .iter()
+        .enumerate()
+        .map(|(index, (signature, stack))| (index, identification.display_name(&signature.name), stack))
+        .filter(|(_, display_name, _)| display_name.to_lowercase().contains(&filter))
+        .filter(|(_, _, stack)| {
+            inventory.view.tab == InventoryTab::All
+                || stack.first().is_some_and(|item| item_tab(*item, &equippables, &consumables) == inventory.view.tab)
+        })
+        .collect();
+
+    let value = |stack: &Vec<Entity>| stack.first().and_then(|item| items.get(*item)).map(|item| item.base_value).unwrap_or(0);
+    let weight = |stack: &Vec<Entity>| stack.first().and_then(|item| items.get(*item)).map(|item| item.weight).unwrap_or(0.0);
+    let slot = |stack: &Vec<Entity>| stack.first().and_then(|item| equippables.get(*item)).map(|e| e.slot as usize).unwrap_or(usize::MAX);
+    let equipped = |stack: &Vec<Entity>| stack.iter().any(|item| equipment.contains(*item));
+    view.sort_by(|a, b| {
+        let order = match inventory.view.sort {
+            InventorySort::Acquired => Ordering::Equal,
+            InventorySort::Name => a.1.to_lowercase().cmp(&b.1.to_lowercase()),
+            InventorySort::Value => value(b.2).cmp(&value(a.2)),
+            InventorySort::Weight => weight(b.2).total_cmp(&weight(a.2)),
+            InventorySort::Slot => slot(a.2).cmp(&slot(b.2)),
+        };
+        return equipped(b.2).cmp(&equipped(a.2)).then(order);
+    });
+    return view.into_iter().map(|(index, _, _)| index).collect();
+}
+
+/// Returns the stack highlighted on the inventory screen, if any.
+pub fn get_highlighted_stack<'a>(ecs: &World, inventory: &'a Inventory) -> Option<(&'a ItemSignature, &'a Vec<Entity>)> {
+    return get_inventory_view(ecs, inventory)
+        .get(inventory.index)
+        .and_then(|index| inventory.items.get_index(*index));
+}
+
+/// Decides which inventory tab an item is listed under.
+fn item_tab(item: Entity, equippables: &ReadStorage<Equippable>, consumables: &ReadStorage<Consumable>) -> InventoryTab {
+    if let Some(equippable) = equippables.get(item) {
+        return match equippable.slot {
+            EquipmentSlot::Weapon | EquipmentSlot::Quiver => InventoryTab::Weapons,
+            _ => InventoryTab::Armor,
+        };
+    }
+    return match consumables.get(item) {
+        Some(consumable) if consumable.effects.iter().any(|effect| matches!(effect, ItemEffect::LearnSpell { .. })) => InventoryTab::Spellbooks,
+        Some(_) => InventoryTab::Consumables,
+        None => InventoryTab::Junk,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{Builder, WorldExt};
+
+    fn item_world() -> World {
+        let mut world = World::new();
+        world.register::<Name>();
+        world.register::<Rarity>();
+        world.register::<Cursed>();
+        world.register::<Durability>();
+        world.register::<Equippable>();
+        world.register::<Equipped>();
+        world.register::<Item>();
+        world.register::<Position>();
+        world.register::<Hidden>();
+        return world;
+    }
+
+    fn spawn_dagger(world: &mut World, curse: Option<Cursed>, durability: i32) -> Entity {
+        let mut builder = world
+            .create_entity()
+            .with(Name { name: "Dagger".to_string() })
+            .with(Durability { current: durability, max: 30 });
+        if let Some(curse) = curse {
+            builder = builder.with(curse);
+        }
+        return builder.build();
+    }
+
+    #[test]
+    fn item_signature_sets_apart_wear_and_known_curses() {
+        let mut world = item_world();
+        let fresh = spawn_dagger(&mut world, None, 30);
+        let also_fresh = spawn_dagger(&mut world, None, 30);
+        let worn = spawn_dagger(&mut world, None, 12);
+        let secretly_cursed = spawn_dagger(&mut world, Some(Cursed { known: false }), 30);
+        let cursed = spawn_dagger(&mut world, Some(Cursed { known: true }), 30);
+
+        let signature = |item: Entity| get_item_signature(&world, item);
+        assert_eq!(signature(fresh), signature(also_fresh));
+        assert_eq!(signature(fresh), signature(secretly_cursed));
+        assert_ne!(signature(fresh), signature(worn));
+        assert_ne!(signature(fresh), signature(cursed));
+        assert_eq!(signature(worn).durability, Some(12));
+    }
+
+    #[test]
+    fn tile_items_are_grouped_by_signature() {
+        let mut world = item_world();
+        let mut drop_dagger = |durability: i32| {
+            let dagger = spawn_dagger(&mut world, None, durability);
+            world.write_storage::<Item>()
+                .insert(dagger, Item { description: String::new(), base_value: 10, weight: 1.0 })
+                .expect("Unable to insert item");
+            world.write_storage::<Position>()
+                .insert(dagger, Position { x: 3, y: 4 })
+                .expect("Unable to insert position");
+            return dagger;
+        };
+        let fresh = drop_dagger(30);
+        let also_fresh = drop_dagger(30);
+        let worn = drop_dagger(12);
+
+        let tile_items = get_tile_items(&world, 3, 4);
+        assert_eq!(tile_items.len(), 2);
+        assert_eq!(tile_items[&get_item_signature(&world, fresh)], vec![fresh, also_fresh]);
+        assert_eq!(tile_items[&get_item_signature(&world, worn)], vec![worn]);
+        assert!(get_tile_items(&world, 4, 4).is_empty());
+    }
+
+    fn spawn_equipped(world: &mut World, owner: Entity, slot: EquipmentSlot, hands: i32) -> Entity {
+        let equip_slot = if slot == EquipmentSlot::Shield && hands > 0 { EquipmentSlot::Weapon } else { slot };
+        return world
+            .create_entity()
+            .with(Equippable { slot: equip_slot, hands, light: false })
+            .with(Equipped { slot, owner })
+            .build();
+    }
+
+    fn conflicts(world: &World, owner: Entity, equippable: &Equippable, slot: EquipmentSlot) -> Vec<Entity> {
+        return get_equip_conflicts(
+            owner,
+            equippable,
+            slot,
+            &world.entities(),
+            &world.read_storage::<Equipped>(),
+            &world.read_storage::<Equippable>(),
+        );
+    }
+
+    #[test]
+    fn two_handed_weapon_displaces_off_hand() {
+        let mut world = item_world();
+        let owner = world.create_entity().build();
+        let sword = spawn_equipped(&mut world, owner, EquipmentSlot::Weapon, 1);
+        let shield = spawn_equipped(&mut world, owner, EquipmentSlot::Shield, 0);
+        let helmet = spawn_equipped(&mut world, owner, EquipmentSlot::Head, 0);
+
+        let greatsword = Equippable { slot: EquipmentSlot::Weapon, hands: 2, light: false };
+        let displaced = conflicts(&world, owner, &greatsword, EquipmentSlot::Weapon);
+        assert_eq!(displaced.len(), 2);
+        assert!(displaced.contains(&sword) && displaced.contains(&shield));
+        assert!(!displaced.contains(&helmet));
+    }
+
+    #[test]
+    fn off_hand_item_displaces_two_handed_weapon() {
+        let mut world = item_world();
+        let owner = world.create_entity().build();
+        let greatsword = spawn_equipped(&mut world, owner, EquipmentSlot::Weapon, 2);
+
+        let shield = Equippable { slot: EquipmentSlot::Shield, hands: 0, light: false };
+        assert_eq!(conflicts(&world, owner, &shield, EquipmentSlot::Shield), vec![greatsword]);
+
+        let dagger = Equippable { slot: EquipmentSlot::Weapon, hands: 1, light: true };
+        assert_eq!(conflicts(&world, owner, &dagger, EquipmentSlot::Shield), vec![greatsword]);
+    }
+
+    #[test]
+    fn one_handed_weapon_keeps_off_hand_and_ignores_other_owners() {
+        let mut world = item_world();
+        let owner = world.create_entity().build();
+        let other = world.create_entity().build();
+        let sword = spawn_equipped(&mut world, owner, EquipmentSlot::Weapon, 1);
+        spawn_equipped(&mut world, owner, EquipmentSlot::Shield, 0);
+        spawn_equipped(&mut world, other, EquipmentSlot::Weapon, 2);
+
+        let axe = Equippable { slot: EquipmentSlot::Weapon, hands: 1, light: false };
+        assert_eq!(conflicts(&world, owner, &axe, EquipmentSlot::Weapon), vec![sword]);
+    }
+}
@@ -7,11 +7,24 @@ use specs::prelude::*;
 use crate::{
     Attack, Damage, Name, Stats,
     component::{
-        Armor, AttackType, DamageType, Equipped, Lifetime, MeleeWeapon, Position, RangedWeapon, Renderable
+        AlertState, Alertness, Ammunition, AreaShape, Armor, AttackType, DamageType, DerivedStats,
+        Durability, EquipmentSlot, Equipped, Feats, InBackpack, Inventory, Lifetime, MeleeWeapon,
+        Noise, Position, RangedWeapon, Renderable, Statuses,
     },
+    generate::map::{Map, TileType},
     logbook::logbook::Logger,
+    system::{
+        alertness_system::{MAGIC_NOISE, MELEE_NOISE, RANGED_NOISE},
+        derived_stats_system::{BASE_ARMOR_CLASS, resist_damage, stat_to_modifier},
+        inventory_system::{can_dual_wield, get_quivered_ammo},
+        particle_system::{ParticleStorages, spawn_explosion},
+        ranged_combat_system::get_area_tiles,
+    },
 };
 
+/// How much durability a piece of armor loses to a single blow of fire damage.
+pub const FIRE_ARMOR_WEAR: i32 = 3;
+
 pub struct MeleeCombatSystem {}
 
 impl<'a> System<'a> for MeleeCombatSystem {
@@ -22,13 +35,23 @@ impl<'a> System<'a> for MeleeCombatSystem {
         ReadStorage<'a, Name>,
         WriteStorage<'a, Stats>,
         WriteStorage<'a, Damage>,
-        ReadStorage<'a, Equipped>,
+        WriteStorage<'a, Equipped>,
         ReadStorage<'a, MeleeWeapon>,
         ReadStorage<'a, RangedWeapon>,
+        ReadStorage<'a, Ammunition>,
         ReadStorage<'a, Armor>,
+        WriteStorage<'a, Durability>,
+        WriteStorage<'a, Inventory>,
+        WriteStorage<'a, InBackpack>,
+        WriteStorage<'a, Statuses>,
+        ReadStorage<'a, DerivedStats>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, Renderable>,
         WriteStorage<'a, Lifetime>,
+        WriteStorage<'a, Alertness>,
+        WriteStorage<'a, Noise>,
+        ReadExpect<'a, Map>,
+        ReadStorage<'a, Feats>,
     );
 
     /*
@@ -46,95 +69,298 @@ impl<'a> System<'a> for MeleeCombatSystem {
             names,
             mut stats,
             mut damages,
-            equipment,
+            mut equipment,
             melee_weapons,
             ranged_weapons,
-            armor,
+            ammunition,
+            armors,
+            mut durabilities,
+            mut inventories,
+            mut backpacks,
+            mut statuses,
+            derived_stats,
             mut positions,
             mut renderables,
             mut lifetimes,
+            mut alertness,
+            mut noises,
+            map,
+            feats,
         ) = data;
 
         let mut mana_burndown: Vec<(Entity, i32)> = Vec::new();
+        let mut spent_ammo: Vec<(Entity, Entity, Option<Point>)> = Vec::new();
+        let mut worn_down: Vec<(Entity, i32)> = Vec::new();
 
         for (attacker_entity, attack, name, stat) in
             (&entities, &mut attacks, &names, &stats).join()
         {
             // attacker's health
-            if stat.hp.current > 0 {
-                if !stats.contains(attack.target) {
-                    continue;
-                }
-                let target_stats = stats.get(attack.target).unwrap();
-                let target_name = names.get(attack.target).unwrap();
-
-                // target's health
-                if target_stats.hp.current > 0 {
-                    let mut weapon_damage: i32 = 1;
-                    let mut weapon_name: String = "fisticuffs".to_string();
-                    let mut damage_type: DamageType = DamageType::Bludgeoning;
-                    match attack.attack_type {
-                        AttackType::Melee => {
-                            for (equipped, melee_weapon, name) in (&equipment, &melee_weapons, &names).join() {
-                                if equipped.owner == attacker_entity {
-                                    weapon_damage = rng.roll_dice(
-                                        melee_weapon.damage.dice_count,
-                                        melee_weapon.damage.dice_sides,
-                                    ) + melee_weapon.damage.modifier;
-                                    weapon_name = name.name.clone();
-                                    damage_type = melee_weapon.damage_type;
-                                }
+            if stat.hp.current <= 0 {
+                continue;
+            }
+
+            /*
+             * Area of effect spells hit every entity caught in the area, including
+             * the attacker if they happen to be standing in it. Everything else
+             * only ever hits the given target.
+             */
+            let attacker_point = positions.get(attacker_entity).map(|p| Point::new(p.x, p.y));
+            let area = attack.spell.as_ref().and_then(|spell| spell.area);
+            let area_tiles: Vec<usize> = match (area, attack.aim, attacker_point) {
+                (Some(area), Some(aim), Some(origin)) => get_area_tiles(&map, origin, aim, &area),
+                _ => Vec::new(),
+            };
+            let targets: Vec<Entity> = match area {
+                Some(_) => area_tiles
+                    .iter()
+                    .flat_map(|index| map.tile_content[*index].iter())
+                    .filter(|entity| stats.contains(**entity))
+                    .copied()
+                    .collect(),
+                None => attack.target.into_iter().filter(|target| stats.contains(*target)).collect(),
+            };
+            if area.is_none() && targets.is_empty() {
+                continue;
+            }
+
+            let mut weapon_damage: i32 = 1;
+            let mut weapon_name: String = "fisticuffs".to_string();
+            let mut damage_type: DamageType = DamageType::Bludgeoning;
+            let mut ammo: Option<&Ammunition> = None;
+            let mut weapon: Option<Entity> = None;
+            match attack.attack_type {
+                AttackType::Melee => {
+                    for (item, equipped, melee_weapon, name) in (&entities, &equipment, &melee_weapons, &names).join() {
+                        if equipped.owner == attacker_entity && equipped.slot == EquipmentSlot::Weapon {
+                            /* A broken weapon hits no harder than bare hands */
+                            if durabilities.get(item).is_some_and(|durability| durability.is_broken()) {
+                                weapon_name = format!("a broken {}", name.name);
+                                continue;
                             }
+                            weapon = Some(item);
+                            weapon_damage = rng.roll_dice(
+                                melee_weapon.damage.dice_count,
+                                melee_weapon.damage.dice_sides,
+                            ) + melee_weapon.damage.modifier;
+                            weapon_name = name.name.clone();
+                            damage_type = melee_weapon.damage_type;
                         }
-                        AttackType::Ranged => {
-                            for (equipped, ranged_weapon, name) in (&equipment, &ranged_weapons, &names).join() {
-                                if equipped.owner == attacker_entity {
-                                    weapon_damage = rng.roll_dice(
-                                        ranged_weapon.damage.dice_count,
-                                        ranged_weapon.damage.dice_sides,
-                                    ) + ranged_weapon.damage.modifier;
-                                    weapon_name = name.name.clone();
-                                    damage_type = ranged_weapon.damage_type;
-                                }
-                            }
+                    }
+                }
+                AttackType::Ranged => {
+                    let mut ammo_type = None;
+                    for (item, equipped, ranged_weapon, name) in (&entities, &equipment, &ranged_weapons, &names).join() {
+                        if equipped.owner == attacker_entity && equipped.slot == EquipmentSlot::Weapon {
+                            weapon = Some(item);
+                            weapon_damage = rng.roll_dice(
+                                ranged_weapon.damage.dice_count,
+                                ranged_weapon.damage.dice_sides,
+                            ) + ranged_weapon.damage.modifier;
+                            weapon_name = name.name.clone();
+                            damage_type = ranged_weapon.damage_type;
+                            ammo_type = ranged_weapon.ammo;
                         }
-                        AttackType::Magic => {
-                            if let Some(spell) = &attack.spell {
-                                if stat.mp.current >= spell.mp_cost {
-                                    weapon_damage = rng.roll_dice(
-                                        spell.damage.dice_count,
-                                        spell.damage.dice_sides
-                                    ) + spell.damage.modifier;
-                                    weapon_name = spell.name.clone();
-                                    damage_type = spell.damage_type;
-                                    mana_burndown.push((attacker_entity, spell.mp_cost));
-                                } else {
-                                    Logger::new()
-                                        .append("You tried to case a spell, but you don't have enough mana!")
-                                        .log();
-                                    continue;
-                                }
-                            } else {
-                                Logger::new()
-                                    .append("You tried to cast a spell, but you don't know any spells, silly!")
-                                    .log();
-                                continue;
-                            }
+                    }
+
+                    if weapon.is_some_and(|weapon| durabilities.get(weapon).is_some_and(|d| d.is_broken())) {
+                        Logger::new()
+                            .append(format!("{}'s {} is broken and can't be fired!", &name.name, weapon_name))
+                            .log();
+                        continue;
+                    }
+
+                    /*
+                     * Bows and the like need ammunition from the quiver, which is spent
+                     * whether or not the shot lands. Special ammunition can hit harder
+                     * or change the type of damage dealt entirely.
+                     */
+                    if let Some(ammo_type) = ammo_type {
+                        let Some(ammo_entity) =
+                            get_quivered_ammo(attacker_entity, ammo_type, &entities, &equipment, &ammunition)
+                        else {
+                            Logger::new()
+                                .append(format!("{} has no {:?} ammunition to fire!", &name.name, ammo_type))
+                                .log();
+                            continue;
+                        };
+                        let quivered = ammunition.get(ammo_entity).expect("Unable to access quivered ammunition");
+                        weapon_damage += quivered.damage_bonus;
+                        damage_type = quivered.damage_type.unwrap_or(damage_type);
+                        ammo = Some(quivered);
+                        spent_ammo.push((attacker_entity, ammo_entity, attack.aim));
+                    }
+                }
+                AttackType::Magic => {
+                    if let Some(spell) = &attack.spell {
+                        if stat.mp.current >= spell.mp_cost {
+                            weapon_damage = rng.roll_dice(
+                                spell.damage.dice_count,
+                                spell.damage.dice_sides
+                            ) + spell.damage.modifier;
+                            weapon_name = spell.name.clone();
+                            damage_type = spell.damage_type;
+                            mana_burndown.push((attacker_entity, spell.mp_cost));
+                        } else {
+                            Logger::new()
+                                .append("You tried to case a spell, but you don't have enough mana!")
+                                .log();
+                            continue;
+                        }
+                    } else {
+                        Logger::new()
+                            .append("You tried to cast a spell, but you don't know any spells, silly!")
+                            .log();
+                        continue;
+                    }
+                }
+            }
+
+            /*
+             * Fighting is loud, regardless of whether or not the blow lands.
+             */
+            Noise::new(
+                &mut noises,
+                attacker_entity,
+                match attack.attack_type {
+                    AttackType::Melee => MELEE_NOISE,
+                    AttackType::Ranged => RANGED_NOISE,
+                    AttackType::Magic => MAGIC_NOISE,
+                },
+            );
+
+            /*
+             * Area spells fly towards the aim point and then explode over the whole area.
+             */
+            if let (Some(area), Some(aim), Some(origin)) = (area, attack.aim, attacker_point) {
+                if area.shape == AreaShape::Ball {
+                    spawn_bolt(&entities, &mut positions, &mut renderables, &mut lifetimes, origin, aim);
+                }
+                let center = if area.shape == AreaShape::Ball { aim } else { origin };
+                spawn_explosion(
+                    &entities,
+                    ParticleStorages {
+                        positions: &mut positions,
+                        renderables: &mut renderables,
+                        lifetimes: &mut lifetimes,
+                    },
+                    &map,
+                    center,
+                    &area_tiles,
+                    damage_type,
+                );
+                if targets.is_empty() {
+                    Logger::new()
+                        .append(format!("{}'s {} hits nothing but air.", &name.name, weapon_name))
+                        .log();
+                }
+            }
+
+            /*
+             * With the Two-Weapon Fighting feat, a light weapon held in the off hand follows up
+             * every melee attack with a second strike, which gets none of the attacker's damage bonus.
+             */
+            let mut strikes = vec![(weapon_damage, weapon_name, damage_type, false, weapon)];
+            if attack.attack_type == AttackType::Melee
+                && can_dual_wield(attacker_entity, &feats)
+            {
+                for (item, equipped, melee_weapon, name) in (&entities, &equipment, &melee_weapons, &names).join() {
+                    if equipped.owner == attacker_entity
+                        && equipped.slot == EquipmentSlot::Shield
+                        && !durabilities.get(item).is_some_and(|durability| durability.is_broken())
+                    {
+                        strikes.push((
+                            rng.roll_dice(melee_weapon.damage.dice_count, melee_weapon.damage.dice_sides)
+                                + melee_weapon.damage.modifier,
+                            name.name.clone(),
+                            melee_weapon.damage_type,
+                            true,
+                            Some(item),
+                        ));
+                    }
+                }
+            }
+
+            for (weapon_damage, weapon_name, damage_type, offhand, weapon) in strikes {
+                for target in targets.iter() {
+                    let target_stats = stats.get(*target).unwrap();
+                    let target_name = names.get(*target).unwrap();
+
+                    // target's health
+                    if target_stats.hp.current <= 0 {
+                        continue;
+                    }
+                    let mut target_damage = weapon_damage;
+                    let mut is_sneak_attack = false;
+
+                    /*
+                     * Sneak attack!
+                     * Striking a sleeping or unaware target in melee doubles the weapon
+                     * damage and adds the attacker's dexterity on top. Either way, the
+                     * target is certainly paying attention now.
+                     */
+                    if let Some(target_alertness) = alertness.get_mut(*target) {
+                        if target_alertness.state != AlertState::Alert
+                            && attack.attack_type == AttackType::Melee
+                        {
+                            let dexterity = derived_stats.get(attacker_entity).map(|d| d.dexterity).unwrap_or(stat.dexterity);
+                            target_damage = (target_damage * 2) + i32::max(0, stat_to_modifier(dexterity));
+                            is_sneak_attack = true;
+                            Logger::new()
+                                .append(format!("{} catches {} off guard!", &name.name, &target_name.name))
+                                .log();
                         }
+                        target_alertness.state = AlertState::Alert;
                     }
 
-                    let mut armor_defense = 0;
-                    for (equipped, armor) in (&equipment, &armor).join() {
-                        if equipped.owner == attack.target {
-                            armor_defense = armor.defense;
+                    /*
+                     * Roll to hit against the target's armor class.
+                     * Area spells and sneak attacks can't be dodged, and a natural
+                     * 20 or 1 always hits or misses respectively.
+                     */
+                    let attacker_derived = derived_stats.get(attacker_entity).cloned().unwrap_or_default();
+                    let (to_hit, damage_bonus) = match attack.attack_type {
+                        AttackType::Melee => (attacker_derived.melee_to_hit, attacker_derived.melee_damage),
+                        AttackType::Ranged => (attacker_derived.ranged_to_hit, attacker_derived.ranged_damage),
+                        AttackType::Magic => (attacker_derived.spell_to_hit, attacker_derived.spell_power),
+                    };
+                    let damage_bonus = if offhand { 0 } else { damage_bonus };
+                    if area.is_none() && !is_sneak_attack {
+                        let armor_class = derived_stats.get(*target).map(|d| d.armor_class).unwrap_or(BASE_ARMOR_CLASS);
+                        let roll = rng.roll_dice(1, 20);
+                        if roll == 1 || (roll != 20 && roll + to_hit < armor_class) {
+                            Logger::new()
+                                .append(format!(
+                                    "{} attacks {} with {}, but misses",
+                                    &name.name, &target_name.name, weapon_name
+                                ))
+                                .log();
+                            continue;
                         }
                     }
 
-                    let raw_damage = i32::max(0, stat_to_modifier(stat.strength) + weapon_damage);
-                    let raw_defense =
-                        i32::max(0, stat_to_modifier(stat.dexterity) + armor_defense);
-                    let damage_inflicted = i32::max(0, raw_damage - raw_defense);
+                    /*
+                     * Every blow that lands wears down the weapon that dealt it, along with
+                     * a random piece of the target's armor. Fire eats through armor faster.
+                     */
+                    if let Some(weapon) = weapon {
+                        worn_down.push((weapon, 1));
+                    }
+                    let worn_armor: Vec<Entity> = (&entities, &equipment, &armors)
+                        .join()
+                        .filter(|(item, equipped, _)| equipped.owner == *target && durabilities.contains(*item))
+                        .map(|(item, _, _)| item)
+                        .collect();
+                    if !worn_armor.is_empty() {
+                        let piece = worn_armor[rng.roll_dice(1, worn_armor.len() as i32) as usize - 1];
+                        worn_down.push((piece, if damage_type == DamageType::Fire { FIRE_ARMOR_WEAR } else { 1 }));
+                    }
 
+                    let damage_inflicted = resist_damage(
+                        i32::max(0, target_damage + damage_bonus),
+                        damage_type,
+                        derived_stats.get(*target),
+                    );
                     if damage_inflicted == 0 {
                         Logger::new()
                             .append(format!(
@@ -153,19 +379,28 @@ impl<'a> System<'a> for MeleeCombatSystem {
                     Damage::new_damage(
                         &mut damages,
                         Some(attacker_entity),
-                        attack.target,
+                        *target,
                         damage_inflicted,
                     );
+                    if let Some(ammo) = ammo {
+                        if let Some(status) = ammo.status {
+                            Statuses::apply(&mut statuses, *target, status, ammo.status_duration);
+                        }
+                    }
 
                     /*
                      * Create combat particle representing an attack animation.
+                     * Area attacks have already been animated above.
                      */
-                    if let Some(pos) = positions.get(attack.target) {
+                    if area.is_some() {
+                        continue;
+                    }
+                    if let Some(pos) = positions.get(*target) {
                         match attack.attack_type {
                             AttackType::Melee => {
                                 entities
                                     .build_entity()
-                                    .with(pos.clone(), &mut positions)
+                                    .with(*pos, &mut positions)
                                     .with(
                                         Renderable {
                                             glyph: '\\',
@@ -192,107 +427,26 @@ impl<'a> System<'a> for MeleeCombatSystem {
                                     .get(attacker_entity)
                                     .expect("Unable to access ranged attacker position");
                                 let target_pos = positions
-                                    .get(attack.target)
+                                    .get(*target)
                                     .expect("Unable to access ranged target position");
-                                let line_points = line2d(
-                                    rltk::LineAlg::Bresenham,
-                                    Point {
-                                        x: attacker_pos.x,
-                                        y: attacker_pos.y,
-                                    },
-                                    Point {
-                                        x: target_pos.x,
-                                        y: target_pos.y,
-                                    },
+                                let (from, to) = (
+                                    Point::new(attacker_pos.x, attacker_pos.y),
+                                    Point::new(target_pos.x, target_pos.y),
                                 );
-                                let mut prev_point: Option<Point> = None;
-                                for point in line_points.iter() {
-                                    let glyph = prev_point
-                                        .map(|prev| {
-                                            generate_directional_ranged_attack_glyph(prev, *point)
-                                        })
-                                        .unwrap_or('-');
-                                    entities
-                                        .build_entity()
-                                        .with(
-                                            Position {
-                                                x: point.x,
-                                                y: point.y,
-                                            },
-                                            &mut positions,
-                                        )
-                                        .with(
-                                            Renderable {
-                                                glyph: glyph,
-                                                fg: Color::White,
-                                                bg: Color::default(),
-                                                index: 0,
-                                            },
-                                            &mut renderables,
-                                        )
-                                        .with(
-                                            Lifetime {
-                                                created_at: SystemTime::now()
-                                                    .duration_since(UNIX_EPOCH)
-                                                    .expect("uhhhh")
-                                                    .as_millis(),
-                                                lifetime_ms: 100,
-                                            },
-                                            &mut lifetimes,
-                                        )
-                                        .build();
-                                    prev_point = Some(*point);
-                                }
+                                spawn_ranged_trail(&entities, &mut positions, &mut renderables, &mut lifetimes, from, to);
                             }
                             AttackType::Magic => {
                                 let attacker_pos = positions
                                     .get(attacker_entity)
                                     .expect("Unable to access magic attacker position");
                                 let target_pos = positions
-                                    .get(attack.target)
+                                    .get(*target)
                                     .expect("Unable to access magic target position");
-                                let line_points = line2d(
-                                    rltk::LineAlg::Bresenham,
-                                    Point {
-                                        x: attacker_pos.x,
-                                        y: attacker_pos.y,
-                                    },
-                                    Point {
-                                        x: target_pos.x,
-                                        y: target_pos.y,
-                                    },
+                                let (from, to) = (
+                                    Point::new(attacker_pos.x, attacker_pos.y),
+                                    Point::new(target_pos.x, target_pos.y),
                                 );
-                                for point in line_points.iter() {
-                                    entities
-                                        .build_entity()
-                                        .with(
-                                            Position {
-                                                x: point.x,
-                                                y: point.y,
-                                            },
-                                            &mut positions,
-                                        )
-                                        .with(
-                                            Renderable {
-                                                glyph: '*',
-                                                fg: Color::White,
-                                                bg: Color::default(),
-                                                index: 0,
-                                            },
-                                            &mut renderables,
-                                        )
-                                        .with(
-                                            Lifetime {
-                                                created_at: SystemTime::now()
-                                                    .duration_since(UNIX_EPOCH)
-                                                    .expect("uhhhh")
-                                                    .as_millis(),
-                                                lifetime_ms: 100,
-                                            },
-                                            &mut lifetimes,
-                                        )
-                                        .build();
-                                }
+                                spawn_bolt(&entities, &mut positions, &mut renderables, &mut lifetimes, from, to);
                             }
                         }
                     }
@@ -301,6 +455,86 @@ impl<'a> System<'a> for MeleeCombatSystem {
         }
         attacks.clear();
 
+        /*
+         * Gear that runs out of durability breaks, but stays equipped until it is repaired or replaced.
+         */
+        for (item, amount) in worn_down.iter() {
+            let Some(durability) = durabilities.get_mut(*item) else {
+                continue;
+            };
+            if durability.wear(*amount) {
+                let owner_name = equipment
+                    .get(*item)
+                    .and_then(|equipped| names.get(equipped.owner))
+                    .map(|n| n.name.clone())
+                    .unwrap_or_default();
+                let item_name = names.get(*item).map(|n| n.name.clone()).unwrap_or_default();
+                Logger::new()
+                    .append(format!("{}'s ", owner_name))
+                    .append_with_color(Color::Red, item_name)
+                    .append(" breaks!")
+                    .log();
+            }
+        }
+
+        /*
+         * Spent ammunition leaves the quiver, and the next piece of the same
+         * stack takes its place. Some of it survives the shot and lands near
+         * where it was aimed, ready to be picked back up.
+         */
+        for (attacker_entity, ammo_entity, aim) in spent_ammo.iter() {
+            let ammo_name = names.get(*ammo_entity).map(|n| n.name.clone()).unwrap_or_default();
+            let recovery_chance = ammunition.get(*ammo_entity).map(|a| a.recovery_chance).unwrap_or(0);
+            equipment.remove(*ammo_entity);
+            backpacks.remove(*ammo_entity);
+            if let Some(inventory) = inventories.get_mut(*attacker_entity) {
+                if let Some(stack) = inventory.stack_of(*ammo_entity) {
+                    let next = stack.iter().find(|item| *item != ammo_entity).copied();
+                    inventory.remove_item(*ammo_entity);
+                    match next {
+                        Some(next) => {
+                            equipment
+                                .insert(next, Equipped { slot: EquipmentSlot::Quiver, owner: *attacker_entity })
+                                .expect("Unable to restock quiver");
+                        }
+                        None => {
+                            let attacker_name = names.get(*attacker_entity).map(|n| n.name.clone()).unwrap_or_default();
+                            Logger::new()
+                                .append(format!("{} fires the last {}.", attacker_name, ammo_name))
+                                .log();
+                        }
+                    }
+                }
+            }
+
+            let landing = aim.and_then(|aim| {
+                if rng.roll_dice(1, 100) > recovery_chance {
+                    return None;
+                }
+                let tiles: Vec<Point> = (-1..=1)
+                    .flat_map(|dx| (-1..=1).map(move |dy| Point::new(aim.x + dx, aim.y + dy)))
+                    .filter(|point| {
+                        point.x >= 0 && point.x < map.width && point.y >= 0 && point.y < map.height
+                    })
+                    .filter(|point| map.tiles[map.xy_idx(point.x, point.y)] == TileType::Floor)
+                    .collect();
+                if tiles.is_empty() {
+                    return None;
+                }
+                Some(tiles[rng.range(0, tiles.len() as i32) as usize])
+            });
+            match landing {
+                Some(point) => {
+                    positions
+                        .insert(*ammo_entity, Position { x: point.x, y: point.y })
+                        .expect("Unable to drop spent ammunition");
+                }
+                None => {
+                    entities.delete(*ammo_entity).expect("Unable to delete spent ammunition");
+                }
+            }
+        }
+
         /*
          * Commit the mana burndown to ecs.
          */
@@ -311,8 +545,96 @@ impl<'a> System<'a> for MeleeCombatSystem {
     }
 }
 
-pub fn stat_to_modifier(stat: i32) -> i32 {
-    return (stat - 10) / 2;
+/// Spawns a short lived trail of directional particles from one point to another,
+/// e.g. an arrow in flight.
+pub fn spawn_ranged_trail(
+    entities: &Entities,
+    positions: &mut WriteStorage<Position>,
+    renderables: &mut WriteStorage<Renderable>,
+    lifetimes: &mut WriteStorage<Lifetime>,
+    from: Point,
+    to: Point,
+) {
+    let line_points = line2d(rltk::LineAlg::Bresenham, from, to);
+    let mut prev_point: Option<Point> = None;
+    for point in line_points.iter() {
+        let glyph = prev_point
+            .map(|prev| generate_directional_ranged_attack_glyph(prev, *point))
+            .unwrap_or('-');
+        entities
+            .build_entity()
+            .with(
+                Position {
+                    x: point.x,
+                    y: point.y,
+                },
+                positions,
+            )
+            .with(
+                Renderable {
+                    glyph: glyph,
+                    fg: Color::White,
+                    bg: Color::default(),
+                    index: 0,
+                },
+                renderables,
+            )
+            .with(
+                Lifetime {
+                    created_at: SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .expect("uhhhh")
+                        .as_millis(),
+                    lifetime_ms: 100,
+                },
+                lifetimes,
+            )
+            .build();
+        prev_point = Some(*point);
+    }
+}
+
+/// Spawns a short lived trail of magic particles from one point to another.
+fn spawn_bolt(
+    entities: &Entities,
+    positions: &mut WriteStorage<Position>,
+    renderables: &mut WriteStorage<Renderable>,
+    lifetimes: &mut WriteStorage<Lifetime>,
+    from: Point,
+    to: Point,
+) {
+    let line_points = line2d(rltk::LineAlg::Bresenham, from, to);
+    for point in line_points.iter() {
+        entities
+            .build_entity()
+            .with(
+                Position {
+                    x: point.x,
+                    y: point.y,
+                },
+                positions,
+            )
+            .with(
+                Renderable {
+                    glyph: '*',
+                    fg: Color::White,
+                    bg: Color::default(),
+                    index: 0,
+                },
+                renderables,
+            )
+            .with(
+                Lifetime {
+                    created_at: SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .expect("uhhhh")
+                        .as_millis(),
+                    lifetime_ms: 100,
+                },
+                lifetimes,
+            )
+            .build();
+    }
 }
 
 fn generate_directional_ranged_attack_glyph(previous: Point, current: Point) -> char {
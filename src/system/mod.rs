@@ -1,4 +1,6 @@
+pub mod alertness_system;
 pub mod damage_system;
+pub mod derived_stats_system;
 pub mod experience_system;
 pub mod inventory_system;
 pub mod map_indexing_system;
@@ -6,5 +8,8 @@ pub mod melee_combat_system;
 pub mod monster_system;
 pub mod particle_system;
 pub mod ranged_combat_system;
+pub mod regeneration_system;
+pub mod status_system;
+pub mod throw_system;
 pub mod trigger_system;
 pub mod visibility_system;
\ No newline at end of file
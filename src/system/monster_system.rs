@@ -1,7 +1,11 @@
 use rltk::{Point};
 use specs::prelude::*;
 
-use crate::{Attack, Monster, Position, RunState, Viewshed, component::AttackType, generate::map::Map};
+use crate::{
+    Attack, Monster, Position, RunState, Viewshed,
+    component::{AlertState, Alertness, AttackType},
+    generate::map::Map,
+};
 
 pub struct MonsterSystem {
 
@@ -13,6 +17,7 @@ impl<'a> System<'a> for MonsterSystem {
         ReadStorage<'a, Viewshed>,
         WriteStorage<'a, Position>,
         ReadStorage<'a, Monster>,
+        ReadStorage<'a, Alertness>,
         WriteStorage<'a, Attack>,
         ReadExpect<'a, Point>,
         ReadExpect<'a, Entity>,
@@ -26,6 +31,7 @@ impl<'a> System<'a> for MonsterSystem {
             viewshed,
             mut position,
             monster,
+            alertness,
             mut attack,
             player_position,
             player_entity,
@@ -44,6 +50,13 @@ impl<'a> System<'a> for MonsterSystem {
         map.populate_blocked();
 
         for (entity, viewshed, position, _monster) in (&entities, &viewshed, &mut position, &monster).join() {
+            // Sleeping and unaware monsters don't react to the player at all
+            if let Some(alert) = alertness.get(entity) {
+                if alert.state != AlertState::Alert {
+                    continue;
+                }
+            }
+
             if viewshed.visible_tiles.contains(&*player_position) {
                 let path = rltk::a_star_search(
                     map.xy_idx(position.x, position.y),
@@ -60,7 +73,8 @@ impl<'a> System<'a> for MonsterSystem {
                 if distance < 1.5 {
                     attack.insert(entity, Attack {
                         attack_type: AttackType::Melee,
-                        target: *player_entity,
+                        target: Some(*player_entity),
+                        aim: None,
                         spell: None,
                     }).expect("Unable to add monster attack");
                 }
@@ -1,8 +1,13 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use ratatui::style::Color;
+use rltk::Point;
 use specs::prelude::*;
 
-use crate::component::Lifetime;
+use crate::{
+    component::{DamageType, Lifetime, Position, Renderable},
+    generate::map::Map,
+};
 
 pub struct ParticleSystem {
 
@@ -37,3 +42,58 @@ impl<'a> System<'a> for ParticleSystem {
         });
     }
 }
+
+/// The storages every particle is built from, borrowed together from whichever
+/// system or effect is spawning them.
+pub struct ParticleStorages<'s, 'a> {
+    pub positions: &'s mut WriteStorage<'a, Position>,
+    pub renderables: &'s mut WriteStorage<'a, Renderable>,
+    pub lifetimes: &'s mut WriteStorage<'a, Lifetime>,
+}
+
+/// Spawns explosion particles over every tile of an area of effect.
+///
+/// Tiles closer to the center of the blast linger for longer, so the
+/// explosion appears to collapse inwards as the particles expire.
+pub fn spawn_explosion(
+    entities: &Entities,
+    particles: ParticleStorages,
+    map: &Map,
+    center: Point,
+    tiles: &[usize],
+    damage_type: DamageType,
+) {
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("uhhhh")
+        .as_millis();
+    for tile in tiles.iter() {
+        let (x, y) = map.idx_xy(*tile);
+        let distance = rltk::DistanceAlg::Pythagoras.distance2d(center, Point::new(x, y));
+        let (fg, bg) = match damage_type {
+            DamageType::Fire if distance < 1.5 => (Color::Yellow, Color::Red),
+            DamageType::Fire => (Color::LightRed, Color::Rgb(100, 0, 0)),
+            _ => (Color::White, Color::Gray),
+        };
+        entities
+            .build_entity()
+            .with(Position { x, y }, particles.positions)
+            .with(
+                Renderable {
+                    glyph: '*',
+                    fg: fg,
+                    bg: bg,
+                    index: 0,
+                },
+                particles.renderables,
+            )
+            .with(
+                Lifetime {
+                    created_at: created_at,
+                    lifetime_ms: 350 - u128::min(250, (distance * 50.0) as u128),
+                },
+                particles.lifetimes,
+            )
+            .build();
+    }
+}
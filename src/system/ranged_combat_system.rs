@@ -2,7 +2,7 @@ use rltk::{Point, line2d};
 use specs::prelude::*;
 
 use crate::{
-    component::{Equipped, Monster, Name, Position, RangedWeapon},
+    component::{Area, AreaShape, Equipped, Monster, Name, Position, RangedWeapon},
     generate::map::{Map, TileType},
 };
 
@@ -111,3 +111,153 @@ pub fn has_line_of_sight(map: &Map, from: Point, to: Point) -> bool {
             )
         });
 }
+
+/// Returns every tile index affected by an area of effect cast from `origin` towards `aim`.
+///
+/// Walls stop the area from spreading, so only tiles with a clear line from the
+/// center of the area (the aim point for balls, the caster otherwise) are included.
+pub fn get_area_tiles(map: &Map, origin: Point, aim: Point, area: &Area) -> Vec<usize> {
+    let is_open = |point: &Point| {
+        point.x >= 0
+            && point.y >= 0
+            && point.x < map.width
+            && point.y < map.height
+            && matches!(
+                map.tiles[map.xy_idx(point.x, point.y)],
+                TileType::Floor | TileType::DownStairs | TileType::UpStairs
+            )
+    };
+
+    let mut area_tiles = Vec::new();
+    match area.shape {
+        AreaShape::Ball => {
+            for y in (aim.y - area.size)..=(aim.y + area.size) {
+                for x in (aim.x - area.size)..=(aim.x + area.size) {
+                    let point = Point::new(x, y);
+                    if !is_open(&point) {
+                        continue;
+                    }
+                    let distance = rltk::DistanceAlg::Pythagoras.distance2d(aim, point);
+                    if distance <= area.size as f32 && has_line_of_sight(map, aim, point) {
+                        area_tiles.push(map.xy_idx(x, y));
+                    }
+                }
+            }
+        }
+        AreaShape::Line => {
+            if origin == aim {
+                return area_tiles;
+            }
+            // Extend the line past the aim point so that it always reaches its full length
+            let (dx, dy) = ((aim.x - origin.x) as f32, (aim.y - origin.y) as f32);
+            let scale = area.size as f32 / f32::sqrt(dx * dx + dy * dy);
+            let end = Point::new(
+                origin.x + (dx * scale).round() as i32,
+                origin.y + (dy * scale).round() as i32,
+            );
+            for point in line2d(rltk::LineAlg::Bresenham, origin, end).iter().skip(1) {
+                if !is_open(point) {
+                    break;
+                }
+                area_tiles.push(map.xy_idx(point.x, point.y));
+            }
+        }
+        AreaShape::Cone => {
+            if origin == aim {
+                return area_tiles;
+            }
+            let direction = f32::atan2((aim.y - origin.y) as f32, (aim.x - origin.x) as f32);
+            for y in (origin.y - area.size)..=(origin.y + area.size) {
+                for x in (origin.x - area.size)..=(origin.x + area.size) {
+                    let point = Point::new(x, y);
+                    if point == origin || !is_open(&point) {
+                        continue;
+                    }
+                    let distance = rltk::DistanceAlg::Pythagoras.distance2d(origin, point);
+                    if distance > area.size as f32 {
+                        continue;
+                    }
+                    let angle = f32::atan2((y - origin.y) as f32, (x - origin.x) as f32);
+                    let mut difference = (angle - direction).abs();
+                    if difference > std::f32::consts::PI {
+                        difference = 2.0 * std::f32::consts::PI - difference;
+                    }
+                    if difference <= std::f32::consts::FRAC_PI_4 && has_line_of_sight(map, origin, point) {
+                        area_tiles.push(map.xy_idx(x, y));
+                    }
+                }
+            }
+        }
+    }
+    return area_tiles;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An open room of floor with a wall border, `width` by `height` tiles.
+    fn open_map(width: i32, height: i32) -> Map {
+        let mut map = Map { width, height, tiles: vec![TileType::Floor; (width * height) as usize], ..Default::default() };
+        for x in 0..width {
+            for y in 0..height {
+                if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+                    let index = map.xy_idx(x, y);
+                    map.tiles[index] = TileType::Wall;
+                }
+            }
+        }
+        return map;
+    }
+
+    fn set_wall(map: &mut Map, x: i32, y: i32) {
+        let index = map.xy_idx(x, y);
+        map.tiles[index] = TileType::Wall;
+    }
+
+    #[test]
+    fn ball_covers_a_circle_around_the_aim_point() {
+        let map = open_map(15, 15);
+        let tiles = get_area_tiles(&map, Point::new(2, 2), Point::new(7, 7), &Area { shape: AreaShape::Ball, size: 1 });
+        assert_eq!(tiles.len(), 5);
+        for (x, y) in [(7, 7), (6, 7), (8, 7), (7, 6), (7, 8)] {
+            assert!(tiles.contains(&map.xy_idx(x, y)));
+        }
+    }
+
+    #[test]
+    fn ball_does_not_spread_through_walls() {
+        let mut map = open_map(15, 15);
+        for y in 1..14 {
+            set_wall(&mut map, 9, y);
+        }
+        let tiles = get_area_tiles(&map, Point::new(2, 2), Point::new(7, 7), &Area { shape: AreaShape::Ball, size: 3 });
+        assert!(tiles.contains(&map.xy_idx(8, 7)));
+        assert!(!tiles.contains(&map.xy_idx(9, 7)));
+        assert!(!tiles.contains(&map.xy_idx(10, 7)));
+    }
+
+    #[test]
+    fn line_reaches_its_full_length_past_the_aim_point() {
+        let map = open_map(15, 15);
+        let tiles = get_area_tiles(&map, Point::new(2, 7), Point::new(4, 7), &Area { shape: AreaShape::Line, size: 5 });
+        let expected: Vec<usize> = (3..=7).map(|x| map.xy_idx(x, 7)).collect();
+        assert_eq!(tiles, expected);
+    }
+
+    #[test]
+    fn line_stops_at_the_first_wall() {
+        let mut map = open_map(15, 15);
+        set_wall(&mut map, 5, 7);
+        let tiles = get_area_tiles(&map, Point::new(2, 7), Point::new(4, 7), &Area { shape: AreaShape::Line, size: 8 });
+        let expected: Vec<usize> = (3..=4).map(|x| map.xy_idx(x, 7)).collect();
+        assert_eq!(tiles, expected);
+    }
+
+    #[test]
+    fn line_aimed_at_the_caster_is_empty() {
+        let map = open_map(15, 15);
+        let tiles = get_area_tiles(&map, Point::new(7, 7), Point::new(7, 7), &Area { shape: AreaShape::Line, size: 5 });
+        assert!(tiles.is_empty());
+    }
+}
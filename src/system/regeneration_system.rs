@@ -0,0 +1,63 @@
+use specs::prelude::*;
+
+use crate::{
+    RunState,
+    component::{DerivedStats, Stats},
+    system::derived_stats_system::{effective_max_hp, stat_to_modifier},
+};
+
+/// Number of turns between natural regeneration ticks for an unremarkable (10) attribute.
+pub const BASE_REGENERATION_INTERVAL: u32 = 6;
+pub const MIN_REGENERATION_INTERVAL: u32 = 1;
+
+pub struct RegenerationSystem {
+    pub turn: u32,
+}
+
+/**
+ * Slowly restores hp and mp once per monster turn, i.e. once per full round.
+ *
+ * Constitution shortens the interval between hp ticks and wisdom does the same for mp,
+ * so a hardy character recovers noticeably faster between fights. Equipment with an
+ * `mp_regeneration` modifier shortens the mp interval further. Entities that are
+ * already dead are left alone so that they can be cleaned up as usual.
+ */
+impl<'a> System<'a> for RegenerationSystem {
+    type SystemData = (
+        ReadExpect<'a, RunState>,
+        WriteStorage<'a, Stats>,
+        ReadStorage<'a, DerivedStats>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (runstate, mut stats, derived_stats) = data;
+
+        match *runstate {
+            RunState::MonsterTurn => {}
+            _ => return,
+        }
+
+        self.turn = self.turn.wrapping_add(1);
+        for (stat, derived) in (&mut stats, derived_stats.maybe()).join() {
+            if stat.hp.current <= 0 {
+                continue;
+            }
+            let (constitution, wisdom, mp_bonus) = match derived {
+                Some(derived) => (derived.constitution, derived.wisdom, derived.equipment.mp_regeneration),
+                None => (stat.constitution, stat.wisdom, 0),
+            };
+            if self.turn.is_multiple_of(regeneration_interval(constitution, 0)) {
+                stat.hp.current = i32::min(effective_max_hp(stat, derived), stat.hp.current + 1);
+            }
+            if self.turn.is_multiple_of(regeneration_interval(wisdom, mp_bonus)) {
+                stat.mp.current = i32::min(stat.mp.max, stat.mp.current + 1);
+            }
+        }
+    }
+}
+
+/// Each point of attribute modifier, or of bonus, shaves a turn off the regeneration interval.
+fn regeneration_interval(attribute: i32, bonus: i32) -> u32 {
+    let interval = BASE_REGENERATION_INTERVAL as i32 - stat_to_modifier(attribute) - bonus;
+    u32::max(MIN_REGENERATION_INTERVAL, interval.max(0) as u32)
+}
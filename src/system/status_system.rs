@@ -0,0 +1,77 @@
+use ratatui::style::Color;
+use specs::prelude::*;
+
+use crate::{
+    RunState,
+    component::{Damage, DerivedStats, Name, Stats, StatusType, Statuses},
+    logbook::logbook::Logger,
+    system::derived_stats_system::effective_max_hp,
+};
+
+pub const POISON_DAMAGE: i32 = 1;
+pub const REGENERATION_AMOUNT: i32 = 1;
+
+pub struct StatusSystem {}
+
+/**
+ * Ticks down any lingering statuses once per monster turn, i.e. once per full round.
+ *
+ * Poison deals damage through the regular `Damage` pipeline so that it can be fatal,
+ * while regeneration heals directly. Expired statuses are removed, along with the
+ * `Statuses` component itself once nothing is left.
+ */
+impl<'a> System<'a> for StatusSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, RunState>,
+        ReadExpect<'a, Entity>,
+        ReadStorage<'a, Name>,
+        WriteStorage<'a, Stats>,
+        ReadStorage<'a, DerivedStats>,
+        WriteStorage<'a, Statuses>,
+        WriteStorage<'a, Damage>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, runstate, player_entity, names, mut stats, derived_stats, mut statuses, mut damage) = data;
+
+        match *runstate {
+            RunState::MonsterTurn => {}
+            _ => return,
+        }
+
+        let mut cured: Vec<Entity> = Vec::new();
+        for (entity, status_list, stat, name) in (&entities, &mut statuses, &mut stats, &names).join() {
+            for status in status_list.statuses.iter_mut() {
+                match status.status_type {
+                    StatusType::Poisoned => {
+                        Damage::new_damage(&mut damage, None, entity, POISON_DAMAGE);
+                    }
+                    StatusType::Regenerating => {
+                        let max_hp = effective_max_hp(stat, derived_stats.get(entity));
+                        stat.hp.current = i32::min(max_hp, stat.hp.current + REGENERATION_AMOUNT);
+                    }
+                }
+                status.duration -= 1;
+
+                if status.duration <= 0 && entity == *player_entity {
+                    Logger::new()
+                        .append_with_color(Color::Green, format!("{} ", name.name))
+                        .append(match status.status_type {
+                            StatusType::Poisoned => "is no longer poisoned.",
+                            StatusType::Regenerating => "is no longer regenerating.",
+                        })
+                        .log();
+                }
+            }
+            status_list.statuses.retain(|status| status.duration > 0);
+            if status_list.statuses.is_empty() {
+                cured.push(entity);
+            }
+        }
+
+        for entity in cured {
+            statuses.remove(entity);
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use ratatui::style::Color;
+use rltk::{Point, RandomNumberGenerator};
+use specs::prelude::*;
+
+use crate::{
+    Damage, Name, Stats,
+    component::{
+        AlertState, Alertness, Area, AreaShape, Consumable, DamageType, DerivedStats, Equipped,
+        Fragile, InBackpack, Inventory, Item, Lifetime, Noise, Position, Renderable, Throwable,
+        Throwing, WantsToThrowItem,
+    },
+    effect::effect::{Effect, EffectType, create_effect},
+    generate::{identification::IdentificationTable, map::Map},
+    logbook::logbook::Logger,
+    system::{
+        alertness_system::RANGED_NOISE,
+        derived_stats_system::{BASE_ARMOR_CLASS, resist_damage, stat_to_modifier},
+        melee_combat_system::spawn_ranged_trail,
+        particle_system::{ParticleStorages, spawn_explosion},
+        ranged_combat_system::get_area_tiles,
+    },
+};
+
+/// Anything can be thrown this far, give or take the thrower's strength.
+pub const THROW_BASE_RANGE: i32 = 5;
+pub const THROW_MAX_RANGE: i32 = 10;
+/// Every this many pounds of weight shortens a throw by a tile.
+pub const THROW_WEIGHT_PER_TILE: f32 = 2.0;
+
+/// How far the thrower can throw the given item, based on their strength
+/// and the weight of the item. Always at least a single tile.
+pub fn throw_range(ecs: &World, thrower: Entity, item: Entity) -> i32 {
+    let strength = ecs.read_storage::<DerivedStats>().get(thrower).map(|d| d.strength).unwrap_or(10);
+    let weight = ecs.read_storage::<Item>().get(item).map(|i| i.weight).unwrap_or(0.0);
+    let range = THROW_BASE_RANGE + stat_to_modifier(strength) - (weight / THROW_WEIGHT_PER_TILE) as i32;
+    return range.clamp(1, THROW_MAX_RANGE);
+}
+
+pub struct ThrowSystem {}
+
+impl<'a> System<'a> for ThrowSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteExpect<'a, RandomNumberGenerator>,
+        ReadExpect<'a, Map>,
+        ReadExpect<'a, IdentificationTable>,
+        WriteStorage<'a, WantsToThrowItem>,
+        WriteStorage<'a, Throwing>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Stats>,
+        ReadStorage<'a, DerivedStats>,
+        ReadStorage<'a, Throwable>,
+        ReadStorage<'a, Fragile>,
+        ReadStorage<'a, Consumable>,
+        WriteStorage<'a, Inventory>,
+        WriteStorage<'a, InBackpack>,
+        WriteStorage<'a, Equipped>,
+        WriteStorage<'a, Damage>,
+        WriteStorage<'a, Alertness>,
+        WriteStorage<'a, Noise>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Renderable>,
+        WriteStorage<'a, Lifetime>,
+    );
+
+    /*
+     * Thrown items leave the thrower's inventory and fly towards the target tile.
+     *
+     * Throwing weapons damage whatever they land on, fragile items (potions)
+     * shatter and splash their effects over a small area, and everything else
+     * simply lands on the target tile where it can be picked back up.
+     */
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut rng,
+            map,
+            identification,
+            mut wants_throw,
+            mut throwing,
+            names,
+            stats,
+            derived_stats,
+            throwables,
+            fragiles,
+            consumables,
+            mut inventories,
+            mut backpacks,
+            mut equipment,
+            mut damages,
+            mut alertness,
+            mut noises,
+            mut positions,
+            mut renderables,
+            mut lifetimes,
+        ) = data;
+
+        for (thrower, throw) in (&entities, &wants_throw).join() {
+            throwing.remove(thrower);
+
+            /*
+             * The item may have been dropped, sold or destroyed since the throw was readied.
+             */
+            if !entities.is_alive(throw.item)
+                || backpacks.get(throw.item).is_none_or(|backpack| backpack.owner != thrower)
+            {
+                continue;
+            }
+            let Some(origin) = positions.get(thrower).map(|p| Point::new(p.x, p.y)) else {
+                continue;
+            };
+            let thrower_name = names.get(thrower).map(|n| n.name.clone()).unwrap_or_default();
+            let item_name = names
+                .get(throw.item)
+                .map(|n| identification.display_name(&n.name))
+                .unwrap_or("???".to_string());
+
+            /*
+             * Pull the item out of the thrower's pack, shrinking its stack.
+             */
+            backpacks.remove(throw.item);
+            equipment.remove(throw.item);
+            if let Some(inventory) = inventories.get_mut(thrower) {
+                inventory.remove_item(throw.item);
+            }
+
+            Noise::new(&mut noises, thrower, RANGED_NOISE);
+            spawn_ranged_trail(&entities, &mut positions, &mut renderables, &mut lifetimes, origin, throw.target);
+            Logger::new()
+                .append(format!("{} throws the ", thrower_name))
+                .append_with_color(Color::LightBlue, format!("{}.", item_name))
+                .log();
+
+            let target_index = map.xy_idx(throw.target.x, throw.target.y);
+            let victim = map.tile_content[target_index]
+                .iter()
+                .find(|entity| **entity != thrower && stats.contains(**entity))
+                .copied();
+
+            /*
+             * Fragile items shatter on impact, splashing every creature caught in the area
+             * with whichever of their effects can act on a creature.
+             */
+            if let Some(fragile) = fragiles.get(throw.item) {
+                let area = Area { shape: AreaShape::Ball, size: fragile.radius };
+                let area_tiles = get_area_tiles(&map, throw.target, throw.target, &area);
+                spawn_explosion(
+                    &entities,
+                    ParticleStorages {
+                        positions: &mut positions,
+                        renderables: &mut renderables,
+                        lifetimes: &mut lifetimes,
+                    },
+                    &map,
+                    throw.target,
+                    &area_tiles,
+                    DamageType::Bludgeoning,
+                );
+                Logger::new()
+                    .append(format!("The {} shatters!", item_name))
+                    .log();
+                let splashed: Vec<Entity> = area_tiles
+                    .iter()
+                    .flat_map(|index| map.tile_content[*index].iter())
+                    .filter(|entity| stats.contains(**entity))
+                    .copied()
+                    .collect();
+                if let Some(consumable) = consumables.get(throw.item) {
+                    for target in splashed.iter() {
+                        for effect in consumable.effects.iter().filter(|effect| effect.affects_target()) {
+                            create_effect(Effect {
+                                effect_type: EffectType::ItemUse {
+                                    effect: effect.clone(),
+                                    user: *target,
+                                    target: None,
+                                },
+                                _creator: Some(thrower),
+                            });
+                        }
+                    }
+                }
+                entities.delete(throw.item).expect("Unable to delete shattered item");
+                continue;
+            }
+
+            /*
+             * Throwing weapons roll to hit just like any other ranged attack,
+             * with the thrower's strength behind the blow.
+             */
+            if let (Some(throwable), Some(victim)) = (throwables.get(throw.item), victim) {
+                let victim_name = names.get(victim).map(|n| n.name.clone()).unwrap_or_default();
+                if let Some(victim_alertness) = alertness.get_mut(victim) {
+                    victim_alertness.state = AlertState::Alert;
+                }
+                let to_hit = derived_stats.get(thrower).map(|d| d.ranged_to_hit).unwrap_or(0);
+                let armor_class = derived_stats.get(victim).map(|d| d.armor_class).unwrap_or(BASE_ARMOR_CLASS);
+                let roll = rng.roll_dice(1, 20);
+                if roll == 1 || (roll != 20 && roll + to_hit < armor_class) {
+                    Logger::new()
+                        .append(format!("The {} misses {}.", item_name, victim_name))
+                        .log();
+                } else {
+                    let strength = derived_stats.get(thrower).map(|d| d.strength).unwrap_or(10);
+                    let damage = rng.roll_dice(throwable.damage.dice_count, throwable.damage.dice_sides)
+                        + throwable.damage.modifier
+                        + stat_to_modifier(strength);
+                    let damage_inflicted =
+                        resist_damage(i32::max(1, damage), throwable.damage_type, derived_stats.get(victim));
+                    Logger::new()
+                        .append(format!(
+                            "The {} hits {}, inflicting {} {:?} damage",
+                            item_name, victim_name, damage_inflicted, throwable.damage_type
+                        ))
+                        .log();
+                    Damage::new_damage(&mut damages, Some(thrower), victim, damage_inflicted);
+                }
+            }
+
+            positions
+                .insert(throw.item, Position { x: throw.target.x, y: throw.target.y })
+                .expect("Unable to land thrown item");
+        }
+        wants_throw.clear();
+    }
+}
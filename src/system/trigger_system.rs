@@ -3,7 +3,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use ratatui::style::Color;
 use specs::prelude::*;
 
-use crate::{component::{Damage, Hidden, Lifetime, Name, Position, Renderable, Stats, Triggerable}, generate::map::Map, logbook::logbook::Logger};
+use crate::{component::{Damage, Hidden, Lifetime, Name, Noise, Position, Renderable, Stats, Triggerable}, generate::map::Map, logbook::logbook::Logger, system::alertness_system::TRAP_NOISE};
 pub struct TriggerSystem {
 
 }
@@ -20,6 +20,7 @@ impl<'a> System<'a> for TriggerSystem {
         WriteStorage<'a, Damage>,
         WriteStorage<'a, Renderable>,
         WriteStorage<'a, Lifetime>,
+        WriteStorage<'a, Noise>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -34,6 +35,7 @@ impl<'a> System<'a> for TriggerSystem {
             mut damages,
             mut renderables,
             mut lifetimes,
+            mut noises,
         ) = data;
 
         let mut particles_to_create: Vec<Position> = Vec::new();
@@ -57,6 +59,7 @@ impl<'a> System<'a> for TriggerSystem {
                         .append(format!("{} damage!", trigger.damage))
                         .log();
                     Damage::new_damage(&mut damages, None, entity, trigger.damage);
+                    Noise::new(&mut noises, entity, TRAP_NOISE);
                     hidden.remove(*colocated_entity);
                     particles_to_create.push(position.clone());
                     entities_to_remove.push(*colocated_entity);
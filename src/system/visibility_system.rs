@@ -4,11 +4,15 @@ use specs::prelude::*;
 
 use crate::{
     Player, Position, Viewshed,
-    component::{Hidden, Name},
+    component::{DerivedStats, Hidden, Name},
     generate::{map::Map},
     logbook::logbook::Logger,
 };
 
+/// Hidden entities in view are revealed when a perception check (d20 + perception)
+/// meets this difficulty, rolled whenever the viewshed is refreshed.
+pub const SPOT_DIFFICULTY: i32 = 20;
+
 pub struct VisibilitySystem {}
 
 impl<'a> System<'a> for VisibilitySystem {
@@ -21,6 +25,7 @@ impl<'a> System<'a> for VisibilitySystem {
         WriteStorage<'a, Viewshed>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, Hidden>,
+        ReadStorage<'a, DerivedStats>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -33,11 +38,13 @@ impl<'a> System<'a> for VisibilitySystem {
             mut viewshed,
             position,
             mut hidden,
+            derived_stats,
         ) = data;
 
         for (entity, viewshed, pos) in (&entities, &mut viewshed, &position).join() {
+            let range = derived_stats.get(entity).map(|d| d.sight_range).unwrap_or(viewshed.range);
             viewshed.visible_tiles.clear();
-            viewshed.visible_tiles = field_of_view(Point::new(pos.x, pos.y), viewshed.range, &*map);
+            viewshed.visible_tiles = field_of_view(Point::new(pos.x, pos.y), range, &*map);
             viewshed
                 .visible_tiles
                 .retain(|p| p.x >= 0 && p.x < map.width && p.y >= 0 && p.y < map.height);
@@ -49,6 +56,7 @@ impl<'a> System<'a> for VisibilitySystem {
              */
             match player.get(entity) {
                 Some(_) => {
+                    let perception = derived_stats.get(entity).map(|d| d.perception).unwrap_or(0);
                     for tile in viewshed.visible_tiles.iter() {
                         let index = map.xy_idx(tile.x, tile.y);
                         map.revealed_tiles[index] = true;
@@ -59,7 +67,7 @@ impl<'a> System<'a> for VisibilitySystem {
                          */
                         for tile_entity in map.tile_content[index].iter() {
                             if let Some(_hidden) = hidden.get(*tile_entity) {
-                                if rng.roll_dice(1, 20) == 20 {
+                                if rng.roll_dice(1, 20) + perception >= SPOT_DIFFICULTY {
                                     let name = names
                                         .get(*tile_entity)
                                         .expect("Unable to fetch name for hidden entity");